@@ -0,0 +1,136 @@
+//! Cost of adapting a wide table: planning a file, rewriting its projection,
+//! and evaluating the rewritten projection on a batch.
+
+use std::hint::black_box;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr_adapter::PhysicalExprAdapterFactory;
+use schema_evolution::FieldMapping;
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+
+const COLUMNS: usize = 500;
+const ROWS: usize = 8192;
+
+fn schema(name: impl Fn(usize) -> String, data_type: DataType) -> SchemaRef {
+    Arc::new(Schema::new(
+        (0..COLUMNS)
+            .map(|i| Field::new(name(i), data_type.clone(), true))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+fn batch(schema: &SchemaRef) -> RecordBatch {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match field.data_type() {
+            DataType::Int32 => Arc::new(Int32Array::from_iter_values(0..ROWS as i32)) as ArrayRef,
+            _ => Arc::new(Int64Array::from_iter_values(0..ROWS as i64)),
+        })
+        .collect();
+    RecordBatch::try_new(Arc::clone(schema), columns).unwrap()
+}
+
+/// The table schema, and the file schemas of each case with the factory
+/// reading them.
+fn cases() -> (
+    SchemaRef,
+    Vec<(&'static str, SchemaRef, SchemaEvolutionAdapterFactory)>,
+) {
+    let table = schema(|i| format!("c{i}"), DataType::Int64);
+    let renamed = (0..COLUMNS).fold(FieldMapping::new(), |mapping, i| {
+        mapping.with_rename(format!("old_c{i}"), format!("c{i}"))
+    });
+    let cases = vec![
+        (
+            "identity",
+            Arc::clone(&table),
+            SchemaEvolutionAdapterFactory::new(),
+        ),
+        (
+            "reorder",
+            schema(|i| format!("c{}", COLUMNS - 1 - i), DataType::Int64),
+            SchemaEvolutionAdapterFactory::new(),
+        ),
+        (
+            "rename",
+            schema(|i| format!("old_c{i}"), DataType::Int64),
+            SchemaEvolutionAdapterFactory::new().with_field_mapping(renamed),
+        ),
+        (
+            "widen",
+            schema(|i| format!("c{i}"), DataType::Int32),
+            SchemaEvolutionAdapterFactory::new(),
+        ),
+    ];
+    (table, cases)
+}
+
+fn projection(table: &SchemaRef) -> Vec<Arc<dyn PhysicalExpr>> {
+    table
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| Arc::new(Column::new(field.name(), i)) as Arc<dyn PhysicalExpr>)
+        .collect()
+}
+
+fn bench_plan(c: &mut Criterion) {
+    let (table, cases) = cases();
+    let mut group = c.benchmark_group("plan_file");
+    for (name, file, factory) in &cases {
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| factory.create(Arc::clone(&table), Arc::clone(file)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_rewrite(c: &mut Criterion) {
+    let (table, cases) = cases();
+    let projection = projection(&table);
+    let mut group = c.benchmark_group("rewrite_projection");
+    for (name, file, factory) in &cases {
+        let adapter = factory.create(Arc::clone(&table), Arc::clone(file));
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| {
+                projection
+                    .iter()
+                    .map(|expr| adapter.rewrite(Arc::clone(expr)).unwrap())
+                    .collect::<Vec<_>>()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_evaluate(c: &mut Criterion) {
+    let (table, cases) = cases();
+    let projection = projection(&table);
+    let mut group = c.benchmark_group("evaluate_batch");
+    for (name, file, factory) in &cases {
+        let adapter = factory.create(Arc::clone(&table), Arc::clone(file));
+        let rewritten: Vec<_> = projection
+            .iter()
+            .map(|expr| adapter.rewrite(Arc::clone(expr)).unwrap())
+            .collect();
+        let batch = batch(file);
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| {
+                rewritten
+                    .iter()
+                    .map(|expr| expr.evaluate(black_box(&batch)).unwrap())
+                    .collect::<Vec<_>>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_plan, bench_rewrite, bench_evaluate);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+//! Reading columns that need a cast, against reading the same values already
+//! stored in the table type: the most a cache of cast columns could save.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::policy::CoercionPolicy;
+use schema_evolution::single_file::adapt_file_with;
+
+const ROWS: usize = 1 << 20;
+const BATCH_ROWS: usize = 8192;
+
+fn write_parquet(path: &Path, column: ArrayRef) {
+    let batch = RecordBatch::try_from_iter([("amount", column)]).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    for offset in (0..ROWS).step_by(BATCH_ROWS) {
+        writer.write(&batch.slice(offset, BATCH_ROWS)).unwrap();
+    }
+    writer.close().unwrap();
+}
+
+fn bench_materialize(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let cases: [(&str, ArrayRef); 3] = [
+        (
+            "parse_utf8",
+            Arc::new(StringArray::from_iter_values(
+                (0..ROWS).map(|i| i.to_string()),
+            )),
+        ),
+        (
+            "widen_int32",
+            Arc::new(Int32Array::from_iter_values(0..ROWS as i32)),
+        ),
+        // What a cached, already cast column would be read as
+        (
+            "materialized",
+            Arc::new(Int64Array::from_iter_values(0..ROWS as i64)),
+        ),
+    ];
+    let table: SchemaRef = Arc::new(Schema::new(vec![Field::new(
+        "amount",
+        DataType::Int64,
+        true,
+    )]));
+    let ctx = SessionContext::new();
+    let factory = SchemaEvolutionAdapterFactory::new().with_policy(CoercionPolicy::lenient());
+
+    let mut group = c.benchmark_group("read_column");
+    group.sample_size(20);
+    for (name, column) in cases {
+        let path = dir.path().join(format!("{name}.parquet"));
+        write_parquet(&path, column);
+        let path = path.to_str().unwrap().to_string();
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    let stream = adapt_file_with(&ctx, &path, Arc::clone(&table), factory.clone())
+                        .await
+                        .unwrap();
+                    stream.try_collect::<Vec<_>>().await.unwrap()
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_materialize);
+criterion_main!(benches);
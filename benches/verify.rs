@@ -0,0 +1,79 @@
+//! Verifying a pinned schema against the partitions a query touches, against
+//! verifying the whole table.
+
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use criterion::{Criterion, criterion_group, criterion_main};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use datafusion::prelude::SessionContext;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::discovery::SchemaDiscovery;
+use schema_evolution::verify::SchemaVerifier;
+use tempfile::TempDir;
+
+const MONTHS: usize = 12;
+const DAYS: usize = 28;
+const FILES_PER_DAY: usize = 4;
+
+/// A year of daily partitions, `dt=2024-MM-DD/part-N.parquet`.
+fn table() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let batch = RecordBatch::try_from_iter([
+        ("id", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef),
+        ("code", Arc::new(StringArray::from(vec!["a", "b", "c"]))),
+    ])
+    .unwrap();
+    for month in 1..=MONTHS {
+        for day in 1..=DAYS {
+            let partition = dir.path().join(format!("dt=2024-{month:02}-{day:02}"));
+            std::fs::create_dir_all(&partition).unwrap();
+            for part in 0..FILES_PER_DAY {
+                let file =
+                    std::fs::File::create(partition.join(format!("part-{part}.parquet"))).unwrap();
+                let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+                writer.write(&batch).unwrap();
+                writer.close().unwrap();
+            }
+        }
+    }
+    dir
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = table();
+    let ctx = SessionContext::new();
+    let table_url = ListingTableUrl::parse(dir.path().to_str().unwrap()).unwrap();
+    let options = ListingOptions::new(Arc::new(ParquetFormat::default()));
+    let pinned: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("code", DataType::Utf8, true),
+    ]));
+
+    let mut group = c.benchmark_group("verify");
+    group.sample_size(10);
+    let cases = [
+        ("full_table", SchemaDiscovery::new()),
+        (
+            "one_month",
+            SchemaDiscovery::new().with_paths(glob::Pattern::new("dt=2024-06-*").unwrap()),
+        ),
+    ];
+    for (name, discovery) in cases {
+        let verifier = SchemaVerifier::new().with_discovery(discovery);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                runtime
+                    .block_on(verifier.verify(&ctx.state(), &table_url, &options, &pinned))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify);
+criterion_main!(benches);
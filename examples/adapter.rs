@@ -0,0 +1,166 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::{col, lit};
+use datafusion::{
+    datasource::listing::{ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl},
+    prelude::{SessionConfig, SessionContext},
+};
+use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::format::EvolvingFormat;
+use schema_evolution::merge::merge_schemas;
+use schema_evolution::{CoercionPolicy, ColumnDefault, MissingColumnPolicy};
+
+/// This example queries Parquet files whose schemas drifted over time through
+/// `SchemaEvolutionAdapterFactory`:
+/// - File 1: id is Int32, code is UTF8 and there is no 'value' column
+/// - File 2: id is Int64, code is Int64 and 'value' was added
+///
+/// The table schema is unified from the file footers and every file is adapted to
+/// it at scan time.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let temp_path = temp_dir.path();
+
+    // ============================================================================
+    // Step 1: Create the old Parquet file
+    // ============================================================================
+    let old_schema = Schema::new(vec![
+        Field::new("id", DataType::Int32, false), // Int32 type
+        Field::new("code", DataType::Utf8, false),
+    ]);
+
+    let old_batch = RecordBatch::try_new(
+        Arc::new(old_schema),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["A100", "B200", "C300"])),
+        ],
+    )?;
+
+    write_parquet_file(&temp_path.join("data_old.parquet"), &old_batch)?;
+
+    // ============================================================================
+    // Step 2: Create the new Parquet file (wider id, Int64 code, extra column)
+    // ============================================================================
+    let new_schema = Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("code", DataType::Int64, false), // Int64 type - DIFFERENT!
+        Field::new("value", DataType::Int64, false), // Not in the old file
+    ]);
+
+    let new_batch = RecordBatch::try_new(
+        Arc::new(new_schema),
+        vec![
+            Arc::new(Int64Array::from(vec![4, 5, 6])),
+            Arc::new(Int64Array::from(vec![400, 500, 600])),
+            Arc::new(Int64Array::from(vec![400, 500, 600])),
+        ],
+    )?;
+
+    write_parquet_file(&temp_path.join("data_new.parquet"), &new_batch)?;
+
+    // ============================================================================
+    // Step 3: Unify the file schemas and query both files through the adapter
+    // ============================================================================
+    let ctx = SessionContext::new_with_config(SessionConfig::from_env()?);
+    let listing_options =
+        ListingOptions::new(Arc::new(ParquetFormat::default())).with_collect_stat(true);
+    let table_url = ListingTableUrl::parse(temp_path.to_str().unwrap())?;
+
+    let unified = merge_schemas(&ctx.state(), &table_url, &listing_options).await?;
+    println!("Unified schema: {:?}", unified.schema.fields());
+    println!("{}", unified.report);
+
+    let table_config = ListingTableConfig::new(table_url.clone())
+        .with_listing_options(listing_options)
+        .with_schema(Arc::clone(&unified.schema))
+        .with_expr_adapter_factory(Arc::new(SchemaEvolutionAdapterFactory::new()));
+
+    let listing_table = ListingTable::try_new(table_config.clone())?;
+    ctx.register_table("test_data", Arc::new(listing_table))?;
+
+    ctx.sql("SELECT * FROM test_data ORDER BY id")
+        .await?
+        .show()
+        .await?;
+
+    ctx.sql("SELECT id, code FROM test_data WHERE code = '400' OR value IS NULL ORDER BY id")
+        .await?
+        .show()
+        .await?;
+
+    // ============================================================================
+    // Step 4: Only allow lossless widening; Int64 -> UTF8 'code' is rejected
+    // ============================================================================
+    let widening_config = table_config.clone().with_expr_adapter_factory(Arc::new(
+        SchemaEvolutionAdapterFactory::new().with_policy(CoercionPolicy::widening()),
+    ));
+    ctx.register_table(
+        "widening_data",
+        Arc::new(ListingTable::try_new(widening_config)?),
+    )?;
+
+    let result = ctx
+        .sql("SELECT * FROM widening_data ORDER BY id")
+        .await?
+        .show()
+        .await;
+
+    match result {
+        Ok(_) => println!("Query succeeded unexpectedly"),
+        Err(e) => println!("Schema evolution error occurred:\n{}", e),
+    }
+
+    // ============================================================================
+    // Step 5: Fill missing columns with defaults instead of nulls
+    // ============================================================================
+    // 'loaded_at' is in no file; it defaults to the file modification time, which
+    // is known when reading through `EvolvingFormat`
+    let mut fields = unified.schema.fields().to_vec();
+    fields.push(Arc::new(Field::new(
+        "loaded_at",
+        DataType::Timestamp(TimeUnit::Second, None),
+        false,
+    )));
+    let defaults_config = ListingTableConfig::new(table_url)
+        .with_listing_options(ListingOptions::new(Arc::new(EvolvingFormat::new(
+            Arc::new(ParquetFormat::default()),
+        ))))
+        .with_schema(Arc::new(Schema::new(fields)))
+        .with_expr_adapter_factory(Arc::new(
+            SchemaEvolutionAdapterFactory::new().with_missing_column_policy(
+                MissingColumnPolicy::new()
+                    .with_expr("value", col("id") * lit(100i64))
+                    .with_column("loaded_at", ColumnDefault::FileModified),
+            ),
+        ));
+    ctx.register_table(
+        "defaults_data",
+        Arc::new(ListingTable::try_new(defaults_config)?),
+    )?;
+
+    ctx.sql(
+        "SELECT id, value, loaded_at IS NOT NULL AS has_loaded_at FROM defaults_data ORDER BY id",
+    )
+    .await?
+    .show()
+    .await?;
+
+    Ok(())
+}
+
+/// Helper function to write a RecordBatch to a Parquet file
+fn write_parquet_file(path: &Path, batch: &RecordBatch) -> Result<(), Box<dyn std::error::Error>> {
+    let file = std::fs::File::create(path)?;
+    let props = WriterProperties::builder().build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
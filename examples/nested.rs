@@ -0,0 +1,189 @@
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::json::ReaderBuilder;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::{
+    datasource::listing::{ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl},
+    prelude::{SessionConfig, SessionContext},
+};
+use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::merge::merge_schemas;
+
+/// This example queries Parquet files whose nested columns evolved:
+/// - File 1: payload {id: Int32, meta {tags: List<UTF8>}}, items List<{sku, qty: Int32}>
+///   and config Map<UTF8, {enabled, limit: Int32}>
+/// - File 2: payload {meta {source, tags}, id: Int64} (reordered, 'source' added),
+///   items List<{qty: Int64, sku, price}>, a new attrs Map<UTF8, Int32> and
+///   config Map<UTF8, {limit: Int64, enabled, owner}>
+///
+/// Struct fields are matched by name at every level, including the values of
+/// maps, so old files read the new subfields as null and their leaf types are
+/// widened.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let temp_path = temp_dir.path();
+
+    // ============================================================================
+    // Step 1: Create the old Parquet file
+    // ============================================================================
+    let tags = Field::new_list("tags", Field::new_list_field(DataType::Utf8, true), true);
+    let old_schema = Schema::new(vec![
+        Field::new_struct(
+            "payload",
+            vec![
+                Field::new("id", DataType::Int32, false),
+                Field::new_struct("meta", vec![tags.clone()], true),
+            ],
+            false,
+        ),
+        Field::new_list(
+            "items",
+            Field::new_struct(
+                "item",
+                vec![
+                    Field::new("sku", DataType::Utf8, false),
+                    Field::new("qty", DataType::Int32, false),
+                ],
+                true,
+            ),
+            true,
+        ),
+        Field::new_map(
+            "config",
+            "entries",
+            Field::new("key", DataType::Utf8, false),
+            Field::new_struct(
+                "value",
+                vec![
+                    Field::new("enabled", DataType::Boolean, false),
+                    Field::new("limit", DataType::Int32, true),
+                ],
+                true,
+            ),
+            false,
+            true,
+        ),
+    ]);
+    let old_batch = read_json(
+        old_schema,
+        r#"
+        {"payload": {"id": 1, "meta": {"tags": ["a", "b"]}}, "items": [{"sku": "X1", "qty": 2}], "config": {"default": {"enabled": true, "limit": 10}}}
+        {"payload": {"id": 2, "meta": null}, "items": [], "config": {}}
+        "#,
+    )?;
+    write_parquet_file(&temp_path.join("data_old.parquet"), &old_batch)?;
+
+    // ============================================================================
+    // Step 2: Create the new Parquet file (reordered, added and widened subfields)
+    // ============================================================================
+    let new_schema = Schema::new(vec![
+        Field::new_struct(
+            "payload",
+            vec![
+                Field::new_struct(
+                    "meta",
+                    vec![Field::new("source", DataType::Utf8, true), tags],
+                    true,
+                ),
+                Field::new("id", DataType::Int64, false),
+            ],
+            false,
+        ),
+        Field::new_list(
+            "items",
+            Field::new_struct(
+                "item",
+                vec![
+                    Field::new("qty", DataType::Int64, false),
+                    Field::new("sku", DataType::Utf8, false),
+                    Field::new("price", DataType::Float64, true),
+                ],
+                true,
+            ),
+            true,
+        ),
+        Field::new_map(
+            "attrs",
+            "entries",
+            Field::new("key", DataType::Utf8, false),
+            Field::new("value", DataType::Int32, true),
+            false,
+            true,
+        ),
+        Field::new_map(
+            "config",
+            "entries",
+            Field::new("key", DataType::Utf8, false),
+            Field::new_struct(
+                "value",
+                vec![
+                    Field::new("limit", DataType::Int64, true),
+                    Field::new("enabled", DataType::Boolean, false),
+                    Field::new("owner", DataType::Utf8, true),
+                ],
+                true,
+            ),
+            false,
+            true,
+        ),
+    ]);
+    let new_batch = read_json(
+        new_schema,
+        r#"
+        {"payload": {"meta": {"source": "api", "tags": ["c"]}, "id": 3}, "items": [{"qty": 1, "sku": "Y2", "price": 9.5}], "attrs": {"size": 10}, "config": {"default": {"limit": 5000000000, "enabled": false, "owner": "ops"}}}
+        "#,
+    )?;
+    write_parquet_file(&temp_path.join("data_new.parquet"), &new_batch)?;
+
+    // ============================================================================
+    // Step 3: Unify the nested schemas and query the nested columns
+    // ============================================================================
+    let ctx = SessionContext::new_with_config(SessionConfig::from_env()?);
+    let listing_options = ListingOptions::new(Arc::new(ParquetFormat::default()));
+    let table_url = ListingTableUrl::parse(temp_path.to_str().unwrap())?;
+
+    let unified = merge_schemas(&ctx.state(), &table_url, &listing_options).await?;
+    for field in unified.schema.fields() {
+        println!("{}: {}", field.name(), field.data_type());
+    }
+    println!("{}", unified.report);
+
+    let table_config = ListingTableConfig::new(table_url)
+        .with_listing_options(listing_options)
+        .with_schema(unified.schema)
+        .with_expr_adapter_factory(Arc::new(SchemaEvolutionAdapterFactory::new()));
+    ctx.register_table("events", Arc::new(ListingTable::try_new(table_config)?))?;
+
+    ctx.sql(
+        "SELECT payload['id'] AS id, payload['meta']['source'] AS source, \
+         payload['meta']['tags'] AS tags, items, attrs, config FROM events ORDER BY id",
+    )
+    .await?
+    .show()
+    .await?;
+
+    Ok(())
+}
+
+/// Helper function to build a RecordBatch from newline-delimited JSON
+fn read_json(schema: Schema, json: &str) -> Result<RecordBatch, Box<dyn std::error::Error>> {
+    let mut reader =
+        ReaderBuilder::new(Arc::new(schema)).build(Cursor::new(json.trim().as_bytes()))?;
+    Ok(reader.next().ok_or("no rows")??)
+}
+
+/// Helper function to write a RecordBatch to a Parquet file
+fn write_parquet_file(path: &Path, batch: &RecordBatch) -> Result<(), Box<dyn std::error::Error>> {
+    let file = std::fs::File::create(path)?;
+    let props = WriterProperties::builder().build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
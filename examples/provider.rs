@@ -0,0 +1,81 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+use schema_evolution::prelude::*;
+
+/// This example registers a Hive-partitioned Parquet dataset whose schema drifted
+/// between partitions with `SchemaEvolutionTableProvider`:
+/// - day=1/data.parquet: id is Int32, there is no 'value' column
+/// - day=2/data.parquet: id is Int64, 'value' was added
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let temp_path = temp_dir.path();
+
+    // ============================================================================
+    // Step 1: Create one Parquet file per partition
+    // ============================================================================
+    let old_batch = RecordBatch::try_new(
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ])),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2])),
+            Arc::new(StringArray::from(vec!["a", "b"])),
+        ],
+    )?;
+    std::fs::create_dir(temp_path.join("day=1"))?;
+    write_parquet_file(&temp_path.join("day=1/data.parquet"), &old_batch)?;
+
+    let new_batch = RecordBatch::try_new(
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("value", DataType::Int64, false),
+        ])),
+        vec![
+            Arc::new(Int64Array::from(vec![3, 4])),
+            Arc::new(StringArray::from(vec!["c", "d"])),
+            Arc::new(Int64Array::from(vec![30, 40])),
+        ],
+    )?;
+    std::fs::create_dir(temp_path.join("day=2"))?;
+    write_parquet_file(&temp_path.join("day=2/data.parquet"), &new_batch)?;
+
+    // ============================================================================
+    // Step 2: Register the dataset and query it
+    // ============================================================================
+    let ctx = SessionContext::new_with_config(SessionConfig::from_env()?);
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        temp_path.to_str().unwrap(),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new().with_table_partition_cols(vec![("day".into(), DataType::Int32)]),
+    )
+    .await?;
+    println!("{}", provider.report());
+    ctx.register_table("events", Arc::new(provider))?;
+
+    ctx.sql("SELECT * FROM events WHERE day = 1 OR value > 30 ORDER BY id")
+        .await?
+        .show()
+        .await?;
+
+    Ok(())
+}
+
+/// Helper function to write a RecordBatch to a Parquet file
+fn write_parquet_file(path: &Path, batch: &RecordBatch) -> Result<(), Box<dyn std::error::Error>> {
+    let file = std::fs::File::create(path)?;
+    let props = WriterProperties::builder().build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::{
+    datasource::listing::{ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl},
+    prelude::{SessionConfig, SessionContext},
+};
+use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::format::EvolvingFormat;
+use schema_evolution::mapping::MappingScope;
+use schema_evolution::{FieldMapping, SchemaUnifier};
+
+/// This example reads Parquet files written before and after columns were renamed:
+/// - year=2023/data.parquet: 'customer_id' and 'name'
+/// - year=2024/data.parquet: 'cust_id' and 'full_name' after the renames
+///
+/// 'customer_id' always meant 'cust_id', while 'name' only meant 'full_name' in
+/// the files from 2023, so that rename is scoped to their path prefix.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let temp_path = temp_dir.path();
+
+    // ============================================================================
+    // Step 1: Create the files before and after the renames
+    // ============================================================================
+    let old_batch = RecordBatch::try_new(
+        Arc::new(Schema::new(vec![
+            Field::new("customer_id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+        ])),
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2])),
+            Arc::new(StringArray::from(vec!["Ada Lovelace", "Alan Turing"])),
+        ],
+    )?;
+    std::fs::create_dir(temp_path.join("year=2023"))?;
+    write_parquet_file(&temp_path.join("year=2023/data.parquet"), &old_batch)?;
+
+    let new_batch = RecordBatch::try_new(
+        Arc::new(Schema::new(vec![
+            Field::new("cust_id", DataType::Int64, false),
+            Field::new("full_name", DataType::Utf8, false),
+        ])),
+        vec![
+            Arc::new(Int64Array::from(vec![3, 4])),
+            Arc::new(StringArray::from(vec!["Grace Hopper", "Edsger Dijkstra"])),
+        ],
+    )?;
+    std::fs::create_dir(temp_path.join("year=2024"))?;
+    write_parquet_file(&temp_path.join("year=2024/data.parquet"), &new_batch)?;
+
+    // ============================================================================
+    // Step 2: Unify the schemas under the current names and query the files
+    // ============================================================================
+    let ctx = SessionContext::new_with_config(SessionConfig::from_env()?);
+    let table_url = ListingTableUrl::parse(temp_path.to_str().unwrap())?;
+    let mapping = FieldMapping::new()
+        .with_rename("customer_id", "cust_id")
+        .with_scoped_rename(
+            "name",
+            "full_name",
+            MappingScope::path_prefix(format!("{}/year=2023/", table_url.prefix())),
+        );
+
+    // Scoped renames need to know which file is read, which `EvolvingFormat` tells
+    let listing_options = ListingOptions::new(Arc::new(EvolvingFormat::new(Arc::new(
+        ParquetFormat::default(),
+    ))));
+
+    let unified = SchemaUnifier::new()
+        .with_field_mapping(mapping.clone())
+        .unify_url(&ctx.state(), &table_url, &listing_options)
+        .await?;
+    println!("Unified schema: {:?}", unified.schema.fields());
+    println!("{}", unified.report);
+
+    let table_config = ListingTableConfig::new(table_url)
+        .with_listing_options(listing_options)
+        .with_schema(unified.schema)
+        .with_expr_adapter_factory(Arc::new(
+            SchemaEvolutionAdapterFactory::new().with_field_mapping(mapping),
+        ));
+    ctx.register_table("customers", Arc::new(ListingTable::try_new(table_config)?))?;
+
+    ctx.sql("SELECT * FROM customers ORDER BY cust_id")
+        .await?
+        .show()
+        .await?;
+
+    ctx.sql("SELECT full_name FROM customers WHERE cust_id < 3")
+        .await?
+        .show()
+        .await?;
+
+    Ok(())
+}
+
+/// Helper function to write a RecordBatch to a Parquet file
+fn write_parquet_file(path: &Path, batch: &RecordBatch) -> Result<(), Box<dyn std::error::Error>> {
+    let file = std::fs::File::create(path)?;
+    let props = WriterProperties::builder().build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
@@ -0,0 +1,1005 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use arrow::array::ArrayRef;
+use arrow::compute::CastOptions;
+use arrow::datatypes::{DataType, Field, FieldRef, Schema, SchemaRef};
+use datafusion::common::format::DEFAULT_CAST_OPTIONS;
+use datafusion::common::stats::Precision;
+use datafusion::common::tree_node::{Transformed, TransformedResult, TreeNode, TreeNodeRecursion};
+use datafusion::common::{
+    ColumnStatistics, DataFusionError, Result, ScalarValue, Statistics, exec_err,
+};
+use datafusion::logical_expr::Operator;
+use datafusion::physical_expr::expressions::{
+    BinaryExpr, CastColumnExpr, CastExpr, Column, Literal,
+};
+use datafusion::physical_expr::{PhysicalExpr, ScalarFunctionExpr};
+use datafusion::physical_expr_adapter::{PhysicalExprAdapter, PhysicalExprAdapterFactory};
+
+use crate::canonical::{CanonicalizeOptions, canonicalize_field};
+use crate::format::{FileContext, record_reduced_cast_batches};
+use crate::limits::{MemoryLimits, ReducedBatchExpr, is_parse_cast};
+use crate::manifest::EvolutionManifest;
+use crate::mapping::FieldMapping;
+use crate::missing::{ColumnDefault, MissingColumnPolicy, NullArrayExpr};
+use crate::nested::{NestedCastExpr, cast_nested};
+use crate::normalize::NormalizeExpr;
+use crate::policy::{
+    Coercion, CoercionError, CoercionMode, CoercionPolicy, UncoercibleValue, is_lossless_widening,
+    is_order_preserving,
+};
+use crate::row_id::{ROW_ID_COLUMN, RowIdGenerator};
+use crate::sentinel::{SentinelExpr, fill_uncoercible};
+use crate::temporal::{
+    NaiveTimestamps, TemporalCastExpr, cast_temporal, cast_temporal_scalar, differs_from_arrow,
+    is_temporal,
+};
+
+/// Cast options for coercions that may fail on individual values: such values
+/// become null instead of failing the scan.
+const LENIENT_CAST_OPTIONS: CastOptions<'static> = CastOptions {
+    safe: true,
+    ..DEFAULT_CAST_OPTIONS
+};
+
+/// A [`PhysicalExprAdapterFactory`] that reconciles each file's schema with the
+/// table schema at scan time.
+///
+/// Columns are matched by name, coerced to the table's type as allowed by the
+/// [`CoercionPolicy`] when the file stored a different one, and filled as the
+/// [`MissingColumnPolicy`] says (with nulls by default) when an older file does
+/// not have them. Columns renamed over time are read
+/// from their historical names through a [`FieldMapping`]:
+///
+/// ```ignore
+/// let config = ListingTableConfig::new(table_url)
+///     .with_listing_options(listing_options)
+///     .with_schema(table_schema)
+///     .with_expr_adapter_factory(Arc::new(SchemaEvolutionAdapterFactory::new()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemaEvolutionAdapterFactory {
+    policy: CoercionPolicy,
+    mapping: FieldMapping,
+    missing: MissingColumnPolicy,
+    manifest: Option<Arc<EvolutionManifest>>,
+    row_ids: Option<Arc<dyn RowIdGenerator>>,
+    limits: MemoryLimits,
+}
+
+impl SchemaEvolutionAdapterFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy deciding which type differences are reconciled.
+    pub fn with_policy(mut self, policy: CoercionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &CoercionPolicy {
+        &self.policy
+    }
+
+    /// Set the renames to apply. Renames scoped to some files only apply when
+    /// the files are read through [`EvolvingFormat`](crate::format::EvolvingFormat).
+    pub fn with_field_mapping(mut self, mapping: FieldMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    pub fn field_mapping(&self) -> &FieldMapping {
+        &self.mapping
+    }
+
+    /// Set the values of columns that older files lack.
+    pub fn with_missing_column_policy(mut self, missing: MissingColumnPolicy) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    pub fn missing_column_policy(&self) -> &MissingColumnPolicy {
+        &self.missing
+    }
+
+    /// Read each file by applying the migrations an [`EvolutionManifest`]
+    /// declares after the file's version, on top of the other settings. Files
+    /// matching no version fail the scan; path rules need the files read
+    /// through [`EvolvingFormat`](crate::format::EvolvingFormat).
+    pub fn with_manifest(mut self, manifest: Arc<EvolutionManifest>) -> Self {
+        self.manifest = Some(manifest);
+        self
+    }
+
+    pub fn manifest(&self) -> Option<&Arc<EvolutionManifest>> {
+        self.manifest.as_ref()
+    }
+
+    /// Generate the table's [`ROW_ID_COLUMN`] for files that do not store it.
+    /// The ids are filled in by [`EvolvingFormat`](crate::format::EvolvingFormat),
+    /// which the files must be read through.
+    pub fn with_row_ids(mut self, generator: Arc<dyn RowIdGenerator>) -> Self {
+        self.row_ids = Some(generator);
+        self
+    }
+
+    pub fn row_ids(&self) -> Option<&Arc<dyn RowIdGenerator>> {
+        self.row_ids.as_ref()
+    }
+
+    /// Evaluate the string-parse casts of files needing many of them in
+    /// smaller batches.
+    pub fn with_memory_limits(mut self, limits: MemoryLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn memory_limits(&self) -> &MemoryLimits {
+        &self.limits
+    }
+
+    /// The adapter for `file`, configured like the ones this factory creates.
+    pub fn adapter(
+        &self,
+        logical_file_schema: SchemaRef,
+        physical_file_schema: SchemaRef,
+        file: Option<FileContext>,
+    ) -> SchemaEvolutionAdapter {
+        let Some(manifest) = &self.manifest else {
+            let mut adapter = SchemaEvolutionAdapter::new(
+                logical_file_schema,
+                physical_file_schema,
+                &self.policy,
+            )
+            .with_missing_column_policy(self.missing.clone())
+            .with_row_ids(self.row_ids.is_some())
+            .with_memory_limits(self.limits);
+            if let Some(file) = file {
+                adapter = adapter.with_file(file);
+            }
+            if !self.mapping.is_empty() {
+                adapter = adapter.with_field_mapping(self.mapping.clone());
+            }
+            return adapter;
+        };
+
+        let plan = match manifest.plan(file.as_ref(), &physical_file_schema) {
+            Ok(plan) => plan,
+            Err(err) => {
+                return SchemaEvolutionAdapter::new(
+                    logical_file_schema,
+                    physical_file_schema,
+                    &self.policy,
+                )
+                .with_error(err.to_string());
+            }
+        };
+        // Declared casts apply whatever the policy
+        let policy = plan
+            .casts
+            .iter()
+            .fold(self.policy.clone(), |policy, column| {
+                policy.with_column(column.clone(), CoercionMode::Lenient)
+            });
+        let missing = plan
+            .defaults
+            .iter()
+            .fold(self.missing.clone(), |missing, (column, default)| {
+                missing.with_literal(column.clone(), default.clone())
+            });
+        let mapping = self.mapping.renames().iter().fold(
+            plan.renames
+                .iter()
+                .fold(FieldMapping::new(), |mapping, (from, to)| {
+                    mapping.with_rename(from.clone(), to.clone())
+                }),
+            |mapping, rename| {
+                mapping.with_scoped_rename(
+                    rename.from.clone(),
+                    rename.to.clone(),
+                    rename.scope.clone(),
+                )
+            },
+        );
+        let mut adapter =
+            SchemaEvolutionAdapter::new(logical_file_schema, physical_file_schema, &policy)
+                .with_missing_column_policy(missing)
+                .with_dropped_columns(plan.dropped)
+                .with_scales(plan.scales.into_iter().collect())
+                .with_row_ids(self.row_ids.is_some())
+                .with_memory_limits(self.limits);
+        if let Some(file) = file {
+            adapter = adapter.with_file(file);
+        }
+        adapter.with_field_mapping(mapping)
+    }
+}
+
+impl PhysicalExprAdapterFactory for SchemaEvolutionAdapterFactory {
+    fn create(
+        &self,
+        logical_file_schema: SchemaRef,
+        physical_file_schema: SchemaRef,
+    ) -> Arc<dyn PhysicalExprAdapter> {
+        let adapter = self.adapter(
+            logical_file_schema,
+            physical_file_schema,
+            FileContext::current(),
+        );
+        if adapter.parse_batch_rows().is_some() {
+            record_reduced_cast_batches();
+        }
+        Arc::new(adapter)
+    }
+}
+
+/// How one table column is produced from a particular file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnPlan {
+    /// The file stores the column with the table's type; only its index, or its
+    /// name if it was renamed, may differ.
+    Passthrough { index: usize, name: String },
+    /// The file stores the column with another type the policy allows.
+    Cast {
+        index: usize,
+        source: FieldRef,
+        target: FieldRef,
+        coercion: Coercion,
+    },
+    /// The file does not have the column.
+    Missing { target: FieldRef },
+    /// The synthetic row-id column, which the file does not store; read as
+    /// nulls for [`EvolvingFormat`](crate::format::EvolvingFormat) to fill in.
+    RowId { target: FieldRef },
+    /// The policy does not allow coercing the file's type to the table's.
+    Incompatible(CoercionError),
+}
+
+/// How much work reading a whole file takes, decided once per file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilePlan {
+    /// The file schema is the table schema: expressions are used as they are.
+    Identity,
+    /// Every column is stored with the table's type, but some are renamed,
+    /// moved, or next to columns the table does not have.
+    Reorder,
+    /// Some columns are cast, transformed, filled in or conflicting.
+    Adapt,
+}
+
+/// The [`PhysicalExprAdapter`] created by [`SchemaEvolutionAdapterFactory`] for
+/// one file.
+///
+/// The per-column plan is computed once when the file is opened, so rewriting the
+/// projection and the pushed-down predicate only looks columns up. Conflicts are
+/// reported when a conflicting column is actually referenced, which lets queries
+/// that never touch it succeed.
+#[derive(Debug)]
+pub struct SchemaEvolutionAdapter {
+    logical_file_schema: SchemaRef,
+    physical_file_schema: SchemaRef,
+    policy: CoercionPolicy,
+    mapping: FieldMapping,
+    missing: MissingColumnPolicy,
+    file: Option<FileContext>,
+    dropped: HashSet<String>,
+    scales: HashMap<String, f64>,
+    row_ids: bool,
+    error: Option<String>,
+    columns: HashMap<String, ColumnPlan>,
+    file_plan: FilePlan,
+    limits: MemoryLimits,
+    /// The batch size string-parse casts are evaluated in, if reduced.
+    parse_batch_rows: Option<usize>,
+}
+
+impl SchemaEvolutionAdapter {
+    pub fn new(
+        logical_file_schema: SchemaRef,
+        physical_file_schema: SchemaRef,
+        policy: &CoercionPolicy,
+    ) -> Self {
+        let mut adapter = Self {
+            logical_file_schema,
+            physical_file_schema,
+            policy: policy.clone(),
+            mapping: FieldMapping::default(),
+            missing: MissingColumnPolicy::default(),
+            file: None,
+            dropped: HashSet::new(),
+            scales: HashMap::new(),
+            row_ids: false,
+            error: None,
+            columns: HashMap::new(),
+            file_plan: FilePlan::Adapt,
+            limits: MemoryLimits::default(),
+            parse_batch_rows: None,
+        };
+        adapter.plan_columns();
+        adapter
+    }
+
+    /// Read renamed columns from their historical names in the file.
+    pub fn with_field_mapping(mut self, mapping: FieldMapping) -> Self {
+        self.mapping = mapping;
+        self.plan_columns();
+        self
+    }
+
+    /// Fill columns the file lacks with the defaults of `missing`.
+    pub fn with_missing_column_policy(mut self, missing: MissingColumnPolicy) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    /// Set the file the adapter is for, which scoped renames and file-derived
+    /// defaults depend on.
+    pub fn with_file(mut self, file: FileContext) -> Self {
+        self.file = Some(file);
+        self.plan_columns();
+        self
+    }
+
+    /// Read the file columns `dropped` as missing, e.g. because the table
+    /// dropped them and later added another column of the same name.
+    pub fn with_dropped_columns(mut self, dropped: HashSet<String>) -> Self {
+        self.dropped = dropped;
+        self.plan_columns();
+        self
+    }
+
+    /// Multiply the values of the table columns in `scales` by their factor,
+    /// for files written in other units (`0.001` for milliseconds to seconds).
+    pub fn with_scales(mut self, scales: HashMap<String, f64>) -> Self {
+        self.scales = scales;
+        self.plan_columns();
+        self
+    }
+
+    /// Plan the [`ROW_ID_COLUMN`] as generated when the file does not store
+    /// it.
+    pub fn with_row_ids(mut self, row_ids: bool) -> Self {
+        self.row_ids = row_ids;
+        self.plan_columns();
+        self
+    }
+
+    /// Evaluate string-parse casts in smaller batches if the file needs as
+    /// many as `limits` says.
+    pub fn with_memory_limits(mut self, limits: MemoryLimits) -> Self {
+        self.limits = limits;
+        self.plan_columns();
+        self
+    }
+
+    /// The batch size string-parse casts of this file are evaluated in, if
+    /// the [`MemoryLimits`] reduce it.
+    pub fn parse_batch_rows(&self) -> Option<usize> {
+        self.parse_batch_rows
+    }
+
+    /// Fail every rewrite with `message`, for files that cannot be read at all.
+    pub fn with_error(mut self, message: impl Into<String>) -> Self {
+        self.error = Some(message.into());
+        self
+    }
+
+    fn plan_columns(&mut self) {
+        self.columns = self
+            .logical_file_schema
+            .fields()
+            .iter()
+            .map(|target| {
+                let stored = self
+                    .mapping
+                    .resolve(
+                        target.name(),
+                        &self.physical_file_schema,
+                        self.file.as_ref(),
+                    )
+                    .unwrap_or(target.name());
+                let plan = if self.row_ids
+                    && target.name() == ROW_ID_COLUMN
+                    && self.physical_file_schema.field_with_name(stored).is_err()
+                {
+                    ColumnPlan::RowId {
+                        target: Arc::clone(target),
+                    }
+                } else if self.dropped.contains(stored) {
+                    ColumnPlan::Missing {
+                        target: Arc::clone(target),
+                    }
+                } else {
+                    plan_column(
+                        &self.physical_file_schema,
+                        stored,
+                        target,
+                        &self.policy,
+                        self.file.as_ref(),
+                    )
+                };
+                (target.name().clone(), plan)
+            })
+            .collect();
+        self.file_plan = self.plan_file();
+        let parse_casts = self
+            .columns
+            .values()
+            .filter(|plan| {
+                matches!(plan, ColumnPlan::Cast { source, coercion, .. }
+                    if is_parse_cast(source.data_type(), *coercion))
+            })
+            .count();
+        self.parse_batch_rows = self.limits.reduced_batch_rows(parse_casts);
+    }
+
+    fn plan_file(&self) -> FilePlan {
+        let logical = self.logical_file_schema.fields();
+        let physical = self.physical_file_schema.fields();
+        // Writer metadata does not change the batches, the types must match
+        let options = CanonicalizeOptions::metadata_only();
+        let mut identity = logical.len() == physical.len();
+        for (position, target) in logical.iter().enumerate() {
+            let transformed = self.policy.normalization_for(target.name()).is_some()
+                || self.scales.contains_key(target.name());
+            match self.columns.get(target.name()) {
+                Some(ColumnPlan::Passthrough { index, .. }) if !transformed => {
+                    identity &= *index == position
+                        && canonicalize_field(&physical[*index], &options)
+                            == canonicalize_field(target, &options);
+                }
+                _ => return FilePlan::Adapt,
+            }
+        }
+        if identity {
+            FilePlan::Identity
+        } else {
+            FilePlan::Reorder
+        }
+    }
+
+    /// The work reading this file takes.
+    pub fn file_plan(&self) -> FilePlan {
+        self.file_plan
+    }
+
+    /// The plan for the table column `name`, if the table has such a column.
+    pub fn column_plan(&self, name: &str) -> Option<&ColumnPlan> {
+        self.columns.get(name)
+    }
+
+    /// Map `statistics`, computed over the file's own schema, to the table
+    /// schema.
+    ///
+    /// Only what still holds for the adapted values is kept: min/max of widened
+    /// columns are cast, columns filled with a constant get it as min and max,
+    /// normalized and scaled columns keep only their null count, and the
+    /// statistics of columns cast leniently are dropped, since such casts may null out values
+    /// and need not preserve their order. Pruning
+    /// with the file's own statistics would otherwise skip files whose values
+    /// match once adapted, e.g. `'10' < '9'` as strings but not as integers.
+    pub fn adapt_statistics(&self, statistics: Statistics) -> Statistics {
+        let column_statistics = self
+            .logical_file_schema
+            .fields()
+            .iter()
+            .map(|target| {
+                let column = |index: &usize| statistics.column_statistics.get(*index);
+                let transformed = self.policy.normalization_for(target.name()).is_some()
+                    || self.scales.contains_key(target.name());
+                match self.columns.get(target.name()) {
+                    // Normalizing and scaling keep nulls but not the min/max
+                    Some(
+                        ColumnPlan::Passthrough { index, .. }
+                        | ColumnPlan::Cast {
+                            index,
+                            coercion: Coercion::Widen,
+                            ..
+                        },
+                    ) if transformed => column(index).map(|column| {
+                        ColumnStatistics::new_unknown().with_null_count(column.null_count)
+                    }),
+                    Some(ColumnPlan::Passthrough { index, .. }) => column(index).cloned(),
+                    Some(ColumnPlan::Cast {
+                        index,
+                        target,
+                        coercion: Coercion::Widen,
+                        ..
+                    }) => column(index).map(|column| {
+                        widen_statistics(column, target, self.policy.naive_timestamps())
+                    }),
+                    Some(ColumnPlan::Missing { target }) => {
+                        self.missing_statistics(target, statistics.num_rows)
+                    }
+                    Some(ColumnPlan::RowId { .. }) => {
+                        Some(ColumnStatistics::new_unknown().with_null_count(Precision::Exact(0)))
+                    }
+                    _ => None,
+                }
+                .unwrap_or_else(ColumnStatistics::new_unknown)
+            })
+            .collect();
+
+        Statistics {
+            num_rows: statistics.num_rows,
+            total_byte_size: statistics.total_byte_size,
+            column_statistics,
+        }
+    }
+
+    /// The statistics of `target` in a file without it, if its default is a
+    /// constant.
+    fn missing_statistics(
+        &self,
+        target: &FieldRef,
+        num_rows: Precision<usize>,
+    ) -> Option<ColumnStatistics> {
+        let default = self
+            .missing
+            .default_for(target.name())
+            .to_physical(target, &self.logical_file_schema, self.file.as_ref())
+            .ok()?;
+        let value = default.as_any().downcast_ref::<Literal>()?.value();
+        if value.is_null() {
+            return Some(ColumnStatistics::new_unknown().with_null_count(num_rows));
+        }
+        Some(
+            ColumnStatistics::new_unknown()
+                .with_null_count(Precision::Exact(0))
+                .with_min_value(Precision::Exact(value.clone()))
+                .with_max_value(Precision::Exact(value.clone())),
+        )
+    }
+
+    /// Rewrite `expr`, `depth` counting the default expressions being expanded.
+    fn rewrite_expr(
+        &self,
+        expr: Arc<dyn PhysicalExpr>,
+        depth: usize,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        // Top down, so that field accesses are seen before their column; the
+        // rewritten expressions are in the file's terms and not visited again
+        expr.transform_down(|expr| {
+            if let Some(access) = self.rewrite_field_access(&expr)? {
+                return Ok(Transformed::new(access, true, TreeNodeRecursion::Jump));
+            }
+            if let Some(column) = expr.as_any().downcast_ref::<Column>() {
+                let rewritten = self.rewrite_column(column, depth)?;
+                return Ok(Transformed::new(rewritten, true, TreeNodeRecursion::Jump));
+            }
+            Ok(Transformed::no(expr))
+        })
+        .data()
+    }
+
+    /// `get_field` of a struct column the file stores with another struct
+    /// type, read from the file's struct and cast as a leaf, e.g.
+    /// `meta['user_id']` reads the file's `meta.user_id` alone instead of
+    /// casting every field of `meta`. `None` where the whole struct is needed.
+    fn rewrite_field_access(
+        &self,
+        expr: &Arc<dyn PhysicalExpr>,
+    ) -> Result<Option<Arc<dyn PhysicalExpr>>> {
+        let Some(function) = expr.as_any().downcast_ref::<ScalarFunctionExpr>() else {
+            return Ok(None);
+        };
+        let Some((base, names)) = function.args().split_first() else {
+            return Ok(None);
+        };
+        if function.name() != "get_field" || names.is_empty() {
+            return Ok(None);
+        }
+        let Some(column) = base.as_any().downcast_ref::<Column>() else {
+            return Ok(None);
+        };
+        let Some(ColumnPlan::Cast {
+            index,
+            source,
+            target,
+            ..
+        }) = self.columns.get(column.name())
+        else {
+            return Ok(None);
+        };
+        if self.policy.normalization_for(column.name()).is_some()
+            || self.scales.contains_key(column.name())
+        {
+            return Ok(None);
+        }
+
+        let (mut stored, mut wanted) = (Some(Arc::clone(source)), Arc::clone(target));
+        for name in names {
+            let Some(name) = name
+                .as_any()
+                .downcast_ref::<Literal>()
+                .and_then(|literal| literal.value().try_as_str().flatten())
+            else {
+                return Ok(None);
+            };
+            let DataType::Struct(wanted_fields) = wanted.data_type() else {
+                return Ok(None);
+            };
+            let Some((_, field)) = wanted_fields.find(name) else {
+                return Ok(None);
+            };
+            stored = match stored.as_deref().map(Field::data_type) {
+                Some(DataType::Struct(stored_fields)) => {
+                    stored_fields.find(name).map(|(_, field)| Arc::clone(field))
+                }
+                Some(_) => return Ok(None),
+                None => None,
+            };
+            wanted = Arc::clone(field);
+        }
+
+        // Older files may not have the field at all
+        let Some(stored) = stored else {
+            if !wanted.is_nullable() {
+                return Ok(None);
+            }
+            return Ok(Some(Arc::new(Literal::new(ScalarValue::try_from(
+                wanted.data_type(),
+            )?))));
+        };
+        let file_column: Arc<dyn PhysicalExpr> = Arc::new(Column::new(source.name(), *index));
+        let mut args = vec![file_column];
+        args.extend(names.iter().cloned());
+        let access: Arc<dyn PhysicalExpr> = Arc::new(ScalarFunctionExpr::try_new(
+            Arc::clone(function.fun()),
+            args,
+            &self.physical_file_schema,
+            Arc::clone(function.config_options()),
+        )?);
+        let access_field = access.return_field(&self.physical_file_schema)?;
+        match self
+            .policy
+            .resolve(target.name(), stored.data_type(), wanted.data_type())
+        {
+            Ok(coercion) => Ok(Some(cast_expr(
+                access,
+                &access_field,
+                &wanted,
+                coercion,
+                self.policy.naive_timestamps(),
+            ))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn rewrite_column(&self, column: &Column, depth: usize) -> Result<Arc<dyn PhysicalExpr>> {
+        let Some(plan) = self.columns.get(column.name()) else {
+            // Not a table column, e.g. injected by another rewrite; use it from the
+            // file as is if it exists there.
+            let index = self.physical_file_schema.index_of(column.name())?;
+            return Ok(Arc::new(Column::new(column.name(), index)));
+        };
+
+        // Old values are converted to the current units before normalizing
+        let finish = |expr: Arc<dyn PhysicalExpr>| -> Result<Arc<dyn PhysicalExpr>> {
+            let expr = match self.scales.get(column.name()) {
+                Some(factor) => {
+                    let target = self.logical_file_schema.field_with_name(column.name())?;
+                    scale_expr(
+                        expr,
+                        target.data_type(),
+                        *factor,
+                        &self.physical_file_schema,
+                    )?
+                }
+                None => expr,
+            };
+            Ok(match self.policy.normalization_for(column.name()) {
+                Some(normalization) => Arc::new(NormalizeExpr::new(expr, *normalization)),
+                None => expr,
+            })
+        };
+        match plan {
+            ColumnPlan::Passthrough { index, name } => finish(Arc::new(Column::new(name, *index))),
+            ColumnPlan::Cast {
+                index,
+                source,
+                target,
+                coercion,
+            } => {
+                let input: Arc<dyn PhysicalExpr> = Arc::new(Column::new(source.name(), *index));
+                let mut expr = cast_expr(
+                    Arc::clone(&input),
+                    source,
+                    target,
+                    *coercion,
+                    self.policy.naive_timestamps(),
+                );
+                if let (
+                    Coercion::Lenient | Coercion::ViaString,
+                    UncoercibleValue::Sentinel(sentinel),
+                ) = (coercion, self.policy.uncoercible_for(target.name()))
+                {
+                    expr = Arc::new(SentinelExpr::new(input, expr, sentinel.clone()));
+                }
+                if let Some(rows) = self.parse_batch_rows
+                    && is_parse_cast(source.data_type(), *coercion)
+                {
+                    expr = Arc::new(ReducedBatchExpr::new(expr, rows));
+                }
+                finish(expr)
+            }
+            ColumnPlan::Missing { target } => {
+                let default = self.missing.default_for(target.name());
+                let expr =
+                    default.to_physical(target, &self.logical_file_schema, self.file.as_ref())?;
+                if !matches!(default, ColumnDefault::Expr(_)) {
+                    return Ok(expr);
+                }
+                // Defaults may refer to columns that are missing too
+                if depth >= self.columns.len() {
+                    return exec_err!(
+                        "The default expression of column '{}' refers to itself",
+                        target.name()
+                    );
+                }
+                self.rewrite_expr(expr, depth + 1)
+            }
+            ColumnPlan::RowId { target } => Ok(Arc::new(NullArrayExpr::new(Arc::new(
+                target.as_ref().clone().with_nullable(true),
+            )))),
+            ColumnPlan::Incompatible(err) => Err(DataFusionError::External(Box::new(err.clone()))),
+        }
+    }
+}
+
+impl PhysicalExprAdapter for SchemaEvolutionAdapter {
+    fn rewrite(&self, expr: Arc<dyn PhysicalExpr>) -> Result<Arc<dyn PhysicalExpr>> {
+        if let Some(message) = &self.error {
+            return exec_err!("{message}");
+        }
+        if self.file_plan == FilePlan::Identity {
+            return Ok(expr);
+        }
+        // A projected column that is null in this file: share one array across
+        // batches. Elsewhere keep the literal, which the simplifier can fold.
+        if let Some(column) = expr.as_any().downcast_ref::<Column>()
+            && let Some(ColumnPlan::Missing { target }) = self.columns.get(column.name())
+            && target.is_nullable()
+            && self.missing.default_for(target.name()) == &ColumnDefault::Null
+        {
+            return Ok(Arc::new(NullArrayExpr::new(Arc::clone(target))));
+        }
+        collapse_casts(self.rewrite_expr(expr, 0)?)
+    }
+}
+
+/// Fold casts of the query over the lossless widening of a file column into
+/// one cast of the column as stored, e.g. `CAST(id AS INT)` over an `Int32`
+/// column widened to `Int64` reads the column itself. The query's casts only
+/// meet the adapter's in the rewritten expression, so no logical rule can see
+/// both; once folded, pruning sees the column's own statistics again.
+fn collapse_casts(expr: Arc<dyn PhysicalExpr>) -> Result<Arc<dyn PhysicalExpr>> {
+    expr.transform_up(|expr| match collapse_cast(&expr) {
+        Some(collapsed) => Ok(Transformed::yes(collapsed)),
+        None => Ok(Transformed::no(expr)),
+    })
+    .data()
+}
+
+fn collapse_cast(expr: &Arc<dyn PhysicalExpr>) -> Option<Arc<dyn PhysicalExpr>> {
+    let outer = expr.as_any().downcast_ref::<CastExpr>()?;
+    let inner = outer.expr().as_any().downcast_ref::<CastColumnExpr>()?;
+    let from = inner.input_field().data_type();
+    let to = outer.cast_type();
+    // A lenient inner cast nulls values the outer one would cast differently
+    if !is_lossless_widening(from, inner.target_field().data_type()) {
+        return None;
+    }
+    if from == to {
+        return Some(Arc::clone(inner.expr()));
+    }
+    is_lossless_widening(from, to).then(|| {
+        Arc::new(CastExpr::new(
+            Arc::clone(inner.expr()),
+            to.clone(),
+            Some(outer.cast_options().clone()),
+        )) as Arc<dyn PhysicalExpr>
+    })
+}
+
+/// Multiply `expr` by `factor` in the column's type `target`. Integer columns
+/// are divided by the inverse of factors below one, which truncates.
+fn scale_expr(
+    expr: Arc<dyn PhysicalExpr>,
+    target: &DataType,
+    factor: f64,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let (op, operand) = if !target.is_integer() || (factor >= 1.0 && factor.fract() == 0.0) {
+        (Operator::Multiply, factor)
+    } else {
+        let inverse = (1.0 / factor).round();
+        if inverse < 1.0 || (inverse * factor - 1.0).abs() > 1e-9 {
+            return exec_err!("Cannot scale a column of type {target} by {factor}");
+        }
+        (Operator::Divide, inverse)
+    };
+    let literal = ScalarValue::Float64(Some(operand)).cast_to(target)?;
+    // e.g. 0.001 in a decimal column with two digits after the point
+    match literal.cast_to(&DataType::Float64)? {
+        ScalarValue::Float64(Some(value)) if (value - operand).abs() <= operand * 1e-9 => {}
+        _ => return exec_err!("Cannot scale a column of type {target} by {factor}"),
+    }
+    let expr: Arc<dyn PhysicalExpr> =
+        Arc::new(BinaryExpr::new(expr, op, Arc::new(Literal::new(literal))));
+    // Decimal arithmetic changes the precision and scale
+    if expr.data_type(input_schema)? == *target {
+        return Ok(expr);
+    }
+    Ok(Arc::new(CastExpr::new(expr, target.clone(), None)))
+}
+
+/// Cast the min/max of a losslessly widened column, which keeps their order.
+fn widen_statistics(
+    column: &ColumnStatistics,
+    target: &FieldRef,
+    naive: NaiveTimestamps,
+) -> ColumnStatistics {
+    // The same values the scan casts to
+    let cast_value = |value: &ScalarValue| {
+        if is_temporal(&value.data_type()) && is_temporal(target.data_type()) {
+            cast_temporal_scalar(value, target.data_type(), naive, &DEFAULT_CAST_OPTIONS)
+        } else {
+            value.cast_to(target.data_type())
+        }
+    };
+    let cast = |value: &Precision<ScalarValue>| match value {
+        Precision::Exact(value) => cast_value(value).map_or(Precision::Absent, Precision::Exact),
+        Precision::Inexact(value) => {
+            cast_value(value).map_or(Precision::Absent, Precision::Inexact)
+        }
+        Precision::Absent => Precision::Absent,
+    };
+    ColumnStatistics::new_unknown()
+        .with_null_count(column.null_count)
+        .with_min_value(cast(&column.min_value))
+        .with_max_value(cast(&column.max_value))
+        .with_distinct_count(column.distinct_count)
+}
+
+/// Plan the table column `target`, stored in the file under `stored`; errors
+/// name `file` if it is known.
+fn plan_column(
+    physical_file_schema: &SchemaRef,
+    stored: &str,
+    target: &FieldRef,
+    policy: &CoercionPolicy,
+    file: Option<&FileContext>,
+) -> ColumnPlan {
+    let Some((index, source)) = physical_file_schema.column_with_name(stored) else {
+        return ColumnPlan::Missing {
+            target: Arc::clone(target),
+        };
+    };
+
+    match policy.resolve(target.name(), source.data_type(), target.data_type()) {
+        Ok(Coercion::Identity) => ColumnPlan::Passthrough {
+            index,
+            name: source.name().clone(),
+        },
+        Ok(coercion) => ColumnPlan::Cast {
+            index,
+            source: Arc::new(source.clone()),
+            target: Arc::clone(target),
+            coercion,
+        },
+        Err(err) => ColumnPlan::Incompatible(match file {
+            Some(file) => err.with_file(&file.path),
+            None => err,
+        }),
+    }
+}
+
+/// Cast `array` to `target` as `coercion` says, like the expressions of
+/// [`cast_expr`] would, and fill in the values lenient casts fail on as
+/// `uncoercible` says.
+pub(crate) fn cast_array(
+    array: &ArrayRef,
+    target: &DataType,
+    coercion: Coercion,
+    naive: NaiveTimestamps,
+    uncoercible: &UncoercibleValue,
+) -> Result<ArrayRef> {
+    let temporal = is_temporal(array.data_type()) && is_temporal(target);
+    let cast = match coercion {
+        Coercion::Identity => Ok(Arc::clone(array)),
+        Coercion::Widen if temporal => cast_temporal(array, target, naive, &DEFAULT_CAST_OPTIONS),
+        Coercion::Lenient if temporal => cast_temporal(array, target, naive, &LENIENT_CAST_OPTIONS),
+        Coercion::Widen => cast_nested(array, target, &DEFAULT_CAST_OPTIONS),
+        Coercion::Lenient => cast_nested(array, target, &LENIENT_CAST_OPTIONS),
+        Coercion::ViaString => {
+            let string = cast_nested(array, &DataType::Utf8, &LENIENT_CAST_OPTIONS)?;
+            cast_nested(&string, target, &LENIENT_CAST_OPTIONS)
+        }
+    }?;
+    match (coercion, uncoercible) {
+        (Coercion::Lenient | Coercion::ViaString, UncoercibleValue::Sentinel(sentinel)) => {
+            fill_uncoercible(array, &cast, sentinel)
+        }
+        _ => Ok(cast),
+    }
+}
+
+fn cast_expr(
+    expr: Arc<dyn PhysicalExpr>,
+    source: &FieldRef,
+    target: &FieldRef,
+    coercion: Coercion,
+    naive: NaiveTimestamps,
+) -> Arc<dyn PhysicalExpr> {
+    // Where the values differ from Arrow's, pruning must not look through
+    // the cast
+    if is_temporal(source.data_type())
+        && is_temporal(target.data_type())
+        && differs_from_arrow(source.data_type(), target.data_type(), naive)
+    {
+        let options = match coercion {
+            Coercion::Identity | Coercion::Widen => DEFAULT_CAST_OPTIONS,
+            Coercion::Lenient | Coercion::ViaString => LENIENT_CAST_OPTIONS,
+        };
+        return Arc::new(TemporalCastExpr::new(
+            expr,
+            Arc::clone(target),
+            naive,
+            options,
+        ));
+    }
+    if source.data_type().is_nested() || target.data_type().is_nested() {
+        let options = match coercion {
+            Coercion::Identity | Coercion::Widen => DEFAULT_CAST_OPTIONS,
+            Coercion::Lenient | Coercion::ViaString => LENIENT_CAST_OPTIONS,
+        };
+        return Arc::new(NestedCastExpr::new(expr, Arc::clone(target), options));
+    }
+
+    match coercion {
+        Coercion::Identity => expr,
+        Coercion::Widen => Arc::new(CastColumnExpr::new(
+            expr,
+            Arc::clone(source),
+            Arc::clone(target),
+            None,
+        )),
+        // Pruning sees through `CastColumnExpr` and compares the cast min/max,
+        // which is only sound if the cast keeps the order of the values
+        Coercion::Lenient if !is_order_preserving(source.data_type(), target.data_type()) => {
+            Arc::new(NestedCastExpr::new(
+                expr,
+                Arc::clone(target),
+                LENIENT_CAST_OPTIONS,
+            ))
+        }
+        Coercion::Lenient => Arc::new(CastColumnExpr::new(
+            expr,
+            Arc::clone(source),
+            Arc::clone(target),
+            Some(LENIENT_CAST_OPTIONS),
+        )),
+        Coercion::ViaString => {
+            let string = Arc::new(Field::new(
+                source.name(),
+                DataType::Utf8,
+                source.is_nullable(),
+            ));
+            let expr = Arc::new(CastColumnExpr::new(
+                expr,
+                Arc::clone(source),
+                Arc::clone(&string),
+                Some(LENIENT_CAST_OPTIONS),
+            ));
+            Arc::new(CastColumnExpr::new(
+                expr,
+                string,
+                Arc::clone(target),
+                Some(LENIENT_CAST_OPTIONS),
+            ))
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use arrow::datatypes::Schema;
+use datafusion::common::{DataFusionError, Result, exec_err};
+use datafusion::object_store::path::Path;
+use datafusion::object_store::{self, ObjectStore, PutMode, PutOptions, PutPayload, UpdateVersion};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::fingerprint::SchemaFingerprint;
+
+/// The name of the advisory lock object of an [`AppendProtocol`], in the
+/// table directory.
+pub const SCHEMA_LOCK: &str = "_schema.lock";
+
+/// The name of a data file carrying the fingerprint of its schema, e.g.
+/// `part-0001.v1-8c3f0e2a96b1d4c7.parquet`, which [`name_fingerprint`] reads
+/// back.
+pub fn fingerprinted_name(stem: &str, fingerprint: SchemaFingerprint, extension: &str) -> String {
+    format!(
+        "{stem}.v{}-{:016x}.{}",
+        fingerprint.algorithm().version(),
+        fingerprint.hash(),
+        extension.trim_start_matches('.')
+    )
+}
+
+/// The schema fingerprint in the name of the file at `path`, if it was named
+/// by [`fingerprinted_name`].
+pub fn name_fingerprint(path: &str) -> Option<SchemaFingerprint> {
+    let name = path.rsplit('/').next()?;
+    name.split('.')
+        .find_map(|part| part.replacen('-', ":", 1).parse().ok())
+}
+
+/// A protocol for several writers appending to one table without a registry
+/// of its schemas.
+///
+/// Every data file is named after the fingerprint of its schema, so the
+/// schemas a table has are known from a listing, and
+/// [`SchemaDiscovery::with_name_fingerprints`](crate::discovery::SchemaDiscovery::with_name_fingerprints)
+/// reads one footer per schema. A writer whose schema the table already has
+/// appends freely; one introducing a new schema first takes the advisory lock
+/// object [`SCHEMA_LOCK`], so that two writers do not introduce different
+/// schemas at the same time. The lock expires after a lease, in case its
+/// holder crashed.
+///
+/// ```ignore
+/// let protocol = AppendProtocol::new(store, Path::from("events"), "ingest-7");
+/// let append = protocol.begin(&batch.schema()).await?;
+/// let path = Path::from(format!("events/{}", append.file_name("part-0001", "parquet")));
+/// // write the file at `path`
+/// append.commit().await?;
+/// ```
+///
+/// The lock is advisory: writers that do not follow the protocol are not
+/// stopped. An expired lock is replaced conditionally on the version that was
+/// read, so that only one of the writers taking it over gets it, which needs
+/// a store supporting conditional puts. A holder that outlives its lease does
+/// not release its successor's lock, but may still append alongside it.
+#[derive(Debug, Clone)]
+pub struct AppendProtocol {
+    store: Arc<dyn ObjectStore>,
+    table: Path,
+    writer: String,
+    lease: Duration,
+}
+
+impl AppendProtocol {
+    /// Append to the table under `table` in `store`, as the writer named
+    /// `writer` in locks and conflicts.
+    pub fn new(store: Arc<dyn ObjectStore>, table: Path, writer: impl Into<String>) -> Self {
+        Self {
+            store,
+            table,
+            writer: writer.into(),
+            lease: Duration::from_secs(300),
+        }
+    }
+
+    /// How long a lock is held at most; five minutes by default.
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// The fingerprints in the names of the table's files.
+    pub async fn fingerprints(&self) -> Result<HashSet<SchemaFingerprint>> {
+        let objects: Vec<_> = self.store.list(Some(&self.table)).try_collect().await?;
+        Ok(objects
+            .iter()
+            .filter_map(|object| name_fingerprint(object.location.as_ref()))
+            .collect())
+    }
+
+    /// Start appending files with `schema`, taking the lock if the table does
+    /// not have the schema yet.
+    ///
+    /// Fails with an [`AppendConflict`] while another writer introduces a
+    /// different schema.
+    pub async fn begin(&self, schema: &Schema) -> Result<AppendSession> {
+        let fingerprint = SchemaFingerprint::of(schema);
+        let mut session = AppendSession {
+            fingerprint,
+            store: Arc::clone(&self.store),
+            lock: None,
+        };
+        if self.fingerprints().await?.contains(&fingerprint) {
+            return Ok(session);
+        }
+
+        let location = self.table.child(SCHEMA_LOCK);
+        let lock = LockJson {
+            writer: self.writer.clone(),
+            fingerprint: fingerprint.to_string(),
+            expires_at: millis(SystemTime::now() + self.lease),
+        };
+        let payload = PutPayload::from(serde_json::to_vec(&lock).map_err(external)?);
+        // Each attempt either takes the lock or finds it held; an attempt is
+        // only repeated when another writer changed the lock in between
+        let mut mode = PutMode::Create;
+        for _ in 0..LOCK_ATTEMPTS {
+            let options = PutOptions {
+                mode: mode.clone(),
+                ..Default::default()
+            };
+            match self
+                .store
+                .put_opts(&location, payload.clone(), options)
+                .await
+            {
+                Ok(written) => {
+                    session.lock = Some(HeldLock {
+                        location,
+                        version: written.into(),
+                        lock,
+                    });
+                    return Ok(session);
+                }
+                // Taken, or replaced or released since it was read
+                Err(
+                    object_store::Error::AlreadyExists { .. }
+                    | object_store::Error::Precondition { .. }
+                    | object_store::Error::NotFound { .. },
+                ) => {}
+                Err(err) => return Err(err.into()),
+            }
+
+            let held = match self.store.get(&location).await {
+                Ok(held) => held,
+                Err(object_store::Error::NotFound { .. }) => {
+                    mode = PutMode::Create;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let version = UpdateVersion {
+                e_tag: held.meta.e_tag.clone(),
+                version: held.meta.version.clone(),
+            };
+            let held: LockJson = serde_json::from_slice(&held.bytes().await?).map_err(external)?;
+            if held.expires_at <= millis(SystemTime::now()) {
+                // Replace exactly the expired lock that was read: of two
+                // writers taking it over, the second fails the precondition
+                mode = PutMode::Update(version);
+                continue;
+            }
+            if held.writer == self.writer {
+                session.lock = Some(HeldLock {
+                    location,
+                    version,
+                    lock: held,
+                });
+                return Ok(session);
+            }
+            // The same schema introduced twice does not conflict
+            if held.fingerprint == lock.fingerprint {
+                return Ok(session);
+            }
+            return Err(DataFusionError::External(Box::new(AppendConflict {
+                writer: held.writer,
+                fingerprint: held.fingerprint,
+                expires_at: UNIX_EPOCH + Duration::from_millis(held.expires_at),
+            })));
+        }
+        exec_err!("The schema lock {location} kept changing while it was taken")
+    }
+}
+
+/// Files being appended by one writer, started by [`AppendProtocol::begin`].
+#[derive(Debug)]
+pub struct AppendSession {
+    fingerprint: SchemaFingerprint,
+    store: Arc<dyn ObjectStore>,
+    lock: Option<HeldLock>,
+}
+
+/// The lock an [`AppendSession`] holds, as it wrote or found it.
+#[derive(Debug)]
+struct HeldLock {
+    location: Path,
+    version: UpdateVersion,
+    lock: LockJson,
+}
+
+impl AppendSession {
+    pub fn fingerprint(&self) -> SchemaFingerprint {
+        self.fingerprint
+    }
+
+    /// Whether this writer holds the lock, to introduce a new schema.
+    pub fn holds_lock(&self) -> bool {
+        self.lock.is_some()
+    }
+
+    /// The name to write a file under, see [`fingerprinted_name`].
+    pub fn file_name(&self, stem: &str, extension: &str) -> String {
+        fingerprinted_name(stem, self.fingerprint, extension)
+    }
+
+    /// Release the lock, once the files are written.
+    ///
+    /// A lock that expired and was taken over by another writer is left to
+    /// it: the lock is only deleted while it is still the one this session
+    /// wrote, by version where the store has versions and by content.
+    pub async fn commit(self) -> Result<()> {
+        let Some(held) = &self.lock else {
+            return Ok(());
+        };
+        let current = match self.store.get(&held.location).await {
+            Ok(current) => current,
+            Err(object_store::Error::NotFound { .. }) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        let same_version = (held.version.e_tag.is_none()
+            || current.meta.e_tag == held.version.e_tag)
+            && (held.version.version.is_none() || current.meta.version == held.version.version);
+        let current: LockJson =
+            serde_json::from_slice(&current.bytes().await?).map_err(external)?;
+        if !same_version || current != held.lock {
+            log::warn!(
+                "The schema lock {} changed since it was taken, now held by writer '{}'",
+                held.location,
+                current.writer
+            );
+            return Ok(());
+        }
+        match self.store.delete(&held.location).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Another writer holds the lock of an [`AppendProtocol`] to introduce a
+/// different schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppendConflict {
+    pub writer: String,
+    /// The fingerprint of the schema the writer introduces.
+    pub fingerprint: String,
+    /// When the lock expires unless released before.
+    pub expires_at: SystemTime,
+}
+
+impl fmt::Display for AppendConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let remaining = self
+            .expires_at
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        write!(
+            f,
+            "writer '{}' is introducing schema {}; retry once it commits, at most {}s from now",
+            self.writer,
+            self.fingerprint,
+            remaining.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for AppendConflict {}
+
+/// How often [`AppendProtocol::begin`] tries to take a lock that other
+/// writers keep changing.
+const LOCK_ATTEMPTS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct LockJson {
+    writer: String,
+    fingerprint: String,
+    /// Milliseconds since the Unix epoch.
+    expires_at: u64,
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn external(err: serde_json::Error) -> DataFusionError {
+    DataFusionError::External(Box::new(err))
+}
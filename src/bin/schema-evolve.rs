@@ -0,0 +1,188 @@
+//! `schema-evolve inspect <path> [--format parquet|vortex] [--statistics]`:
+//! report how the file schemas of a dataset drifted, before queries over it
+//! start failing, and with `--statistics` how the values changed with them.
+//!
+//! `schema-evolve trend <path> [--format parquet|vortex] [--window <days>]`:
+//! summarize how often new schema versions appeared, which columns changed
+//! most and how many files needed adapting, per window of days (91 by
+//! default).
+//!
+//! `schema-evolve explain-failure --table <path> --sql <query> [--name <table>]
+//! [--format parquet|vortex]`: run a query over the dataset, registered as
+//! `t` unless named otherwise, and if it fails say which stage failed, which
+//! files it fails on, and which of their columns fail to be read, with the
+//! adaptation step each takes.
+
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use datafusion::prelude::{SessionConfig, SessionContext};
+use schema_evolution::diagnose::FailureExplanation;
+use schema_evolution::discovery::SchemaDiscovery;
+use schema_evolution::drift::{StatisticsDrift, StatisticsThresholds};
+use schema_evolution::report::trend;
+use schema_evolution::{DriftReport, EvolutionOptions, SchemaUnifier};
+use vortex::VortexSessionDefault;
+use vortex::session::VortexSession;
+use vortex_datafusion::VortexFormat;
+
+const USAGE: &str = "\
+usage: schema-evolve inspect <path> [--format parquet|vortex] [--statistics]
+       schema-evolve trend <path> [--format parquet|vortex] [--window <days>]
+       schema-evolve explain-failure --table <path> --sql <query> [--name <table>] [--format parquet|vortex]";
+
+enum Command {
+    Inspect { statistics: bool },
+    Trend { window: Duration },
+    ExplainFailure { sql: Option<String>, name: String },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, path, format) = match parse_args(&args) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = match command {
+        Command::Inspect { statistics } => inspect(path, format, statistics).await.map(|report| {
+            print!("{report}");
+            // Exit with an error if even the lenient policy fails on some file
+            report.suggested().is_some()
+        }),
+        Command::Trend { window } => summarize(path, format, window).await.map(|()| true),
+        Command::ExplainFailure { sql, name } => {
+            let sql = sql.unwrap_or_default();
+            explain_failure(path, format, &name, &sql)
+                .await
+                .map(|explanation| {
+                    print!("{explanation}");
+                    explanation.failure.is_none()
+                })
+        }
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<(Command, &str, Arc<dyn FileFormat>), String> {
+    let mut path = None;
+    let mut format: Arc<dyn FileFormat> = Arc::new(ParquetFormat::default());
+    let mut args = args.iter();
+    let mut command = match args.next().map(String::as_str) {
+        Some("inspect") => Command::Inspect { statistics: false },
+        Some("trend") => Command::Trend {
+            window: Duration::from_secs(91 * 86_400),
+        },
+        Some("explain-failure") => Command::ExplainFailure {
+            sql: None,
+            name: "t".to_string(),
+        },
+        Some(command) => return Err(format!("unknown command '{command}'")),
+        None => return Err("missing command".to_string()),
+    };
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), &mut command) {
+            ("--format", _) => {
+                format = match args.next().map(String::as_str) {
+                    Some("parquet") => Arc::new(ParquetFormat::default()),
+                    Some("vortex") => Arc::new(VortexFormat::new(VortexSession::default())),
+                    Some(other) => return Err(format!("unknown format '{other}'")),
+                    None => return Err("--format needs a value".to_string()),
+                }
+            }
+            ("--statistics", Command::Inspect { statistics }) => *statistics = true,
+            ("--window", Command::Trend { window }) => {
+                *window = match args.next().map(|days| days.parse::<u64>()) {
+                    Some(Ok(days)) if days > 0 => Duration::from_secs(days * 86_400),
+                    Some(_) => return Err("--window needs a positive number of days".to_string()),
+                    None => return Err("--window needs a value".to_string()),
+                }
+            }
+            ("--sql", Command::ExplainFailure { sql, .. }) => {
+                *sql = Some(args.next().ok_or("--sql needs a value")?.clone());
+            }
+            ("--name", Command::ExplainFailure { name, .. }) => {
+                *name = args.next().ok_or("--name needs a value")?.clone();
+            }
+            ("--table", Command::ExplainFailure { .. }) if path.is_none() => {
+                path = Some(args.next().ok_or("--table needs a value")?.as_str());
+            }
+            _ if path.is_none() => path = Some(arg.as_str()),
+            _ => return Err(format!("unexpected argument '{arg}'")),
+        }
+    }
+    if let Command::ExplainFailure { sql: None, .. } = command {
+        return Err("missing --sql".to_string());
+    }
+    Ok((command, path.ok_or("missing path")?, format))
+}
+
+async fn inspect(
+    path: &str,
+    format: Arc<dyn FileFormat>,
+    statistics: bool,
+) -> datafusion::common::Result<DriftReport> {
+    let ctx = SessionContext::new_with_config(SessionConfig::from_env()?);
+    let table_url = ListingTableUrl::parse(path)?;
+    let listing_options = ListingOptions::new(format);
+    let report = DriftReport::from_url(&ctx.state(), &table_url, &listing_options).await?;
+    if !statistics {
+        return Ok(report);
+    }
+    let statistics = StatisticsDrift::from_url(
+        &ctx.state(),
+        &table_url,
+        &listing_options,
+        StatisticsThresholds::new(),
+    )
+    .await?;
+    Ok(report.with_statistics(statistics))
+}
+
+async fn summarize(
+    path: &str,
+    format: Arc<dyn FileFormat>,
+    window: Duration,
+) -> datafusion::common::Result<()> {
+    let ctx = SessionContext::new_with_config(SessionConfig::from_env()?);
+    let table_url = ListingTableUrl::parse(path)?;
+    let listing_options = ListingOptions::new(format);
+    let discovered = SchemaDiscovery::new()
+        .discover(&ctx.state(), &table_url, &listing_options)
+        .await?;
+    let unified = SchemaUnifier::new().unify(
+        discovered
+            .files
+            .iter()
+            .map(|(file, schema)| (file.path.clone(), Arc::clone(schema)))
+            .collect(),
+    );
+    print!("{}", trend(&discovered, &unified.report, window));
+    Ok(())
+}
+
+async fn explain_failure(
+    path: &str,
+    format: Arc<dyn FileFormat>,
+    name: &str,
+    sql: &str,
+) -> datafusion::common::Result<FailureExplanation> {
+    let ctx = SessionContext::new_with_config(SessionConfig::from_env()?);
+    let table_url = ListingTableUrl::parse(path)?;
+    FailureExplanation::explain(&ctx, &table_url, format, EvolutionOptions::new(), name, sql).await
+}
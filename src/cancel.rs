@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use datafusion::common::{DataFusionError, Result, exec_datafusion_err};
+use datafusion::object_store::path::Path;
+use datafusion::object_store::{self, ObjectStore};
+use futures::TryStreamExt;
+use tokio_util::sync::CancellationToken;
+
+/// Await `future` unless `token` is cancelled first, in which case `future`
+/// is dropped and `what` fails as cancelled.
+pub(crate) async fn cancellable<T>(
+    token: Option<&CancellationToken>,
+    what: &str,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let Some(token) = token else {
+        return future.await;
+    };
+    if token.is_cancelled() {
+        return Err(cancelled(what));
+    }
+    token
+        .run_until_cancelled(future)
+        .await
+        .unwrap_or_else(|| Err(cancelled(what)))
+}
+
+fn cancelled(what: &str) -> DataFusionError {
+    exec_datafusion_err!("{what} was cancelled")
+}
+
+/// The objects one write creates, deleted again unless the write is
+/// [finished](Self::finish): explicitly by [`Self::clean_up`] when it fails,
+/// or in the background when it is dropped unfinished, e.g. because the
+/// future writing it was.
+pub(crate) struct PartialWrite {
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+    /// The objects under `location` before the write, if it is a directory.
+    existing: Option<HashSet<Path>>,
+    finished: bool,
+}
+
+impl PartialWrite {
+    /// A write to the single object at `location`.
+    pub(crate) fn file(store: Arc<dyn ObjectStore>, location: Path) -> Self {
+        Self {
+            store,
+            location,
+            existing: None,
+            finished: false,
+        }
+    }
+
+    /// A write of new objects under the directory `location`; those already
+    /// there are kept.
+    pub(crate) async fn directory(store: Arc<dyn ObjectStore>, location: Path) -> Result<Self> {
+        let existing = store
+            .list(Some(&location))
+            .map_ok(|object| object.location)
+            .try_collect()
+            .await?;
+        Ok(Self {
+            store,
+            location,
+            existing: Some(existing),
+            finished: false,
+        })
+    }
+
+    /// Keep what was written.
+    pub(crate) fn finish(mut self) {
+        self.finished = true;
+    }
+
+    /// Delete what was written so far.
+    pub(crate) async fn clean_up(mut self) -> Result<()> {
+        self.finished = true;
+        delete_written(
+            Arc::clone(&self.store),
+            self.location.clone(),
+            self.existing.take(),
+        )
+        .await
+    }
+}
+
+impl Drop for PartialWrite {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            log::warn!("Could not clean up the partial write to {}", self.location);
+            return;
+        };
+        let (store, location, existing) = (
+            Arc::clone(&self.store),
+            self.location.clone(),
+            self.existing.take(),
+        );
+        runtime.spawn(async move {
+            if let Err(err) = delete_written(store, location.clone(), existing).await {
+                log::warn!("Could not clean up the partial write to {location}: {err}");
+            }
+        });
+    }
+}
+
+async fn delete_written(
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+    existing: Option<HashSet<Path>>,
+) -> Result<()> {
+    let Some(existing) = existing else {
+        return match store.delete(&location).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(err) => Err(err.into()),
+        };
+    };
+    let written: Vec<Path> = store
+        .list(Some(&location))
+        .map_ok(|object| object.location)
+        .try_filter(|path| futures::future::ready(!existing.contains(path)))
+        .try_collect()
+        .await?;
+    for path in written {
+        match store.delete(&path).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, FieldRef, Fields, Schema};
+
+/// Metadata keys written by common producers that say nothing about the logical
+/// shape of the data and change from writer to writer.
+pub const DEFAULT_VOLATILE_METADATA_KEYS: &[&str] = &[
+    "ARROW:schema",
+    "pandas",
+    "org.apache.spark.version",
+    "org.apache.spark.sql.parquet.row.metadata",
+];
+
+/// Rules applied by [`canonicalize_with`].
+///
+/// The defaults treat physically different but logically equal encodings as the
+/// same type, which is what every equality check in this crate wants.
+#[derive(Debug, Clone)]
+pub struct CanonicalizeOptions {
+    /// Map `LargeUtf8` and `Utf8View` to `Utf8`.
+    pub normalize_strings: bool,
+    /// Map `LargeBinary` and `BinaryView` to `Binary`.
+    pub normalize_binary: bool,
+    /// Map `LargeList` and `ListView` to `List`.
+    pub normalize_lists: bool,
+    /// Replace `Dictionary(K, V)` by its value type `V`.
+    pub unwrap_dictionaries: bool,
+    /// Rename list item fields to `item` (Parquet writers disagree between
+    /// `item`, `element` and `array`).
+    pub normalize_list_field_names: bool,
+    /// Metadata keys removed from the schema and from every field.
+    pub volatile_metadata_keys: Vec<String>,
+}
+
+impl Default for CanonicalizeOptions {
+    fn default() -> Self {
+        Self {
+            normalize_strings: true,
+            normalize_binary: true,
+            normalize_lists: true,
+            unwrap_dictionaries: true,
+            normalize_list_field_names: true,
+            volatile_metadata_keys: DEFAULT_VOLATILE_METADATA_KEYS
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl CanonicalizeOptions {
+    /// Options that only strip volatile metadata and keep every type as written.
+    pub fn metadata_only() -> Self {
+        Self {
+            normalize_strings: false,
+            normalize_binary: false,
+            normalize_lists: false,
+            unwrap_dictionaries: false,
+            normalize_list_field_names: false,
+            ..Default::default()
+        }
+    }
+
+    /// Add a metadata key that should be ignored.
+    pub fn with_volatile_metadata_key(mut self, key: impl Into<String>) -> Self {
+        self.volatile_metadata_keys.push(key.into());
+        self
+    }
+
+    fn is_volatile(&self, key: &str) -> bool {
+        self.volatile_metadata_keys.iter().any(|k| k == key)
+    }
+}
+
+/// Canonicalize `schema` with the default [`CanonicalizeOptions`].
+pub fn canonicalize(schema: &Schema) -> Schema {
+    canonicalize_with(schema, &CanonicalizeOptions::default())
+}
+
+/// Canonicalize `schema`: normalize equivalent types (recursively, including
+/// struct, list and map children) and strip volatile metadata.
+///
+/// Arrow keeps metadata in a `HashMap`, so the result has no meaningful key order;
+/// use [`sorted_metadata`] wherever the canonical form is serialized.
+pub fn canonicalize_with(schema: &Schema, options: &CanonicalizeOptions) -> Schema {
+    let fields: Fields = schema
+        .fields()
+        .iter()
+        .map(|field| canonicalize_field(field, options))
+        .collect();
+    Schema::new_with_metadata(fields, strip_metadata(schema.metadata(), options))
+}
+
+/// Canonicalize a single field with the given options.
+pub fn canonicalize_field(field: &Field, options: &CanonicalizeOptions) -> FieldRef {
+    Arc::new(
+        Field::new(
+            field.name(),
+            canonicalize_type(field.data_type(), options),
+            field.is_nullable(),
+        )
+        .with_metadata(strip_metadata(field.metadata(), options)),
+    )
+}
+
+/// Canonicalize a data type with the given options.
+pub fn canonicalize_type(data_type: &DataType, options: &CanonicalizeOptions) -> DataType {
+    match data_type {
+        DataType::LargeUtf8 | DataType::Utf8View if options.normalize_strings => DataType::Utf8,
+        DataType::LargeBinary | DataType::BinaryView if options.normalize_binary => {
+            DataType::Binary
+        }
+        DataType::Dictionary(_, value) if options.unwrap_dictionaries => {
+            canonicalize_type(value, options)
+        }
+        DataType::Dictionary(key, value) => {
+            DataType::Dictionary(key.clone(), Box::new(canonicalize_type(value, options)))
+        }
+        DataType::List(item) => DataType::List(canonicalize_list_item(item, options)),
+        DataType::LargeList(item) | DataType::ListView(item) | DataType::LargeListView(item)
+            if options.normalize_lists =>
+        {
+            DataType::List(canonicalize_list_item(item, options))
+        }
+        DataType::LargeList(item) => DataType::LargeList(canonicalize_list_item(item, options)),
+        DataType::ListView(item) => DataType::ListView(canonicalize_list_item(item, options)),
+        DataType::LargeListView(item) => {
+            DataType::LargeListView(canonicalize_list_item(item, options))
+        }
+        DataType::FixedSizeList(item, size) => {
+            DataType::FixedSizeList(canonicalize_list_item(item, options), *size)
+        }
+        DataType::Struct(fields) => DataType::Struct(
+            fields
+                .iter()
+                .map(|field| canonicalize_field(field, options))
+                .collect(),
+        ),
+        DataType::Map(entries, sorted) => {
+            DataType::Map(canonicalize_field(entries, options), *sorted)
+        }
+        other => other.clone(),
+    }
+}
+
+/// Whether two schemas are equal once both are canonicalized with `options`.
+pub fn equivalent(left: &Schema, right: &Schema, options: &CanonicalizeOptions) -> bool {
+    canonicalize_with(left, options) == canonicalize_with(right, options)
+}
+
+/// Metadata entries in key order, for serializing a canonical schema.
+pub fn sorted_metadata(metadata: &HashMap<String, String>) -> BTreeMap<&str, &str> {
+    metadata
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect()
+}
+
+fn canonicalize_list_item(item: &FieldRef, options: &CanonicalizeOptions) -> FieldRef {
+    let item = canonicalize_field(item, options);
+    if options.normalize_list_field_names && item.name() != "item" {
+        Arc::new(item.as_ref().clone().with_name("item"))
+    } else {
+        item
+    }
+}
+
+fn strip_metadata(
+    metadata: &HashMap<String, String>,
+    options: &CanonicalizeOptions,
+) -> HashMap<String, String> {
+    metadata
+        .iter()
+        .filter(|(key, _)| !options.is_volatile(key))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
@@ -0,0 +1,309 @@
+use std::fmt;
+use std::sync::Arc;
+
+use arrow::datatypes::{Schema, SchemaRef};
+use datafusion::catalog::TableProvider;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{ListingTable, ListingTableConfig, ListingTableUrl};
+use datafusion::physical_plan::collect;
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+
+use crate::adapter::ColumnPlan;
+use crate::format::{EvolvingFormat, FileContext};
+use crate::provider::{EvolutionOptions, SchemaEvolutionTableProvider};
+
+/// Why a query over an evolved table fails, narrowed down from DataFusion's
+/// error to the files and columns it fails on.
+///
+/// The query is registered, planned and run as a scan would; if it fails
+/// while reading, it is run again over each file alone, and for each file it
+/// fails on, every column is read alone, naming the adaptation step of the
+/// columns that fail. Files a query cannot even be planned over alone, e.g.
+/// because it uses partition columns, are not reported.
+///
+/// ```ignore
+/// let explanation = FailureExplanation::explain(
+///     &ctx,
+///     &ListingTableUrl::parse("s3://bucket/events/")?,
+///     Arc::new(ParquetFormat::default()),
+///     EvolutionOptions::new(),
+///     "events",
+///     "SELECT sum(amount) FROM events",
+/// )
+/// .await?;
+/// print!("{explanation}");
+/// ```
+#[derive(Debug, Clone)]
+pub struct FailureExplanation {
+    pub sql: String,
+    /// How the query failed; `None` if it succeeded.
+    pub failure: Option<QueryFailure>,
+    /// The files the query fails on when run over each alone.
+    pub files: Vec<FileFailure>,
+}
+
+/// The stage a query failed at, with DataFusion's error.
+#[derive(Debug, Clone)]
+pub struct QueryFailure {
+    pub stage: FailureStage,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureStage {
+    /// Reading the file schemas and unifying them.
+    Registration,
+    /// Planning the query over the table schema.
+    Planning,
+    /// Reading and adapting the files.
+    Execution,
+}
+
+impl fmt::Display for FailureStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Registration => "registering the table",
+            Self::Planning => "planning",
+            Self::Execution => "execution",
+        })
+    }
+}
+
+/// A file the query fails on alone.
+#[derive(Debug, Clone)]
+pub struct FileFailure {
+    /// The path relative to the object store root.
+    pub path: String,
+    pub error: String,
+    /// The columns that fail to be read from the file alone; empty if they
+    /// all read, and only the query's own expressions over them fail.
+    pub columns: Vec<ColumnFailure>,
+}
+
+/// A column that fails to be read from a file.
+#[derive(Debug, Clone)]
+pub struct ColumnFailure {
+    pub column: String,
+    /// How the adapter produces the column from the file.
+    pub step: String,
+    pub error: String,
+}
+
+impl FailureExplanation {
+    /// Register the files under `table_url` as `table` with `options`, run
+    /// `sql` over it and explain how it fails.
+    pub async fn explain(
+        ctx: &SessionContext,
+        table_url: &ListingTableUrl,
+        format: Arc<dyn FileFormat>,
+        options: EvolutionOptions,
+        table: &str,
+        sql: &str,
+    ) -> Result<Self> {
+        let mut explanation = Self {
+            sql: sql.to_string(),
+            failure: None,
+            files: Vec::new(),
+        };
+        let provider = match SchemaEvolutionTableProvider::try_new(
+            &ctx.state(),
+            table_url.as_str(),
+            format,
+            options,
+        )
+        .await
+        {
+            Ok(provider) => provider,
+            Err(err) => {
+                explanation.failure = Some(QueryFailure {
+                    stage: FailureStage::Registration,
+                    error: err.to_string(),
+                });
+                return Ok(explanation);
+            }
+        };
+        let listing_options = provider.listing_table().options().clone();
+        let table_schema = provider.schema();
+        match run(ctx, table, Arc::new(provider), sql).await {
+            Ok(()) => return Ok(explanation),
+            Err(failure) => {
+                let stage = failure.stage;
+                explanation.failure = Some(failure);
+                if stage != FailureStage::Execution {
+                    return Ok(explanation);
+                }
+            }
+        }
+
+        // Run the query over each file alone, with the table's schema and
+        // adapter
+        let partition_cols: Vec<&str> = listing_options
+            .table_partition_cols
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let file_schema: SchemaRef = Arc::new(Schema::new_with_metadata(
+            table_schema
+                .fields()
+                .iter()
+                .filter(|field| !partition_cols.contains(&field.name().as_str()))
+                .cloned()
+                .collect::<Vec<_>>(),
+            table_schema.metadata().clone(),
+        ));
+        let options = listing_options.with_table_partition_cols(Vec::new());
+        let adapter_factory = options
+            .format
+            .as_any()
+            .downcast_ref::<EvolvingFormat>()
+            .map(|format| format.adapter_factory().clone())
+            .unwrap_or_default();
+        let state = ctx.state();
+        let store = state.runtime_env().object_store(table_url)?;
+        let mut objects: Vec<_> = table_url
+            .list_all_files(&state, store.as_ref(), &options.file_extension)
+            .await?
+            .try_collect()
+            .await?;
+        objects.sort_by(|left, right| left.location.cmp(&right.location));
+        for object in objects {
+            let url = ListingTableUrl::parse(format!(
+                "{}{}",
+                table_url.object_store().as_str(),
+                object.location
+            ))?;
+            let file_table = || {
+                let config = ListingTableConfig::new(url.clone())
+                    .with_listing_options(options.clone())
+                    .with_schema(Arc::clone(&file_schema))
+                    .with_expr_adapter_factory(Arc::new(adapter_factory.clone()));
+                ListingTable::try_new(config).map(|table| Arc::new(table) as Arc<dyn TableProvider>)
+            };
+            let error = match run(ctx, table, file_table()?, sql).await {
+                Err(failure) if failure.stage == FailureStage::Execution => failure.error,
+                _ => continue,
+            };
+
+            let stored_schema = options
+                .format
+                .infer_schema(&state, &store, std::slice::from_ref(&object))
+                .await?;
+            let adapter = adapter_factory.adapter(
+                Arc::clone(&file_schema),
+                stored_schema,
+                Some(FileContext::from(&object)),
+            );
+            let mut columns = Vec::new();
+            for field in file_schema.fields() {
+                let column_sql = format!(
+                    "SELECT \"{}\" FROM {table}",
+                    field.name().replace('"', "\"\"")
+                );
+                let provider = file_table()?;
+                if let Err(failure) = run(ctx, table, provider, &column_sql).await {
+                    columns.push(ColumnFailure {
+                        column: field.name().clone(),
+                        step: adapter
+                            .column_plan(field.name())
+                            .map_or_else(|| "unknown".to_string(), describe),
+                        error: failure.error,
+                    });
+                }
+            }
+            explanation.files.push(FileFailure {
+                path: object.location.to_string(),
+                error,
+                columns,
+            });
+        }
+        Ok(explanation)
+    }
+}
+
+/// Run `sql` over `provider` registered as `table` in a session of its own,
+/// with the configuration and object stores of `ctx`.
+async fn run(
+    ctx: &SessionContext,
+    table: &str,
+    provider: Arc<dyn TableProvider>,
+    sql: &str,
+) -> std::result::Result<(), QueryFailure> {
+    let planning = |err: DataFusionError| QueryFailure {
+        stage: FailureStage::Planning,
+        error: err.to_string(),
+    };
+    let session = SessionContext::new_with_config_rt(ctx.copied_config(), ctx.runtime_env());
+    session.register_table(table, provider).map_err(planning)?;
+    let plan = session
+        .sql(sql)
+        .await
+        .map_err(planning)?
+        .create_physical_plan()
+        .await
+        .map_err(planning)?;
+    collect(plan, session.task_ctx())
+        .await
+        .map(|_| ())
+        .map_err(|err| QueryFailure {
+            stage: FailureStage::Execution,
+            error: err.to_string(),
+        })
+}
+
+fn describe(plan: &ColumnPlan) -> String {
+    match plan {
+        ColumnPlan::Passthrough { name, .. } => format!("read as stored in '{name}'"),
+        ColumnPlan::Cast {
+            source,
+            target,
+            coercion,
+            ..
+        } => format!(
+            "{coercion:?} cast of '{}' from {} to {}",
+            source.name(),
+            source.data_type(),
+            target.data_type()
+        ),
+        ColumnPlan::Missing { .. } => "filled in as missing from the file".to_string(),
+        ColumnPlan::RowId { .. } => "generated row id".to_string(),
+        ColumnPlan::Incompatible(err) => err.to_string(),
+    }
+}
+
+impl fmt::Display for FailureExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(failure) = &self.failure else {
+            return writeln!(f, "The query succeeded");
+        };
+        writeln!(
+            f,
+            "The query failed in {}: {}",
+            failure.stage, failure.error
+        )?;
+        if failure.stage != FailureStage::Execution {
+            return Ok(());
+        }
+        if self.files.is_empty() {
+            return writeln!(
+                f,
+                "\nIt succeeds over every file alone: the failure comes from combining them"
+            );
+        }
+        for file in &self.files {
+            writeln!(f, "\nfile {}\n  {}", file.path, file.error)?;
+            if file.columns.is_empty() {
+                writeln!(
+                    f,
+                    "  every column reads alone; the query's own expressions over them fail"
+                )?;
+            }
+            for column in &file.columns {
+                writeln!(f, "  column '{}': {}", column.column, column.step)?;
+                writeln!(f, "    {}", column.error)?;
+            }
+        }
+        Ok(())
+    }
+}
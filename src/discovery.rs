@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use arrow::datatypes::SchemaRef;
+use datafusion::catalog::Session;
+use datafusion::common::Result;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use datafusion::object_store::{ObjectMeta, ObjectStore};
+use futures::{StreamExt, TryStreamExt, future};
+use tokio_util::sync::CancellationToken;
+
+use crate::append::name_fingerprint;
+use crate::cancel::cancellable;
+use crate::format::FileContext;
+use crate::notify::FileEvent;
+
+/// Reads the schemas of a dataset's files from their footers.
+///
+/// Footers are fetched a bounded number at a time, served from a
+/// [`SchemaCache`] when the file has not changed since it was last read, and
+/// optionally sampled: once enough consecutive files agree on a schema, the
+/// remaining files are assumed to share it and are not read.
+///
+/// ```ignore
+/// let cache = SchemaCache::new();
+/// let discovery = SchemaDiscovery::new()
+///     .with_concurrency(64)
+///     .with_agreement(100)
+///     .with_cache(cache.clone());
+/// let unified = SchemaUnifier::new()
+///     .with_discovery(discovery)
+///     .unify_url(&state, &table_url, &listing_options)
+///     .await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemaDiscovery {
+    concurrency: Option<usize>,
+    agreement: Option<usize>,
+    cache: Option<SchemaCache>,
+    name_fingerprints: bool,
+    paths: Vec<glob::Pattern>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl SchemaDiscovery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch at most `concurrency` footers at a time; defaults to the
+    /// session's `meta_fetch_concurrency`.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency.max(1));
+        self
+    }
+
+    /// Stop reading footers once `files` consecutive files have the same
+    /// schema, visiting the most recently modified files first.
+    ///
+    /// Files that are not read do not take part in unification, so a schema
+    /// change among them is only noticed when a scan reaches them: added and
+    /// widened columns are still adapted, but a conflict fails the query
+    /// rather than the registration.
+    pub fn with_agreement(mut self, files: usize) -> Self {
+        self.agreement = Some(files.max(1));
+        self
+    }
+
+    /// Trust the schema fingerprints in file names written by an
+    /// [`AppendProtocol`](crate::append::AppendProtocol): one footer is read per
+    /// fingerprint, and checked against it, instead of one per file. Takes
+    /// precedence over [`Self::with_agreement`].
+    pub fn with_name_fingerprints(mut self) -> Self {
+        self.name_fingerprints = true;
+        self
+    }
+
+    /// Only read the files whose path relative to the table matches one of
+    /// the globs, e.g. `dt=2024-06-*` for the partitions a query touches;
+    /// the others are neither read nor reported.
+    pub fn with_paths(mut self, pattern: glob::Pattern) -> Self {
+        self.paths.push(pattern);
+        self
+    }
+
+    pub fn with_cache(mut self, cache: SchemaCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn cache(&self) -> Option<&SchemaCache> {
+        self.cache.as_ref()
+    }
+
+    /// Stop listing and reading footers once `token` is cancelled, failing
+    /// the discovery. Dropping the discovery's future stops it as well.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// List the files under `table_url` and read their schemas with the format
+    /// in `options`.
+    pub async fn discover(
+        &self,
+        state: &dyn Session,
+        table_url: &ListingTableUrl,
+        options: &ListingOptions,
+    ) -> Result<DiscoveredSchemas> {
+        cancellable(
+            self.cancellation.as_ref(),
+            "Schema discovery",
+            self.discover_files(state, table_url, options),
+        )
+        .await
+    }
+
+    async fn discover_files(
+        &self,
+        state: &dyn Session,
+        table_url: &ListingTableUrl,
+        options: &ListingOptions,
+    ) -> Result<DiscoveredSchemas> {
+        let store = state.runtime_env().object_store(table_url)?;
+        let mut objects: Vec<_> = table_url
+            .list_all_files(state, store.as_ref(), &options.file_extension)
+            .await?
+            // Empty files cannot affect the schema but fail to parse
+            .try_filter(|object| future::ready(object.size > 0))
+            .try_collect()
+            .await?;
+        if !self.paths.is_empty() {
+            let prefix = table_url.prefix().as_ref();
+            objects.retain(|object| {
+                let path = object.location.as_ref();
+                let relative = path
+                    .strip_prefix(prefix)
+                    .unwrap_or(path)
+                    .trim_start_matches('/');
+                self.paths.iter().any(|pattern| pattern.matches(relative))
+            });
+        }
+
+        if self.agreement.is_some() {
+            // The newest files are the likeliest to carry a schema change
+            objects.sort_by(|left, right| {
+                right
+                    .last_modified
+                    .cmp(&left.last_modified)
+                    .then_with(|| left.location.cmp(&right.location))
+            });
+        }
+
+        let concurrency = self
+            .concurrency
+            .unwrap_or(state.config_options().execution.meta_fetch_concurrency);
+        if self.name_fingerprints {
+            return self
+                .discover_by_name(state, &store, options, objects, concurrency)
+                .await;
+        }
+        let reads = futures::stream::iter(objects.iter().cloned())
+            .map(|object| self.read(state, &store, options, object));
+
+        let Some(agreement) = self.agreement else {
+            let files = reads.buffer_unordered(concurrency).try_collect().await?;
+            return Ok(DiscoveredSchemas {
+                files,
+                skipped: Vec::new(),
+            });
+        };
+
+        // Read in order, so that the files after the agreeing run are the ones
+        // skipped; dropping the stream cancels the reads still in flight
+        let mut reads = std::pin::pin!(reads.buffered(concurrency));
+        let mut files: Vec<(FileContext, SchemaRef)> = Vec::new();
+        let mut run = 0;
+        while let Some((file, schema)) = reads.try_next().await? {
+            run = match files.last() {
+                Some((_, previous)) if previous.fields() == schema.fields() => run + 1,
+                _ => 1,
+            };
+            files.push((file, schema));
+            if run >= agreement {
+                break;
+            }
+        }
+        let skipped = objects[files.len()..]
+            .iter()
+            .map(FileContext::from)
+            .collect();
+        Ok(DiscoveredSchemas { files, skipped })
+    }
+
+    /// Read one footer per fingerprint the file names carry, and those of the
+    /// files without one or whose fingerprint a footer did not confirm.
+    async fn discover_by_name(
+        &self,
+        state: &dyn Session,
+        store: &Arc<dyn ObjectStore>,
+        options: &ListingOptions,
+        objects: Vec<ObjectMeta>,
+        concurrency: usize,
+    ) -> Result<DiscoveredSchemas> {
+        let mut first = HashSet::new();
+        let (read, named): (Vec<_>, Vec<_>) = objects.into_iter().partition(|object| {
+            name_fingerprint(object.location.as_ref()).is_none_or(|fp| first.insert(fp))
+        });
+        let mut files: Vec<(FileContext, SchemaRef)> = futures::stream::iter(read)
+            .map(|object| self.read(state, store, options, object))
+            .buffer_unordered(concurrency)
+            .try_collect()
+            .await?;
+
+        let confirmed: HashMap<_, _> = files
+            .iter()
+            .filter_map(|(file, schema)| {
+                let fingerprint = name_fingerprint(&file.path)?;
+                fingerprint
+                    .matches(schema)
+                    .then(|| (fingerprint, Arc::clone(schema)))
+            })
+            .collect();
+        let mut unconfirmed = Vec::new();
+        for object in named {
+            match name_fingerprint(object.location.as_ref())
+                .and_then(|fingerprint| confirmed.get(&fingerprint))
+            {
+                Some(schema) => files.push((FileContext::from(&object), Arc::clone(schema))),
+                None => unconfirmed.push(object),
+            }
+        }
+        let unconfirmed: Vec<_> = futures::stream::iter(unconfirmed)
+            .map(|object| self.read(state, store, options, object))
+            .buffer_unordered(concurrency)
+            .try_collect()
+            .await?;
+        files.extend(unconfirmed);
+        Ok(DiscoveredSchemas {
+            files,
+            skipped: Vec::new(),
+        })
+    }
+
+    /// Read the schema of one file, or take it from the cache.
+    async fn read(
+        &self,
+        state: &dyn Session,
+        store: &Arc<dyn ObjectStore>,
+        options: &ListingOptions,
+        object: ObjectMeta,
+    ) -> Result<(FileContext, SchemaRef)> {
+        if let Some(schema) = self.cache.as_ref().and_then(|cache| cache.get(&object)) {
+            return Ok((FileContext::from(&object), schema));
+        }
+        let schema = options
+            .format
+            .infer_schema(state, store, std::slice::from_ref(&object))
+            .await?;
+        if let Some(cache) = &self.cache {
+            cache.insert(&object, Arc::clone(&schema));
+        }
+        Ok((FileContext::from(&object), schema))
+    }
+}
+
+/// The file schemas read by [`SchemaDiscovery::discover`].
+#[derive(Debug, Clone)]
+pub struct DiscoveredSchemas {
+    pub files: Vec<(FileContext, SchemaRef)>,
+    /// Files not read because the files before them agreed on a schema.
+    pub skipped: Vec<FileContext>,
+}
+
+/// File schemas already read from their footers, shared between clones.
+///
+/// An entry is keyed by the file's path and entity tag, or its size and
+/// modification time when the store has no entity tags, so a rewritten file is
+/// read again. Registering the same table twice with one cache only fetches
+/// the footers of new and changed files.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaCache {
+    entries: Arc<RwLock<HashMap<CacheKey, SchemaRef>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    path: String,
+    version: String,
+}
+
+impl From<&ObjectMeta> for CacheKey {
+    fn from(object: &ObjectMeta) -> Self {
+        Self {
+            path: object.location.to_string(),
+            version: object_version(object),
+        }
+    }
+}
+
+/// The version of a file: its entity tag, or its size and modification time
+/// when the store has no entity tags.
+pub(crate) fn object_version(object: &ObjectMeta) -> String {
+    match &object.e_tag {
+        Some(e_tag) => e_tag.clone(),
+        None => format!(
+            "{}:{}",
+            object.size,
+            object.last_modified.timestamp_micros()
+        ),
+    }
+}
+
+impl SchemaCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached schema of `object`, if this version of it was read before.
+    pub fn get(&self, object: &ObjectMeta) -> Option<SchemaRef> {
+        self.entries
+            .read()
+            .unwrap()
+            .get(&CacheKey::from(object))
+            .cloned()
+    }
+
+    pub fn insert(&self, object: &ObjectMeta, schema: SchemaRef) {
+        self.entries
+            .write()
+            .unwrap()
+            .insert(CacheKey::from(object), schema);
+    }
+
+    /// Forget every version of the file at `path`.
+    pub fn remove(&self, path: &str) {
+        self.entries
+            .write()
+            .unwrap()
+            .retain(|key, _| key.path != path);
+    }
+
+    /// Keep the cache current from a notification: removed files are
+    /// forgotten, while created ones are read when next discovered.
+    pub fn apply(&self, event: &FileEvent) {
+        if let FileEvent::Removed { location, .. } = event {
+            self.remove(location.as_ref());
+        }
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
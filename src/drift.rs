@@ -0,0 +1,598 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use arrow::datatypes::{DataType, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::common::{DataFusionError, Result, ScalarValue, Statistics};
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use futures::{StreamExt, TryStreamExt, future};
+
+use crate::discovery::SchemaDiscovery;
+use crate::fingerprint::SchemaFingerprint;
+use crate::format::FileContext;
+use crate::merge::{Deviation, SchemaUnifier, UnifiedSchema};
+use crate::policy::{CoercionMode, CoercionPolicy};
+
+/// How the schemas of a dataset's files drifted apart: every type and
+/// nullability each column was stored with, and the unified schema and
+/// conflicting files under each [`CoercionMode`].
+///
+/// ```ignore
+/// let report = DriftReport::from_url(&ctx.state(), &table_url, &listing_options).await?;
+/// println!("{report}");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DriftReport {
+    pub files: Vec<String>,
+    /// In the order the columns first appear, visiting files in path order.
+    pub columns: Vec<ColumnHistory>,
+    /// The result of unifying the files under each mode, strictest first.
+    pub unified: Vec<(CoercionMode, UnifiedSchema)>,
+    /// How the values changed, if [`Self::with_statistics`] was given them.
+    pub statistics: Option<StatisticsDrift>,
+}
+
+/// The ways one column was stored across the files of a dataset.
+#[derive(Debug, Clone)]
+pub struct ColumnHistory {
+    pub name: String,
+    /// In the order they first appear.
+    pub versions: Vec<ColumnVersion>,
+    /// The files without the column.
+    pub missing: Vec<String>,
+}
+
+/// One type and nullability a column was stored with, and the files storing it
+/// so.
+#[derive(Debug, Clone)]
+pub struct ColumnVersion {
+    pub data_type: DataType,
+    pub nullable: bool,
+    pub files: Vec<String>,
+}
+
+impl DriftReport {
+    /// Read the schema of every file under `table_url` with the format in
+    /// `options` and report on them.
+    pub async fn from_url(
+        state: &dyn Session,
+        table_url: &ListingTableUrl,
+        options: &ListingOptions,
+    ) -> Result<Self> {
+        let discovered = SchemaDiscovery::new()
+            .discover(state, table_url, options)
+            .await?;
+        Ok(Self::new(
+            discovered
+                .files
+                .into_iter()
+                .map(|(file, schema)| (file.path, schema))
+                .collect(),
+        ))
+    }
+
+    /// Report on already known file schemas, given as `(path, schema)` pairs.
+    pub fn new(mut files: Vec<(String, SchemaRef)>) -> Self {
+        files.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        let mut columns: Vec<ColumnHistory> = Vec::new();
+        for (path, schema) in &files {
+            for field in schema.fields() {
+                let history = match columns.iter_mut().position(|c| c.name == *field.name()) {
+                    Some(position) => &mut columns[position],
+                    None => {
+                        columns.push(ColumnHistory {
+                            name: field.name().clone(),
+                            versions: Vec::new(),
+                            missing: Vec::new(),
+                        });
+                        columns.last_mut().unwrap()
+                    }
+                };
+                match history.versions.iter_mut().find(|version| {
+                    version.data_type == *field.data_type()
+                        && version.nullable == field.is_nullable()
+                }) {
+                    Some(version) => version.files.push(path.clone()),
+                    None => history.versions.push(ColumnVersion {
+                        data_type: field.data_type().clone(),
+                        nullable: field.is_nullable(),
+                        files: vec![path.clone()],
+                    }),
+                }
+            }
+        }
+        for history in &mut columns {
+            history.missing = files
+                .iter()
+                .filter(|(_, schema)| schema.field_with_name(&history.name).is_err())
+                .map(|(path, _)| path.clone())
+                .collect();
+        }
+
+        let unified = [
+            CoercionMode::Strict,
+            CoercionMode::Widening,
+            CoercionMode::Lenient,
+        ]
+        .into_iter()
+        .map(|mode| {
+            let unifier = SchemaUnifier::new().with_policy(CoercionPolicy::new(mode));
+            (mode, unifier.unify(files.clone()))
+        })
+        .collect();
+
+        Self {
+            files: files.into_iter().map(|(path, _)| path).collect(),
+            columns,
+            unified,
+            statistics: None,
+        }
+    }
+
+    /// Also report how the column statistics changed.
+    pub fn with_statistics(mut self, statistics: StatisticsDrift) -> Self {
+        self.statistics = Some(statistics);
+        self
+    }
+
+    /// Columns stored with more than one type or nullability, or missing from
+    /// some files.
+    pub fn drifted_columns(&self) -> impl Iterator<Item = &ColumnHistory> {
+        self.columns
+            .iter()
+            .filter(|column| column.versions.len() > 1 || !column.missing.is_empty())
+    }
+
+    /// The unified schema of the strictest mode every file can be adapted
+    /// under, if any.
+    pub fn suggested(&self) -> Option<&(CoercionMode, UnifiedSchema)> {
+        self.unified
+            .iter()
+            .find(|(_, unified)| unified.report.is_compatible())
+    }
+}
+
+impl ColumnHistory {
+    /// Whether the files store the column with different types.
+    pub fn type_changed(&self) -> bool {
+        self.versions
+            .iter()
+            .any(|version| version.data_type != self.versions[0].data_type)
+    }
+
+    /// Whether some files store the column as nullable and others do not.
+    pub fn nullability_changed(&self) -> bool {
+        self.versions
+            .iter()
+            .any(|version| version.nullable != self.versions[0].nullable)
+    }
+}
+
+impl fmt::Display for DriftReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} files, {} columns, {} drifted",
+            self.files.len(),
+            self.columns.len(),
+            self.drifted_columns().count()
+        )?;
+
+        for column in self.drifted_columns() {
+            writeln!(f, "\ncolumn '{}'", column.name)?;
+            for version in &column.versions {
+                let nullable = if version.nullable {
+                    "nullable"
+                } else {
+                    "not null"
+                };
+                writeln!(
+                    f,
+                    "  {} {nullable} in {}",
+                    version.data_type,
+                    FileList(&version.files)
+                )?;
+            }
+            if !column.missing.is_empty() {
+                writeln!(f, "  missing from {}", FileList(&column.missing))?;
+            }
+        }
+
+        writeln!(f)?;
+        for (mode, unified) in &self.unified {
+            let failing: Vec<_> = unified
+                .report
+                .files
+                .iter()
+                .filter(|file| {
+                    file.deviations
+                        .iter()
+                        .any(|deviation| matches!(deviation, Deviation::Conflict(_)))
+                })
+                .collect();
+            if failing.is_empty() {
+                writeln!(f, "{mode}: every file is compatible")?;
+                continue;
+            }
+            writeln!(f, "{mode}: {} files fail", failing.len())?;
+            for err in unified.report.conflicts() {
+                writeln!(f, "  {err}")?;
+            }
+        }
+
+        match self.suggested() {
+            Some((mode, unified)) => {
+                writeln!(f, "\nsuggested schema ({mode}):")?;
+                for field in unified.schema.fields() {
+                    let nullable = if field.is_nullable() { "" } else { " not null" };
+                    writeln!(f, "  {}: {}{nullable}", field.name(), field.data_type())?;
+                }
+            }
+            None => writeln!(f, "\nno coercion mode reconciles every file")?,
+        }
+
+        if let Some(statistics) = &self.statistics {
+            write!(f, "\n{statistics}")?;
+        }
+        Ok(())
+    }
+}
+
+/// How far the statistics of a column may move between generations before a
+/// [`StatisticsDrift`] alerts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatisticsThresholds {
+    /// The change of the fraction of null values, e.g. `0.1` for ten points.
+    pub null_rate: f64,
+    /// How far the min or max of a numeric column may move, as a multiple of
+    /// the earlier generation's range.
+    pub range_shift: f64,
+}
+
+impl Default for StatisticsThresholds {
+    fn default() -> Self {
+        Self {
+            null_rate: 0.1,
+            range_shift: 1.0,
+        }
+    }
+}
+
+impl StatisticsThresholds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_null_rate(mut self, null_rate: f64) -> Self {
+        self.null_rate = null_rate;
+        self
+    }
+
+    pub fn with_range_shift(mut self, range_shift: f64) -> Self {
+        self.range_shift = range_shift;
+        self
+    }
+}
+
+/// How the values of a dataset's columns changed between generations of
+/// files, read from the statistics in the file footers.
+///
+/// A generation is the files sharing a schema fingerprint, ordered by the
+/// modification time of their oldest file, since a writer that changes a
+/// schema often changes what it writes too: a column that starts being null,
+/// or milliseconds that became seconds under the same type. Consecutive
+/// generations are compared column by column.
+///
+/// ```ignore
+/// let statistics = StatisticsDrift::from_url(
+///     &ctx.state(), &table_url, &listing_options, StatisticsThresholds::new(),
+/// )
+/// .await?;
+/// for alert in &statistics.alerts {
+///     println!("{alert}");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatisticsDrift {
+    pub generations: Vec<Generation>,
+    pub alerts: Vec<StatisticsAlert>,
+}
+
+/// The files of a dataset written with one schema.
+#[derive(Debug, Clone)]
+pub struct Generation {
+    pub fingerprint: SchemaFingerprint,
+    /// In path order.
+    pub files: Vec<String>,
+    /// In the order of the schema.
+    pub columns: Vec<ColumnStatistics>,
+}
+
+/// The statistics of one column over the files of a [`Generation`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    pub name: String,
+    /// The fraction of null values, if every file has row and null counts.
+    pub null_rate: Option<f64>,
+    /// The min and max of a numeric column, if every non-empty file has them.
+    pub range: Option<(f64, f64)>,
+}
+
+/// A column whose statistics moved more than the [`StatisticsThresholds`]
+/// allow between a generation and the one before it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatisticsAlert {
+    NullRate {
+        column: String,
+        /// The index of the later generation.
+        generation: usize,
+        before: f64,
+        after: f64,
+    },
+    RangeShift {
+        column: String,
+        /// The index of the later generation.
+        generation: usize,
+        before: (f64, f64),
+        after: (f64, f64),
+    },
+}
+
+impl StatisticsDrift {
+    /// Read the schema and statistics of every file under `table_url` with
+    /// the format in `options` and compare the generations.
+    pub async fn from_url(
+        state: &dyn Session,
+        table_url: &ListingTableUrl,
+        options: &ListingOptions,
+        thresholds: StatisticsThresholds,
+    ) -> Result<Self> {
+        let store = state.runtime_env().object_store(table_url)?;
+        let objects: Vec<_> = table_url
+            .list_all_files(state, store.as_ref(), &options.file_extension)
+            .await?
+            .try_filter(|object| future::ready(object.size > 0))
+            .try_collect()
+            .await?;
+        let files = futures::stream::iter(objects)
+            .map(|object| {
+                let store = Arc::clone(&store);
+                let format = Arc::clone(&options.format);
+                async move {
+                    let schema = format
+                        .infer_schema(state, &store, std::slice::from_ref(&object))
+                        .await?;
+                    let statistics = format
+                        .infer_stats(state, &store, Arc::clone(&schema), &object)
+                        .await?;
+                    Ok::<_, DataFusionError>((FileContext::from(&object), schema, statistics))
+                }
+            })
+            .buffer_unordered(state.config_options().execution.meta_fetch_concurrency)
+            .try_collect()
+            .await?;
+        Ok(Self::new(files, thresholds))
+    }
+
+    /// Compare already known file statistics, each over its file's schema.
+    pub fn new(
+        files: Vec<(FileContext, SchemaRef, Statistics)>,
+        thresholds: StatisticsThresholds,
+    ) -> Self {
+        struct Group {
+            fingerprint: SchemaFingerprint,
+            schema: SchemaRef,
+            oldest: Option<SystemTime>,
+            files: Vec<(String, SchemaRef, Statistics)>,
+        }
+        let mut groups: Vec<Group> = Vec::new();
+        let mut positions = HashMap::new();
+        for (file, schema, statistics) in files {
+            let fingerprint = SchemaFingerprint::of(&schema);
+            let position = *positions.entry(fingerprint).or_insert_with(|| {
+                groups.push(Group {
+                    fingerprint,
+                    schema: Arc::clone(&schema),
+                    oldest: file.last_modified,
+                    files: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            let group = &mut groups[position];
+            group.oldest = group.oldest.min(file.last_modified);
+            group.files.push((file.path, schema, statistics));
+        }
+        groups.sort_by(|left, right| left.oldest.cmp(&right.oldest));
+
+        let generations: Vec<Generation> = groups
+            .into_iter()
+            .map(|mut group| {
+                group.files.sort_by(|left, right| left.0.cmp(&right.0));
+                let columns = group
+                    .schema
+                    .fields()
+                    .iter()
+                    .map(|field| column_statistics(field.name(), field.data_type(), &group.files))
+                    .collect();
+                Generation {
+                    fingerprint: group.fingerprint,
+                    files: group.files.into_iter().map(|(path, ..)| path).collect(),
+                    columns,
+                }
+            })
+            .collect();
+
+        let mut alerts = Vec::new();
+        for (generation, pair) in generations.windows(2).enumerate() {
+            let generation = generation + 1;
+            for after in &pair[1].columns {
+                let Some(before) = pair[0].columns.iter().find(|c| c.name == after.name) else {
+                    continue;
+                };
+                if let (Some(before), Some(after_rate)) = (before.null_rate, after.null_rate)
+                    && (after_rate - before).abs() > thresholds.null_rate
+                {
+                    alerts.push(StatisticsAlert::NullRate {
+                        column: after.name.clone(),
+                        generation,
+                        before,
+                        after: after_rate,
+                    });
+                }
+                if let (Some(before), Some(after_range)) = (before.range, after.range) {
+                    let allowed =
+                        (before.1 - before.0).max(f64::MIN_POSITIVE) * thresholds.range_shift;
+                    if (after_range.0 - before.0).abs() > allowed
+                        || (after_range.1 - before.1).abs() > allowed
+                    {
+                        alerts.push(StatisticsAlert::RangeShift {
+                            column: after.name.clone(),
+                            generation,
+                            before,
+                            after: after_range,
+                        });
+                    }
+                }
+            }
+        }
+        Self {
+            generations,
+            alerts,
+        }
+    }
+}
+
+/// Sum the null counts and widen the min/max of one column over the files of
+/// a generation.
+fn column_statistics(
+    name: &str,
+    data_type: &DataType,
+    files: &[(String, SchemaRef, Statistics)],
+) -> ColumnStatistics {
+    let (mut rows, mut nulls) = (Some(0), Some(0));
+    let mut range: Option<Option<(f64, f64)>> = data_type.is_numeric().then_some(None);
+    for (_, schema, statistics) in files {
+        let column = schema
+            .index_of(name)
+            .ok()
+            .and_then(|index| statistics.column_statistics.get(index));
+        let file_rows = statistics.num_rows.get_value().copied();
+        rows = rows
+            .zip(file_rows)
+            .map(|(rows, file_rows)| rows + file_rows);
+        nulls = nulls
+            .zip(column.and_then(|column| column.null_count.get_value().copied()))
+            .map(|(nulls, file_nulls)| nulls + file_nulls);
+        if file_rows == Some(0) {
+            continue;
+        }
+        let bounds = column.and_then(|column| {
+            Some((
+                as_f64(column.min_value.get_value()?)?,
+                as_f64(column.max_value.get_value()?)?,
+            ))
+        });
+        range = match (range, bounds) {
+            (Some(None), Some(bounds)) => Some(Some(bounds)),
+            (Some(Some((min, max))), Some(bounds)) => {
+                Some(Some((min.min(bounds.0), max.max(bounds.1))))
+            }
+            _ => None,
+        };
+    }
+    ColumnStatistics {
+        name: name.to_string(),
+        null_rate: rows
+            .zip(nulls)
+            .filter(|(rows, _)| *rows > 0)
+            .map(|(rows, nulls)| nulls as f64 / rows as f64),
+        range: range.flatten(),
+    }
+}
+
+fn as_f64(value: &ScalarValue) -> Option<f64> {
+    match value.cast_to(&DataType::Float64).ok()? {
+        ScalarValue::Float64(Some(value)) if value.is_finite() => Some(value),
+        _ => None,
+    }
+}
+
+impl fmt::Display for StatisticsDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} generations, {} statistics alerts",
+            self.generations.len(),
+            self.alerts.len()
+        )?;
+        for alert in &self.alerts {
+            let generation = match alert {
+                StatisticsAlert::NullRate { generation, .. }
+                | StatisticsAlert::RangeShift { generation, .. } => *generation,
+            };
+            writeln!(f, "  {alert}")?;
+            writeln!(
+                f,
+                "    from {}",
+                FileList(&self.generations[generation].files)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for StatisticsAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NullRate {
+                column,
+                generation,
+                before,
+                after,
+            } => write!(
+                f,
+                "column '{column}': null rate {:.1}% -> {:.1}% in generation {}",
+                before * 100.0,
+                after * 100.0,
+                generation + 1
+            ),
+            Self::RangeShift {
+                column,
+                generation,
+                before,
+                after,
+            } => write!(
+                f,
+                "column '{column}': range [{}, {}] -> [{}, {}] in generation {}",
+                before.0,
+                before.1,
+                after.0,
+                after.1,
+                generation + 1
+            ),
+        }
+    }
+}
+
+/// Formats a few paths of a list and how many more there are.
+struct FileList<'a>(&'a [String]);
+
+impl fmt::Display for FileList<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const SHOWN: usize = 3;
+        let noun = if self.0.len() == 1 { "file" } else { "files" };
+        write!(
+            f,
+            "{} {noun}: {}",
+            self.0.len(),
+            self.0[..self.0.len().min(SHOWN)].join(", ")
+        )?;
+        if self.0.len() > SHOWN {
+            write!(f, ", ... ({} more)", self.0.len() - SHOWN)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use arrow::datatypes::DataType;
+
+use crate::policy::is_same_logical_type;
+
+/// The type [`SchemaUnifier`](crate::SchemaUnifier) gives a string or binary
+/// column whose files store it in different encodings, e.g. `Utf8` in older
+/// files and `Dictionary(Int32, Utf8)` or `Utf8View` in newer ones.
+///
+/// The encoding decides what reading each file costs: Arrow turns plain
+/// arrays and dictionaries into views into their existing buffers, and only
+/// re-keys dictionaries, but materializes every value when a dictionary or a
+/// view becomes a plain array.
+///
+/// ```ignore
+/// let unifier = SchemaUnifier::new().with_string_encoding(StringEncoding::View);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StringEncoding {
+    /// `Utf8` or `Binary`; dictionaries and views are materialized.
+    #[default]
+    Plain,
+    /// `Utf8View` or `BinaryView`, which no file's values are copied into.
+    View,
+    /// `Dictionary(Int32, Utf8)` or `Dictionary(Int32, Binary)` if any file
+    /// stores a dictionary: other dictionaries are re-keyed and plain arrays
+    /// are dictionary-encoded. Columns no file stores as a dictionary stay
+    /// plain.
+    Dictionary,
+}
+
+impl StringEncoding {
+    /// The table type of a column unified to `data_type` from files storing it
+    /// as `file_types`. Only top-level columns whose files all store the same
+    /// strings or bytes in different encodings are changed.
+    pub fn apply(&self, data_type: &DataType, file_types: &[&DataType]) -> DataType {
+        let plain = match data_type {
+            DataType::Utf8 | DataType::Binary => data_type,
+            _ => return data_type.clone(),
+        };
+        let mixed = file_types
+            .iter()
+            .any(|file_type| *file_type != file_types[0]);
+        if !mixed
+            || !file_types
+                .iter()
+                .all(|file_type| is_same_logical_type(file_type, plain))
+        {
+            return data_type.clone();
+        }
+
+        match self {
+            Self::Plain => data_type.clone(),
+            Self::View if *plain == DataType::Utf8 => DataType::Utf8View,
+            Self::View => DataType::BinaryView,
+            Self::Dictionary
+                if file_types
+                    .iter()
+                    .any(|file_type| matches!(file_type, DataType::Dictionary(..))) =>
+            {
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(plain.clone()))
+            }
+            Self::Dictionary => data_type.clone(),
+        }
+    }
+}
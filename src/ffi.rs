@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use arrow::array::{RecordBatch, RecordBatchReader};
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::ffi_stream::FFI_ArrowArrayStream;
+use datafusion::common::Result;
+use datafusion::execution::SendableRecordBatchStream;
+use datafusion::prelude::DataFrame;
+use futures::StreamExt;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// Export the batches of `df`, e.g. a scan of a
+/// [`SchemaEvolutionTableProvider`](crate::SchemaEvolutionTableProvider), as
+/// an Arrow C stream, so that polars, pyarrow or DuckDB read the unified
+/// batches without going through SQL.
+///
+/// The scan runs on the current tokio runtime. The consumer may pull batches
+/// from any thread, including the workers of a multi-threaded runtime, but
+/// not from the thread of a current-thread runtime, which would have to wait
+/// on itself.
+///
+/// ```ignore
+/// let df = ctx.read_table(Arc::new(provider))?;
+/// let stream = Box::new(export_c_stream(df).await?);
+/// // hand the pointer to the consumer, which takes ownership of the stream
+/// let pointer = Box::into_raw(stream);
+/// ```
+pub async fn export_c_stream(df: DataFrame) -> Result<FFI_ArrowArrayStream> {
+    let stream = df.execute_stream().await?;
+    let reader = BlockingBatchReader::new(stream, Handle::current());
+    Ok(FFI_ArrowArrayStream::new(Box::new(reader)))
+}
+
+/// A [`RecordBatchReader`] over a DataFusion stream, polled on `runtime` as
+/// batches are pulled. Pulled from within a multi-threaded runtime, the
+/// worker is handed off with [`tokio::task::block_in_place`] while it waits.
+pub struct BlockingBatchReader {
+    schema: SchemaRef,
+    stream: SendableRecordBatchStream,
+    runtime: Handle,
+}
+
+impl BlockingBatchReader {
+    pub fn new(stream: SendableRecordBatchStream, runtime: Handle) -> Self {
+        Self {
+            schema: stream.schema(),
+            stream,
+            runtime,
+        }
+    }
+}
+
+impl Iterator for BlockingBatchReader {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.stream.next();
+        let batch = match Handle::try_current().map(|handle| handle.runtime_flavor()) {
+            Ok(RuntimeFlavor::MultiThread) => {
+                tokio::task::block_in_place(|| self.runtime.block_on(next))
+            }
+            _ => self.runtime.block_on(next),
+        }?;
+        Some(batch.map_err(ArrowError::from))
+    }
+}
+
+impl RecordBatchReader for BlockingBatchReader {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::Cursor;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use arrow::array::{ArrayRef, AsArray, RecordBatch, StringArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, UInt64Type};
+use arrow::ipc::CompressionType;
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::{IpcWriteOptions, StreamWriter};
+use datafusion::common::{DataFusionError, Result, config_err, exec_err};
+use datafusion::object_store::path::Path;
+use datafusion::object_store::{ObjectStore, PutPayload};
+use serde::{Deserialize, Serialize};
+
+/// Written last in an index file, after the footer and its length.
+const MAGIC: &[u8; 6] = b"SEVIDX";
+/// The layout version, written after [`MAGIC`].
+const VERSION: &[u8; 2] = b"01";
+/// The footer length, the magic and the version.
+const TAIL: u64 = 16;
+
+/// The version of an [`EvolutionManifest`](crate::manifest::EvolutionManifest) each
+/// file of a table was written with, for tables with too many files to list
+/// them in the manifest's JSON.
+///
+/// The entries are sorted by path and stored in chunks, each an Arrow IPC
+/// stream compressed with zstd, followed by a footer with the first and last
+/// path of every chunk. The index is read through an object store: opening it
+/// reads only the footer, and a chunk is read with a ranged request when a
+/// file in its range is [loaded](Self::load). [`EvolvingFormat`](crate::format::EvolvingFormat)
+/// loads the chunks of the files a scan plans before opening them, so a scan
+/// whose partition filter leaves few files only reads their chunks; lookups
+/// themselves never wait on the store.
+///
+/// ```ignore
+/// ManifestFileIndex::write(&store, &Path::from("events.index"), entries, 65_536).await?;
+/// let index = ManifestFileIndex::open(store, Path::from("events.index")).await?;
+/// let manifest = EvolutionManifest::load("events.json")?.with_file_index(Arc::new(index));
+/// ```
+#[derive(Debug)]
+pub struct ManifestFileIndex {
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+    chunks: Vec<ChunkJson>,
+    loaded: Mutex<HashMap<usize, Arc<[(String, u64)]>>>,
+}
+
+impl ManifestFileIndex {
+    /// Write `files`, as `(path, version)` pairs with paths relative to the
+    /// object store root, in chunks of `chunk_rows` entries.
+    pub async fn write(
+        store: &dyn ObjectStore,
+        location: &Path,
+        files: impl IntoIterator<Item = (String, u64)>,
+        chunk_rows: usize,
+    ) -> Result<()> {
+        let mut files: Vec<_> = files
+            .into_iter()
+            .map(|(path, version)| (path.trim_start_matches('/').to_string(), version))
+            .collect();
+        files.sort_by(|left, right| left.0.cmp(&right.0));
+        if let Some(pair) = files.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return config_err!("The file {} is listed twice", pair[0].0);
+        }
+
+        let options =
+            IpcWriteOptions::default().try_with_compression(Some(CompressionType::ZSTD))?;
+        let schema = Arc::new(entry_schema());
+        let mut out = Vec::new();
+        let mut footer = FooterJson { chunks: Vec::new() };
+        for chunk in files.chunks(chunk_rows.max(1)) {
+            let paths: ArrayRef = Arc::new(StringArray::from_iter_values(
+                chunk.iter().map(|(path, _)| path),
+            ));
+            let versions: ArrayRef = Arc::new(UInt64Array::from_iter_values(
+                chunk.iter().map(|(_, version)| *version),
+            ));
+            let batch = RecordBatch::try_new(Arc::clone(&schema), vec![paths, versions])?;
+            let mut writer =
+                StreamWriter::try_new_with_options(Vec::new(), &schema, options.clone())?;
+            writer.write(&batch)?;
+            let bytes = writer.into_inner()?;
+            footer.chunks.push(ChunkJson {
+                first: chunk[0].0.clone(),
+                last: chunk[chunk.len() - 1].0.clone(),
+                offset: out.len() as u64,
+                length: bytes.len() as u64,
+            });
+            out.extend_from_slice(&bytes);
+        }
+
+        let footer =
+            serde_json::to_vec(&footer).map_err(|err| DataFusionError::External(Box::new(err)))?;
+        out.extend_from_slice(&footer);
+        out.extend_from_slice(&(footer.len() as u64).to_le_bytes());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(VERSION);
+        store.put(location, PutPayload::from(out)).await?;
+        Ok(())
+    }
+
+    /// Read the footer of the index at `location` in `store`.
+    pub async fn open(store: Arc<dyn ObjectStore>, location: Path) -> Result<Self> {
+        let size = store.head(&location).await?.size;
+        if size < TAIL {
+            return config_err!("{location} is not a manifest file index");
+        }
+        let tail = store.get_range(&location, size - TAIL..size).await?;
+        if tail[8..14] != MAGIC[..] {
+            return config_err!("{location} is not a manifest file index");
+        }
+        if tail[14..] != VERSION[..] {
+            return config_err!(
+                "{location} is a manifest file index of version {}, which this version does not read",
+                String::from_utf8_lossy(&tail[14..])
+            );
+        }
+        let length = u64::from_le_bytes(tail[..8].try_into().unwrap());
+        if length > size - TAIL {
+            return config_err!("The footer of {location} is truncated");
+        }
+        let footer = store
+            .get_range(&location, size - TAIL - length..size - TAIL)
+            .await?;
+        let footer: FooterJson = match serde_json::from_slice(&footer) {
+            Ok(footer) => footer,
+            Err(err) => return config_err!("Invalid footer in {location}: {err}"),
+        };
+        Ok(Self {
+            store,
+            location,
+            chunks: footer.chunks,
+            loaded: Mutex::default(),
+        })
+    }
+
+    /// The version the file at `path` was written with, if it is indexed.
+    ///
+    /// Fails if the chunk the path is in was not [loaded](Self::load).
+    pub fn version_of(&self, path: &str) -> Result<Option<u64>> {
+        let path = path.trim_start_matches('/');
+        let Some(position) = self.position(path) else {
+            return Ok(None);
+        };
+        let Some(entries) = self.loaded.lock().unwrap().get(&position).cloned() else {
+            return exec_err!(
+                "Chunk {position} of the file index {} is not loaded; load {path} before planning it",
+                self.location
+            );
+        };
+        Ok(entries
+            .binary_search_by(|(entry, _)| entry.as_str().cmp(path))
+            .ok()
+            .map(|found| entries[found].1))
+    }
+
+    /// Read the chunks the files at `paths` are in, unless they already were.
+    pub async fn load<'a>(&self, paths: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let positions: BTreeSet<usize> = paths
+            .into_iter()
+            .filter_map(|path| self.position(path.trim_start_matches('/')))
+            .collect();
+        self.read_chunks(positions).await
+    }
+
+    /// Read the chunks that may hold paths starting with `prefix`, e.g. the
+    /// partition directory `events/dt=2024-06-` a query is restricted to,
+    /// ahead of the lookups. Returns how many chunks that is.
+    pub async fn prefetch(&self, prefix: &str) -> Result<usize> {
+        let prefix = prefix.trim_start_matches('/');
+        let positions: BTreeSet<usize> = self
+            .chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| {
+                chunk.last.as_str() >= prefix
+                    && (chunk.first.as_str() <= prefix || chunk.first.starts_with(prefix))
+            })
+            .map(|(position, _)| position)
+            .collect();
+        let count = positions.len();
+        self.read_chunks(positions).await?;
+        Ok(count)
+    }
+
+    /// The number of chunks in the index.
+    pub fn num_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// The number of chunks read so far.
+    pub fn loaded_chunks(&self) -> usize {
+        self.loaded.lock().unwrap().len()
+    }
+
+    /// The chunk whose path range holds `path`.
+    fn position(&self, path: &str) -> Option<usize> {
+        let position = self
+            .chunks
+            .partition_point(|chunk| chunk.last.as_str() < path);
+        self.chunks
+            .get(position)
+            .is_some_and(|chunk| chunk.first.as_str() <= path)
+            .then_some(position)
+    }
+
+    async fn read_chunks(&self, positions: BTreeSet<usize>) -> Result<()> {
+        let positions: Vec<usize> = {
+            let loaded = self.loaded.lock().unwrap();
+            positions
+                .into_iter()
+                .filter(|position| !loaded.contains_key(position))
+                .collect()
+        };
+        if positions.is_empty() {
+            return Ok(());
+        }
+        let ranges: Vec<Range<u64>> = positions
+            .iter()
+            .map(|&position| {
+                let chunk = &self.chunks[position];
+                chunk.offset..chunk.offset + chunk.length
+            })
+            .collect();
+        let chunks = self.store.get_ranges(&self.location, &ranges).await?;
+        for (position, bytes) in positions.into_iter().zip(chunks) {
+            let entries = self.decode(position, bytes.to_vec())?;
+            self.loaded.lock().unwrap().insert(position, entries);
+        }
+        Ok(())
+    }
+
+    fn decode(&self, position: usize, bytes: Vec<u8>) -> Result<Arc<[(String, u64)]>> {
+        let mut reader = StreamReader::try_new(Cursor::new(bytes), None)?;
+        let Some(batch) = reader.next().transpose()? else {
+            return config_err!("Chunk {position} of {} is empty", self.location);
+        };
+        if batch.schema().fields() != entry_schema().fields() {
+            return config_err!(
+                "Chunk {position} of {} has schema {}",
+                self.location,
+                batch.schema()
+            );
+        }
+        let paths = batch.column(0).as_string::<i32>();
+        let versions = batch.column(1).as_primitive::<UInt64Type>();
+        Ok(paths
+            .iter()
+            .zip(versions.values())
+            .map(|(path, version)| (path.unwrap_or_default().to_string(), *version))
+            .collect())
+    }
+}
+
+fn entry_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("path", DataType::Utf8, false),
+        Field::new("version", DataType::UInt64, false),
+    ])
+}
+
+#[derive(Serialize, Deserialize)]
+struct FooterJson {
+    chunks: Vec<ChunkJson>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChunkJson {
+    first: String,
+    last: String,
+    offset: u64,
+    length: u64,
+}
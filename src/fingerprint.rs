@@ -0,0 +1,362 @@
+use std::fmt;
+use std::str::FromStr;
+
+use arrow::datatypes::{DataType, Field, IntervalUnit, Schema, TimeUnit, UnionMode};
+
+use crate::canonical::{CanonicalizeOptions, canonicalize_with, sorted_metadata};
+
+/// Algorithm used to compute a [`SchemaFingerprint`].
+///
+/// Fingerprints are persisted next to data, so an algorithm is never changed once
+/// released: a new one is added and becomes [`FingerprintAlgorithm::CURRENT`],
+/// while older fingerprints keep verifying with the algorithm they were made with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FingerprintAlgorithm {
+    /// FNV-1a (64 bit) over a tagged encoding of the canonicalized schema.
+    V1,
+}
+
+impl FingerprintAlgorithm {
+    /// The algorithm used for newly computed fingerprints.
+    pub const CURRENT: Self = Self::V1;
+
+    /// Version number written in the textual form of a fingerprint.
+    pub fn version(self) -> u32 {
+        match self {
+            Self::V1 => 1,
+        }
+    }
+
+    /// Look up an algorithm by its version number.
+    pub fn from_version(version: u32) -> Option<Self> {
+        match version {
+            1 => Some(Self::V1),
+            _ => None,
+        }
+    }
+}
+
+/// A stable hash identifying a schema, tagged with the algorithm that produced it.
+///
+/// The textual form is `v<version>:<16 hex digits>`, e.g. `v1:8c3f0e2a96b1d4c7`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SchemaFingerprint {
+    algorithm: FingerprintAlgorithm,
+    hash: u64,
+}
+
+impl SchemaFingerprint {
+    /// Fingerprint `schema` with the current algorithm.
+    pub fn of(schema: &Schema) -> Self {
+        Self::with_algorithm(schema, FingerprintAlgorithm::CURRENT)
+    }
+
+    /// Fingerprint `schema` with a specific algorithm.
+    pub fn with_algorithm(schema: &Schema, algorithm: FingerprintAlgorithm) -> Self {
+        let hash = match algorithm {
+            FingerprintAlgorithm::V1 => {
+                let mut hasher = Fnv1a::new();
+                encode_schema_v1(
+                    &canonicalize_with(schema, &canonicalize_options_v1()),
+                    &mut hasher,
+                );
+                hasher.finish()
+            }
+        };
+        Self { algorithm, hash }
+    }
+
+    pub fn algorithm(&self) -> FingerprintAlgorithm {
+        self.algorithm
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Whether this fingerprint was computed with the current algorithm.
+    pub fn is_current(&self) -> bool {
+        self.algorithm == FingerprintAlgorithm::CURRENT
+    }
+
+    /// Whether `schema` has this fingerprint, recomputing with the algorithm this
+    /// fingerprint was made with rather than the current one.
+    pub fn matches(&self, schema: &Schema) -> bool {
+        Self::with_algorithm(schema, self.algorithm) == *self
+    }
+
+    /// Verify `schema` against this (possibly old) fingerprint and return the
+    /// fingerprint under the current algorithm, or `None` if the schema does not
+    /// match and the stored value must not be trusted.
+    pub fn upgrade(&self, schema: &Schema) -> Option<Self> {
+        if !self.matches(schema) {
+            return None;
+        }
+        if self.is_current() {
+            Some(*self)
+        } else {
+            Some(Self::of(schema))
+        }
+    }
+}
+
+impl fmt::Display for SchemaFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}:{:016x}", self.algorithm.version(), self.hash)
+    }
+}
+
+/// Error returned when parsing a [`SchemaFingerprint`] from text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFingerprintError(String);
+
+impl fmt::Display for ParseFingerprintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid schema fingerprint: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseFingerprintError {}
+
+impl FromStr for SchemaFingerprint {
+    type Err = ParseFingerprintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseFingerprintError(s.to_string());
+        let (version, hash) = s
+            .strip_prefix('v')
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or_else(err)?;
+        let version = version.parse::<u32>().map_err(|_| err())?;
+        let algorithm = FingerprintAlgorithm::from_version(version).ok_or_else(err)?;
+        let hash = u64::from_str_radix(hash, 16).map_err(|_| err())?;
+        Ok(Self { algorithm, hash })
+    }
+}
+
+/// 64 bit FNV-1a; chosen because its output is fixed by its definition, unlike
+/// `std`'s hashers which may change between Rust releases.
+pub(crate) struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub(crate) fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.write(&[value]);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Length-prefixed so that `("ab", "c")` and `("a", "bc")` differ.
+    pub(crate) fn write_str(&mut self, value: &str) {
+        self.write_u32(value.len() as u32);
+        self.write(value.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// The V1 encoding below is frozen: changing any tag, the order of writes or
+// the canonicalization options changes every persisted fingerprint. Add a new
+// algorithm instead.
+
+/// The canonicalization V1 fingerprints are computed over, spelled out rather
+/// than taken from [`CanonicalizeOptions::default`], whose volatile keys may
+/// grow.
+fn canonicalize_options_v1() -> CanonicalizeOptions {
+    CanonicalizeOptions {
+        normalize_strings: true,
+        normalize_binary: true,
+        normalize_lists: true,
+        unwrap_dictionaries: true,
+        normalize_list_field_names: true,
+        volatile_metadata_keys: [
+            "ARROW:schema",
+            "pandas",
+            "org.apache.spark.version",
+            "org.apache.spark.sql.parquet.row.metadata",
+        ]
+        .map(String::from)
+        .to_vec(),
+    }
+}
+
+fn encode_schema_v1(schema: &Schema, hasher: &mut Fnv1a) {
+    hasher.write_u32(schema.fields().len() as u32);
+    for field in schema.fields() {
+        encode_field_v1(field, hasher);
+    }
+    encode_metadata_v1(schema.metadata(), hasher);
+}
+
+fn encode_field_v1(field: &Field, hasher: &mut Fnv1a) {
+    hasher.write_str(field.name());
+    hasher.write_u8(field.is_nullable() as u8);
+    encode_type_v1(field.data_type(), hasher);
+    encode_metadata_v1(field.metadata(), hasher);
+}
+
+fn encode_metadata_v1(metadata: &std::collections::HashMap<String, String>, hasher: &mut Fnv1a) {
+    let sorted = sorted_metadata(metadata);
+    hasher.write_u32(sorted.len() as u32);
+    for (key, value) in sorted {
+        hasher.write_str(key);
+        hasher.write_str(value);
+    }
+}
+
+fn encode_type_v1(data_type: &DataType, hasher: &mut Fnv1a) {
+    match data_type {
+        DataType::Null => hasher.write_u8(0),
+        DataType::Boolean => hasher.write_u8(1),
+        DataType::Int8 => hasher.write_u8(2),
+        DataType::Int16 => hasher.write_u8(3),
+        DataType::Int32 => hasher.write_u8(4),
+        DataType::Int64 => hasher.write_u8(5),
+        DataType::UInt8 => hasher.write_u8(6),
+        DataType::UInt16 => hasher.write_u8(7),
+        DataType::UInt32 => hasher.write_u8(8),
+        DataType::UInt64 => hasher.write_u8(9),
+        DataType::Float16 => hasher.write_u8(10),
+        DataType::Float32 => hasher.write_u8(11),
+        DataType::Float64 => hasher.write_u8(12),
+        DataType::Timestamp(unit, tz) => {
+            hasher.write_u8(13);
+            encode_time_unit_v1(unit, hasher);
+            match tz {
+                Some(tz) => {
+                    hasher.write_u8(1);
+                    hasher.write_str(tz);
+                }
+                None => hasher.write_u8(0),
+            }
+        }
+        DataType::Date32 => hasher.write_u8(14),
+        DataType::Date64 => hasher.write_u8(15),
+        DataType::Time32(unit) => {
+            hasher.write_u8(16);
+            encode_time_unit_v1(unit, hasher);
+        }
+        DataType::Time64(unit) => {
+            hasher.write_u8(17);
+            encode_time_unit_v1(unit, hasher);
+        }
+        DataType::Duration(unit) => {
+            hasher.write_u8(18);
+            encode_time_unit_v1(unit, hasher);
+        }
+        DataType::Interval(unit) => {
+            hasher.write_u8(19);
+            hasher.write_u8(match unit {
+                IntervalUnit::YearMonth => 0,
+                IntervalUnit::DayTime => 1,
+                IntervalUnit::MonthDayNano => 2,
+            });
+        }
+        DataType::Binary => hasher.write_u8(20),
+        DataType::FixedSizeBinary(size) => {
+            hasher.write_u8(21);
+            hasher.write_i32(*size);
+        }
+        DataType::LargeBinary => hasher.write_u8(22),
+        DataType::BinaryView => hasher.write_u8(23),
+        DataType::Utf8 => hasher.write_u8(24),
+        DataType::LargeUtf8 => hasher.write_u8(25),
+        DataType::Utf8View => hasher.write_u8(26),
+        DataType::List(item) => {
+            hasher.write_u8(27);
+            encode_field_v1(item, hasher);
+        }
+        DataType::ListView(item) => {
+            hasher.write_u8(28);
+            encode_field_v1(item, hasher);
+        }
+        DataType::FixedSizeList(item, size) => {
+            hasher.write_u8(29);
+            encode_field_v1(item, hasher);
+            hasher.write_i32(*size);
+        }
+        DataType::LargeList(item) => {
+            hasher.write_u8(30);
+            encode_field_v1(item, hasher);
+        }
+        DataType::LargeListView(item) => {
+            hasher.write_u8(31);
+            encode_field_v1(item, hasher);
+        }
+        DataType::Struct(fields) => {
+            hasher.write_u8(32);
+            hasher.write_u32(fields.len() as u32);
+            for field in fields {
+                encode_field_v1(field, hasher);
+            }
+        }
+        DataType::Union(fields, mode) => {
+            hasher.write_u8(33);
+            hasher.write_u8(match mode {
+                UnionMode::Sparse => 0,
+                UnionMode::Dense => 1,
+            });
+            hasher.write_u32(fields.len() as u32);
+            for (type_id, field) in fields.iter() {
+                hasher.write(&type_id.to_le_bytes());
+                encode_field_v1(field, hasher);
+            }
+        }
+        DataType::Dictionary(key, value) => {
+            hasher.write_u8(34);
+            encode_type_v1(key, hasher);
+            encode_type_v1(value, hasher);
+        }
+        DataType::Decimal32(precision, scale) => encode_decimal_v1(35, *precision, *scale, hasher),
+        DataType::Decimal64(precision, scale) => encode_decimal_v1(36, *precision, *scale, hasher),
+        DataType::Decimal128(precision, scale) => encode_decimal_v1(37, *precision, *scale, hasher),
+        DataType::Decimal256(precision, scale) => encode_decimal_v1(38, *precision, *scale, hasher),
+        DataType::Map(entries, sorted) => {
+            hasher.write_u8(39);
+            encode_field_v1(entries, hasher);
+            hasher.write_u8(*sorted as u8);
+        }
+        DataType::RunEndEncoded(run_ends, values) => {
+            hasher.write_u8(40);
+            encode_field_v1(run_ends, hasher);
+            encode_field_v1(values, hasher);
+        }
+    }
+}
+
+fn encode_time_unit_v1(unit: &TimeUnit, hasher: &mut Fnv1a) {
+    hasher.write_u8(match unit {
+        TimeUnit::Second => 0,
+        TimeUnit::Millisecond => 1,
+        TimeUnit::Microsecond => 2,
+        TimeUnit::Nanosecond => 3,
+    });
+}
+
+fn encode_decimal_v1(tag: u8, precision: u8, scale: i8, hasher: &mut Fnv1a) {
+    hasher.write_u8(tag);
+    hasher.write_u8(precision);
+    hasher.write(&scale.to_le_bytes());
+}
@@ -0,0 +1,709 @@
+use std::any::Any;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use arrow::array::{Array, BooleanArray};
+use arrow::compute::kernels::zip::zip;
+use arrow::compute::{and, filter_record_batch, is_not_null};
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::catalog::Session;
+use datafusion::common::{DataFusionError, Result, Statistics, exec_err};
+use datafusion::config::ConfigOptions;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::datasource::listing::PartitionedFile;
+use datafusion::datasource::physical_plan::{
+    FileOpenFuture, FileOpener, FileScanConfig, FileScanConfigBuilder, FileSinkConfig, FileSource,
+};
+use datafusion::datasource::source::DataSourceExec;
+use datafusion::datasource::table_schema::TableSchema;
+use datafusion::object_store::{ObjectMeta, ObjectStore};
+use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::utils::collect_columns;
+use datafusion::physical_expr::{LexOrdering, LexRequirement, PhysicalExpr, PhysicalSortExpr};
+use datafusion::physical_plan::filter_pushdown::{FilterPushdownPropagation, PushedDown};
+use datafusion::physical_plan::metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder};
+use datafusion::physical_plan::projection::ProjectionExprs;
+use datafusion::physical_plan::sort_pushdown::SortOrderPushdownResult;
+use datafusion::physical_plan::{DisplayFormatType, ExecutionPlan};
+use futures::StreamExt;
+
+use crate::adapter::{SchemaEvolutionAdapterFactory, cast_array};
+use crate::limits::{MemoryLimits, evaluate_in_slices, is_parse_cast};
+use crate::policy::{Coercion, CoercionPolicy, NullViolation};
+use crate::row_id::{ROW_ID_COLUMN, RowIdGenerator};
+
+tokio::task_local! {
+    static CURRENT_FILE: FileContext;
+    static REDUCED_CAST_BATCHES: Count;
+}
+
+/// Count the file being opened in the scan's `files_with_reduced_cast_batches`
+/// metric, if opened through [`EvolvingFormat`].
+pub(crate) fn record_reduced_cast_batches() {
+    let _ = REDUCED_CAST_BATCHES.try_with(|count| count.add(1));
+}
+
+/// The file a scan is currently opening.
+///
+/// DataFusion creates the expression adapter of a file without saying which file
+/// it is; while a file is opened through [`EvolvingFormat`], the adapter can look
+/// it up with [`FileContext::current`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileContext {
+    /// The path relative to the object store root.
+    pub path: String,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl FileContext {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            last_modified: None,
+        }
+    }
+
+    pub fn with_last_modified(mut self, last_modified: SystemTime) -> Self {
+        self.last_modified = Some(last_modified);
+        self
+    }
+
+    /// The file being opened by the current task, if opened through
+    /// [`EvolvingFormat`].
+    pub fn current() -> Option<Self> {
+        CURRENT_FILE.try_with(Clone::clone).ok()
+    }
+}
+
+impl From<&ObjectMeta> for FileContext {
+    fn from(object: &ObjectMeta) -> Self {
+        Self::new(object.location.to_string()).with_last_modified(object.last_modified.into())
+    }
+}
+
+/// Wraps a [`FileFormat`] so that each file is opened with its [`FileContext`]
+/// set, which lets [`SchemaEvolutionAdapterFactory`](crate::adapter::SchemaEvolutionAdapterFactory)
+/// apply file-scoped settings such as path-prefixed renames.
+///
+/// File statistics are collected over each file's own schema and adapted to
+/// the table schema like the file's columns, so that they can be used to prune
+/// files and answer aggregates.
+///
+/// Batches the inner format's reader returns with other types than the table's
+/// are cast under the adapter factory's policy, so formats whose readers do not
+/// apply the adapted projection, or decode to their own encodings like
+/// `VortexFormat`, produce the same batches as Parquet.
+///
+/// ```ignore
+/// let format = EvolvingFormat::new(Arc::new(ParquetFormat::default()))
+///     .with_adapter_factory(adapter_factory.clone());
+/// let listing_options = ListingOptions::new(Arc::new(format));
+/// ```
+#[derive(Debug)]
+pub struct EvolvingFormat {
+    inner: Arc<dyn FileFormat>,
+    adapter_factory: SchemaEvolutionAdapterFactory,
+}
+
+impl EvolvingFormat {
+    pub fn new(inner: Arc<dyn FileFormat>) -> Self {
+        Self {
+            inner,
+            adapter_factory: SchemaEvolutionAdapterFactory::default(),
+        }
+    }
+
+    /// Adapt file statistics with the settings of the scan's adapter factory.
+    pub fn with_adapter_factory(mut self, adapter_factory: SchemaEvolutionAdapterFactory) -> Self {
+        self.adapter_factory = adapter_factory;
+        self
+    }
+
+    pub fn inner(&self) -> &Arc<dyn FileFormat> {
+        &self.inner
+    }
+
+    pub fn adapter_factory(&self) -> &SchemaEvolutionAdapterFactory {
+        &self.adapter_factory
+    }
+
+    /// Read the chunks of the manifest's file index that `objects` are in,
+    /// which the adapters look their versions up in without waiting.
+    async fn load_file_index<'a>(
+        &self,
+        objects: impl IntoIterator<Item = &'a ObjectMeta>,
+    ) -> Result<()> {
+        let Some(index) = self
+            .adapter_factory
+            .manifest()
+            .and_then(|manifest| manifest.file_index())
+        else {
+            return Ok(());
+        };
+        let paths: Vec<String> = objects
+            .into_iter()
+            .map(|object| object.location.to_string())
+            .collect();
+        index.load(paths.iter().map(String::as_str)).await
+    }
+}
+
+#[async_trait]
+impl FileFormat for EvolvingFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_ext(&self) -> String {
+        self.inner.get_ext()
+    }
+
+    fn get_ext_with_compression(
+        &self,
+        file_compression_type: &FileCompressionType,
+    ) -> Result<String> {
+        self.inner.get_ext_with_compression(file_compression_type)
+    }
+
+    fn compression_type(&self) -> Option<FileCompressionType> {
+        self.inner.compression_type()
+    }
+
+    async fn infer_schema(
+        &self,
+        state: &dyn Session,
+        store: &Arc<dyn ObjectStore>,
+        objects: &[ObjectMeta],
+    ) -> Result<SchemaRef> {
+        self.inner.infer_schema(state, store, objects).await
+    }
+
+    async fn infer_stats(
+        &self,
+        state: &dyn Session,
+        store: &Arc<dyn ObjectStore>,
+        table_schema: SchemaRef,
+        object: &ObjectMeta,
+    ) -> Result<Statistics> {
+        self.load_file_index([object]).await?;
+        // Inferring them over the table schema would read columns of another
+        // type, or another name, as missing or with min/max of the wrong order
+        let file_schema = self
+            .inner
+            .infer_schema(state, store, std::slice::from_ref(object))
+            .await?;
+        let statistics = self
+            .inner
+            .infer_stats(state, store, Arc::clone(&file_schema), object)
+            .await?;
+        Ok(self
+            .adapter_factory
+            .adapter(table_schema, file_schema, Some(FileContext::from(object)))
+            .adapt_statistics(statistics))
+    }
+
+    async fn create_physical_plan(
+        &self,
+        state: &dyn Session,
+        conf: FileScanConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // The inner format expects its own source, e.g. to configure a
+        // `ParquetSource`; wrap whatever it builds afterwards.
+        let source = match conf.file_source().as_any().downcast_ref::<EvolvingSource>() {
+            Some(source) => Arc::clone(&source.inner),
+            None => Arc::clone(conf.file_source()),
+        };
+        self.load_file_index(
+            conf.file_groups
+                .iter()
+                .flat_map(|group| group.iter())
+                .map(|file| &file.object_meta),
+        )
+        .await?;
+        let conf = FileScanConfigBuilder::from(conf)
+            .with_source(source)
+            .build();
+        let plan = self.inner.create_physical_plan(state, conf).await?;
+
+        let Some(config) = plan
+            .as_any()
+            .downcast_ref::<DataSourceExec>()
+            .and_then(|exec| exec.data_source().as_any().downcast_ref::<FileScanConfig>())
+        else {
+            return Ok(plan);
+        };
+        let source = EvolvingSource::wrap(
+            Arc::clone(config.file_source()),
+            self.adapter_factory.clone(),
+        );
+        let config = FileScanConfigBuilder::from(config.clone())
+            .with_source(source)
+            .build();
+        Ok(DataSourceExec::from_data_source(config))
+    }
+
+    async fn create_writer_physical_plan(
+        &self,
+        input: Arc<dyn ExecutionPlan>,
+        state: &dyn Session,
+        conf: FileSinkConfig,
+        order_requirements: Option<LexRequirement>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.inner
+            .create_writer_physical_plan(input, state, conf, order_requirements)
+            .await
+    }
+
+    fn file_source(&self, table_schema: TableSchema) -> Arc<dyn FileSource> {
+        EvolvingSource::wrap(
+            self.inner.file_source(table_schema),
+            self.adapter_factory.clone(),
+        )
+    }
+}
+
+/// The [`FileSource`] of [`EvolvingFormat`]: delegates to the inner format's
+/// source and wraps its openers.
+struct EvolvingSource {
+    inner: Arc<dyn FileSource>,
+    adapter_factory: SchemaEvolutionAdapterFactory,
+}
+
+impl EvolvingSource {
+    fn wrap(
+        inner: Arc<dyn FileSource>,
+        adapter_factory: SchemaEvolutionAdapterFactory,
+    ) -> Arc<dyn FileSource> {
+        Arc::new(Self {
+            inner,
+            adapter_factory,
+        })
+    }
+
+    fn rewrap(&self, inner: Arc<dyn FileSource>) -> Arc<dyn FileSource> {
+        Self::wrap(inner, self.adapter_factory.clone())
+    }
+
+    /// The row-id generator, if the table has a row-id column. Row ids count
+    /// the rows the reader returns, so the files must then be read whole, in
+    /// order and unfiltered.
+    fn row_ids(&self) -> Option<&Arc<dyn RowIdGenerator>> {
+        self.adapter_factory.row_ids().filter(|_| {
+            self.inner
+                .table_schema()
+                .table_schema()
+                .field_with_name(ROW_ID_COLUMN)
+                .is_ok()
+        })
+    }
+
+    /// The columns the table declares non-nullable whose nulls the policy
+    /// fills with a value.
+    fn filled_columns(&self) -> HashSet<&str> {
+        let policy = self.adapter_factory.policy();
+        self.inner
+            .table_schema()
+            .table_schema()
+            .fields()
+            .iter()
+            .filter(|field| {
+                !field.is_nullable()
+                    && matches!(
+                        policy.null_violation_for(field.name()),
+                        NullViolation::Fill(_)
+                    )
+            })
+            .map(|field| field.name().as_str())
+            .collect()
+    }
+}
+
+impl FileSource for EvolvingSource {
+    fn create_file_opener(
+        &self,
+        object_store: Arc<dyn ObjectStore>,
+        base_config: &FileScanConfig,
+        partition: usize,
+    ) -> Result<Arc<dyn FileOpener>> {
+        let inner = self
+            .inner
+            .create_file_opener(object_store, base_config, partition)?;
+        let reduced_cast_batches = MetricBuilder::new(self.metrics())
+            .counter("files_with_reduced_cast_batches", partition);
+        Ok(Arc::new(EvolvingOpener {
+            inner,
+            table_schema: Arc::clone(self.inner.table_schema().table_schema()),
+            policy: Arc::new(self.adapter_factory.policy().clone()),
+            row_ids: self.row_ids().cloned(),
+            limits: *self.adapter_factory.memory_limits(),
+            reduced_cast_batches,
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_schema(&self) -> &TableSchema {
+        self.inner.table_schema()
+    }
+
+    fn with_batch_size(&self, batch_size: usize) -> Arc<dyn FileSource> {
+        self.rewrap(self.inner.with_batch_size(batch_size))
+    }
+
+    fn filter(&self) -> Option<Arc<dyn PhysicalExpr>> {
+        self.inner.filter()
+    }
+
+    fn projection(&self) -> Option<&ProjectionExprs> {
+        self.inner.projection()
+    }
+
+    fn metrics(&self) -> &ExecutionPlanMetricsSet {
+        self.inner.metrics()
+    }
+
+    fn file_type(&self) -> &str {
+        self.inner.file_type()
+    }
+
+    fn fmt_extra(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt_extra(t, f)
+    }
+
+    fn supports_repartitioning(&self) -> bool {
+        self.inner.supports_repartitioning()
+    }
+
+    fn repartitioned(
+        &self,
+        target_partitions: usize,
+        repartition_file_min_size: usize,
+        output_ordering: Option<LexOrdering>,
+        config: &FileScanConfig,
+    ) -> Result<Option<FileScanConfig>> {
+        if self.row_ids().is_some() {
+            return Ok(None);
+        }
+        self.inner.repartitioned(
+            target_partitions,
+            repartition_file_min_size,
+            output_ordering,
+            config,
+        )
+    }
+
+    fn try_pushdown_filters(
+        &self,
+        filters: Vec<Arc<dyn PhysicalExpr>>,
+        config: &ConfigOptions,
+    ) -> Result<FilterPushdownPropagation<Arc<dyn FileSource>>> {
+        if self.row_ids().is_some() {
+            return Ok(FilterPushdownPropagation::with_parent_pushdown_result(
+                vec![PushedDown::No; filters.len()],
+            ));
+        }
+        // Nulls are filled after the reader returns its batches, so it must
+        // neither prune nor filter rows on the values they hide
+        let filled = self.filled_columns();
+        let held: Vec<bool> = filters
+            .iter()
+            .map(|filter| {
+                collect_columns(filter)
+                    .iter()
+                    .any(|column| filled.contains(column.name()))
+            })
+            .collect();
+        let pushable = filters
+            .into_iter()
+            .zip(&held)
+            .filter(|(_, held)| !**held)
+            .map(|(filter, _)| filter)
+            .collect();
+        let mut propagation = self.inner.try_pushdown_filters(pushable, config)?;
+        let mut pushed = propagation.filters.into_iter();
+        propagation.filters = held
+            .iter()
+            .map(|held| match held {
+                true => PushedDown::No,
+                false => pushed.next().unwrap_or(PushedDown::No),
+            })
+            .collect();
+        propagation.updated_node = propagation.updated_node.map(|node| self.rewrap(node));
+        Ok(propagation)
+    }
+
+    fn try_reverse_output(
+        &self,
+        order: &[PhysicalSortExpr],
+        eq_properties: &EquivalenceProperties,
+    ) -> Result<SortOrderPushdownResult<Arc<dyn FileSource>>> {
+        if self.row_ids().is_some() {
+            return Ok(SortOrderPushdownResult::Unsupported);
+        }
+        Ok(self
+            .inner
+            .try_reverse_output(order, eq_properties)?
+            .map(|source| self.rewrap(source)))
+    }
+
+    fn try_pushdown_projection(
+        &self,
+        projection: &ProjectionExprs,
+    ) -> Result<Option<Arc<dyn FileSource>>> {
+        // The ids are filled in after reading, so only plain references to
+        // them can be evaluated by the reader
+        if self.row_ids().is_some()
+            && projection.iter().any(|projected| {
+                projected.expr.as_any().downcast_ref::<Column>().is_none()
+                    && collect_columns(&projected.expr)
+                        .iter()
+                        .any(|column| column.name() == ROW_ID_COLUMN)
+            })
+        {
+            return Ok(None);
+        }
+        Ok(self
+            .inner
+            .try_pushdown_projection(projection)?
+            .map(|source| self.rewrap(source)))
+    }
+}
+
+/// Opens files with their [`FileContext`] set, casts the columns the inner
+/// reader decoded to another type than the table's, and applies the policy's
+/// [`NullViolation`] to nulls in columns the table declares non-nullable.
+/// Generated row ids are filled in before the nulls are checked.
+///
+/// Readers that evaluate the adapted projection themselves return the table's
+/// types already. Others decode the file's own types, or their preferred
+/// encoding of them (Vortex returns strings as `Utf8View`), which would
+/// otherwise reach operators expecting the table schema.
+struct EvolvingOpener {
+    inner: Arc<dyn FileOpener>,
+    table_schema: SchemaRef,
+    policy: Arc<CoercionPolicy>,
+    row_ids: Option<Arc<dyn RowIdGenerator>>,
+    limits: MemoryLimits,
+    reduced_cast_batches: Count,
+}
+
+impl FileOpener for EvolvingOpener {
+    fn open(&self, partitioned_file: PartitionedFile) -> Result<FileOpenFuture> {
+        let file = FileContext::from(&partitioned_file.object_meta);
+        let mut conformer = BatchConformer {
+            table_schema: Arc::clone(&self.table_schema),
+            policy: Arc::clone(&self.policy),
+            row_ids: self.row_ids.clone(),
+            object: partitioned_file.object_meta.clone(),
+            offset: 0,
+            plan: None,
+            limits: self.limits,
+            reduced_cast_batches: self.reduced_cast_batches.clone(),
+            reduced: false,
+        };
+        let count = self.reduced_cast_batches.clone();
+        let future = REDUCED_CAST_BATCHES.sync_scope(count.clone(), || {
+            CURRENT_FILE.sync_scope(file.clone(), || self.inner.open(partitioned_file))
+        })?;
+        let future = async move {
+            let stream = future.await?;
+            Ok(stream.map(move |batch| conformer.conform(batch?)).boxed())
+        };
+        Ok(Box::pin(
+            REDUCED_CAST_BATCHES.scope(count, CURRENT_FILE.scope(file, future)),
+        ))
+    }
+}
+
+/// Conforms the batches of one file to the table schema: casts the columns
+/// whose type differs from the table column of the same name as the policy
+/// allows, fills in generated row ids, and handles nulls in columns the table
+/// declares non-nullable. Columns the table does not have, such as computed
+/// projections, are kept as they are.
+///
+/// What a batch needs is decided once per batch schema, which readers share
+/// between all batches of a file; batches that need nothing pass through
+/// untouched.
+struct BatchConformer {
+    table_schema: SchemaRef,
+    policy: Arc<CoercionPolicy>,
+    row_ids: Option<Arc<dyn RowIdGenerator>>,
+    object: ObjectMeta,
+    /// The row the next batch starts at, counted in the rows read from the
+    /// scanned range of the file. This is the row of the file only when the
+    /// whole file is read: scans repartitioned by byte range start mid-file,
+    /// and pruned pages and pushed-down filters skip rows.
+    offset: usize,
+    plan: Option<BatchPlan>,
+    limits: MemoryLimits,
+    reduced_cast_batches: Count,
+    /// Whether the file was counted in `reduced_cast_batches`.
+    reduced: bool,
+}
+
+/// The work batches of one schema need.
+struct BatchPlan {
+    input: SchemaRef,
+    output: SchemaRef,
+    /// Columns to cast, with their table type.
+    casts: Vec<(usize, DataType, Coercion)>,
+    row_id: Option<usize>,
+    /// Columns the table declares non-nullable.
+    required: Vec<usize>,
+    /// The batch size string-parse casts are evaluated in, if reduced.
+    parse_batch_rows: Option<usize>,
+}
+
+impl BatchConformer {
+    fn conform(&mut self, batch: RecordBatch) -> Result<RecordBatch> {
+        let offset = self.offset;
+        self.offset += batch.num_rows();
+        let plan = match self.plan.take() {
+            Some(plan) if Arc::ptr_eq(&plan.input, &batch.schema()) => self.plan.insert(plan),
+            _ => {
+                let plan = self.plan_batches(batch.schema())?;
+                if plan.parse_batch_rows.is_some() && !self.reduced {
+                    self.reduced = true;
+                    self.reduced_cast_batches.add(1);
+                }
+                self.plan.insert(plan)
+            }
+        };
+        if plan.casts.is_empty() && plan.row_id.is_none() && plan.required.is_empty() {
+            return Ok(batch);
+        }
+
+        let mut columns = batch.columns().to_vec();
+        for (index, table_type, coercion) in &plan.casts {
+            let column = Arc::clone(&columns[*index]);
+            let cast = |offset: usize, len: usize| {
+                cast_array(
+                    &column.slice(offset, len),
+                    table_type,
+                    *coercion,
+                    self.policy.naive_timestamps(),
+                    self.policy
+                        .uncoercible_for(plan.output.field(*index).name()),
+                )
+            };
+            columns[*index] = match plan.parse_batch_rows {
+                Some(rows) if is_parse_cast(column.data_type(), *coercion) => {
+                    evaluate_in_slices(column.len(), rows, cast)?
+                }
+                _ => cast(0, column.len())?,
+            };
+        }
+        if let (Some(index), Some(generator)) = (plan.row_id, &self.row_ids) {
+            columns[index] = generator.generate(&self.object, offset as u64, batch.num_rows())?;
+        }
+
+        let mut keep: Option<BooleanArray> = None;
+        for index in &plan.required {
+            let column = &mut columns[*index];
+            if column.null_count() == 0 {
+                continue;
+            }
+            let name = plan.output.field(*index).name();
+            let valid = is_not_null(column)?;
+            match self.policy.null_violation_for(name) {
+                NullViolation::Error => {
+                    let row = (0..valid.len()).find(|row| !valid.value(*row)).unwrap_or(0);
+                    return exec_err!(
+                        "Non-nullable column '{name}' is null in row {} read from the scanned range of file {}",
+                        offset + row,
+                        self.object.location
+                    );
+                }
+                NullViolation::Fill(value) => {
+                    let value = value.cast_to(column.data_type())?.to_scalar()?;
+                    *column = zip(&valid, column, &value)?;
+                }
+                NullViolation::DropRow => {
+                    keep = Some(match keep {
+                        Some(keep) => and(&keep, &valid)?,
+                        None => valid,
+                    });
+                }
+            }
+        }
+
+        let batch = RecordBatch::try_new(Arc::clone(&plan.output), columns)?;
+        match keep {
+            Some(keep) => Ok(filter_record_batch(&batch, &keep)?),
+            None => Ok(batch),
+        }
+    }
+
+    fn plan_batches(&self, input: SchemaRef) -> Result<BatchPlan> {
+        let mut fields = input.fields().to_vec();
+        let mut casts = Vec::new();
+        let mut row_id = None;
+        let mut required = Vec::new();
+        for (index, field) in input.fields().iter().enumerate() {
+            let Ok(table_field) = self.table_schema.field_with_name(field.name()) else {
+                continue;
+            };
+            if !table_field.is_nullable() {
+                required.push(index);
+            }
+            if let Some(generator) = &self.row_ids
+                && field.name() == ROW_ID_COLUMN
+            {
+                row_id = Some(index);
+                fields[index] = Arc::new(
+                    field
+                        .as_ref()
+                        .clone()
+                        .with_data_type(generator.data_type())
+                        .with_nullable(false),
+                );
+                continue;
+            }
+            if table_field.data_type() == field.data_type() {
+                continue;
+            }
+            let coercion = self
+                .policy
+                .resolve(field.name(), field.data_type(), table_field.data_type())
+                .map_err(|err| {
+                    DataFusionError::External(Box::new(
+                        err.with_file(self.object.location.to_string()),
+                    ))
+                })?;
+            casts.push((index, table_field.data_type().clone(), coercion));
+            fields[index] = Arc::new(
+                field
+                    .as_ref()
+                    .clone()
+                    .with_data_type(table_field.data_type().clone()),
+            );
+        }
+        let parse_casts = casts
+            .iter()
+            .filter(|(index, _, coercion)| {
+                is_parse_cast(input.field(*index).data_type(), *coercion)
+            })
+            .count();
+        let output = if casts.is_empty() && row_id.is_none() {
+            Arc::clone(&input)
+        } else {
+            Arc::new(Schema::new_with_metadata(fields, input.metadata().clone()))
+        };
+        Ok(BatchPlan {
+            input,
+            output,
+            casts,
+            row_id,
+            required,
+            parse_batch_rows: self.limits.reduced_batch_rows(parse_casts),
+        })
+    }
+}
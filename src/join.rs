@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use arrow::datatypes::DataType;
+use datafusion::common::config::ConfigOptions;
+use datafusion::common::tree_node::{Transformed, TransformedResult, TreeNode};
+use datafusion::common::{DFSchema, Result};
+use datafusion::logical_expr::{
+    BinaryExpr, Expr, ExprSchemable, LogicalPlan, Operator, cast, try_cast,
+};
+use datafusion::optimizer::analyzer::{Analyzer, AnalyzerRule};
+
+use crate::merge::common_type;
+use crate::policy::{CoercionMode, is_lossless_widening};
+
+/// An [`AnalyzerRule`] casting the keys of equi-joins whose sides have
+/// different types to their [`common_type`], the type their files would be
+/// unified to, instead of leaving them to DataFusion's comparison coercion.
+/// Joining a `Utf8` key with an `Int64` one then compares strings under the
+/// default lenient mode, rather than parsing every string and failing on the
+/// first that is not a number.
+///
+/// Keys that can only be cast lossily are cast with `TRY_CAST`, so values
+/// that do not convert are null and match nothing. Keys without a common type
+/// under the mode are left to DataFusion.
+///
+/// The rule must run before DataFusion's type coercion:
+///
+/// ```ignore
+/// let state = SessionStateBuilder::new()
+///     .with_default_features()
+///     .with_analyzer_rules(JoinKeyCoercion::new().with_default_rules())
+///     .build();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct JoinKeyCoercion {
+    mode: CoercionMode,
+}
+
+impl JoinKeyCoercion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The mode join keys are unified under; lenient by default.
+    pub fn with_mode(mut self, mode: CoercionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// This rule followed by DataFusion's default analyzer rules.
+    pub fn with_default_rules(self) -> Vec<Arc<dyn AnalyzerRule + Send + Sync>> {
+        let mut rules: Vec<Arc<dyn AnalyzerRule + Send + Sync>> = vec![Arc::new(self)];
+        rules.extend(Analyzer::new().rules);
+        rules
+    }
+
+    /// `left` and `right` cast to their common type, if they differ and have
+    /// one.
+    fn coerce(
+        &self,
+        left: &Expr,
+        left_schema: &DFSchema,
+        right: &Expr,
+        right_schema: &DFSchema,
+    ) -> Result<Option<(Expr, Expr)>> {
+        let left_type = left.get_type(left_schema)?;
+        let right_type = right.get_type(right_schema)?;
+        if left_type == right_type {
+            return Ok(None);
+        }
+        let Some(target) = common_type(&left_type, &right_type, self.mode) else {
+            return Ok(None);
+        };
+        Ok(Some((
+            coerce_key(left.clone(), &left_type, &target),
+            coerce_key(right.clone(), &right_type, &target),
+        )))
+    }
+}
+
+impl AnalyzerRule for JoinKeyCoercion {
+    fn analyze(&self, plan: LogicalPlan, _config: &ConfigOptions) -> Result<LogicalPlan> {
+        plan.transform_up_with_subqueries(|plan| {
+            let LogicalPlan::Join(mut join) = plan else {
+                return Ok(Transformed::no(plan));
+            };
+            let (left_schema, right_schema) = (join.left.schema(), join.right.schema());
+            let mut changed = false;
+
+            let mut on = Vec::with_capacity(join.on.len());
+            for (left, right) in &join.on {
+                match self.coerce(left, left_schema, right, right_schema)? {
+                    Some(pair) => {
+                        on.push(pair);
+                        changed = true;
+                    }
+                    None => on.push((left.clone(), right.clone())),
+                }
+            }
+
+            // Keys written in `ON` are still a filter before the optimizer
+            // extracts them
+            let filter = match &join.filter {
+                Some(predicate) => {
+                    let transformed = predicate.clone().transform_up(|expr| {
+                        let Expr::BinaryExpr(BinaryExpr { left, op, right }) = &expr else {
+                            return Ok(Transformed::no(expr));
+                        };
+                        if !matches!(op, Operator::Eq | Operator::IsNotDistinctFrom) {
+                            return Ok(Transformed::no(expr));
+                        }
+                        let (Expr::Column(left_column), Expr::Column(right_column)) =
+                            (left.as_ref(), right.as_ref())
+                        else {
+                            return Ok(Transformed::no(expr));
+                        };
+                        // Either side may name either input
+                        let coerced = if left_schema.has_column(left_column)
+                            && right_schema.has_column(right_column)
+                        {
+                            self.coerce(left, left_schema, right, right_schema)?
+                        } else if right_schema.has_column(left_column)
+                            && left_schema.has_column(right_column)
+                        {
+                            self.coerce(left, right_schema, right, left_schema)?
+                        } else {
+                            None
+                        };
+                        Ok(match coerced {
+                            Some((left, right)) => Transformed::yes(Expr::BinaryExpr(
+                                BinaryExpr::new(Box::new(left), *op, Box::new(right)),
+                            )),
+                            None => Transformed::no(expr),
+                        })
+                    })?;
+                    changed |= transformed.transformed;
+                    Some(transformed.data)
+                }
+                None => None,
+            };
+
+            if !changed {
+                return Ok(Transformed::no(LogicalPlan::Join(join)));
+            }
+            join.on = on;
+            join.filter = filter;
+            Ok(Transformed::yes(LogicalPlan::Join(join)))
+        })
+        .data()
+    }
+
+    fn name(&self) -> &str {
+        "join_key_coercion"
+    }
+}
+
+fn coerce_key(expr: Expr, from: &DataType, to: &DataType) -> Expr {
+    if from == to {
+        expr
+    } else if is_lossless_widening(from, to) {
+        cast(expr, to.clone())
+    } else {
+        try_cast(expr, to.clone())
+    }
+}
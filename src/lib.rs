@@ -0,0 +1,80 @@
+//! Schema evolution helpers for querying Parquet and Vortex files with
+//! heterogeneous schemas through DataFusion.
+//!
+//! [`prelude`] holds the API covered by semver, and is re-exported here. The
+//! other modules are built on DataFusion's and Vortex's own APIs and change
+//! with them; they are public for the crate's tests, benchmarks and
+//! command-line tool, but hidden from the documentation and not covered by
+//! semver.
+
+#[doc(hidden)]
+pub mod adapter;
+#[doc(hidden)]
+pub mod append;
+mod cancel;
+#[doc(hidden)]
+pub mod canonical;
+#[doc(hidden)]
+pub mod diagnose;
+#[doc(hidden)]
+pub mod discovery;
+#[doc(hidden)]
+pub mod drift;
+#[doc(hidden)]
+pub mod encoding;
+#[doc(hidden)]
+pub mod ffi;
+#[doc(hidden)]
+pub mod file_index;
+#[doc(hidden)]
+pub mod fingerprint;
+#[doc(hidden)]
+pub mod format;
+#[doc(hidden)]
+pub mod join;
+#[doc(hidden)]
+pub mod limits;
+#[doc(hidden)]
+pub mod manifest;
+#[doc(hidden)]
+pub mod mapping;
+#[doc(hidden)]
+pub mod merge;
+#[doc(hidden)]
+pub mod missing;
+#[doc(hidden)]
+pub mod nested;
+#[doc(hidden)]
+pub mod normalize;
+#[doc(hidden)]
+pub mod notify;
+#[cfg(feature = "polars")]
+#[doc(hidden)]
+pub mod polars_interop;
+#[doc(hidden)]
+pub mod policy;
+pub mod prelude;
+#[doc(hidden)]
+pub mod provider;
+#[doc(hidden)]
+pub mod registry;
+#[doc(hidden)]
+pub mod report;
+#[doc(hidden)]
+pub mod rewrite;
+#[doc(hidden)]
+pub mod row_id;
+#[doc(hidden)]
+pub mod sentinel;
+#[doc(hidden)]
+pub mod single_file;
+#[doc(hidden)]
+pub mod sql;
+#[doc(hidden)]
+pub mod streaming;
+#[doc(hidden)]
+pub mod temporal;
+#[doc(hidden)]
+pub mod verify;
+
+pub use prelude::*;
@@ -0,0 +1,203 @@
+use std::any::Any;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef};
+use arrow::compute::concat;
+use arrow::datatypes::{DataType, FieldRef, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{Result, exec_err};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+
+use crate::policy::Coercion;
+
+/// Bounds on the memory adapting a file may take.
+///
+/// Parsing strings, and casts that go through strings, allocate intermediate
+/// buffers the size of the whole batch for every such column. Files needing at
+/// least [`with_min_parse_casts`](Self::with_min_parse_casts) of them have
+/// those casts evaluated in batches of at most
+/// [`with_parse_batch_rows`](Self::with_parse_batch_rows) rows instead; the
+/// scan's `files_with_reduced_cast_batches` metric counts them.
+///
+/// ```ignore
+/// let options = EvolutionOptions::new().with_memory_limits(
+///     MemoryLimits::new()
+///         .with_parse_batch_rows(1024)
+///         .with_min_parse_casts(8),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimits {
+    parse_batch_rows: Option<usize>,
+    min_parse_casts: usize,
+}
+
+impl Default for MemoryLimits {
+    fn default() -> Self {
+        Self {
+            parse_batch_rows: None,
+            min_parse_casts: 1,
+        }
+    }
+}
+
+impl MemoryLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The most rows string-parse casts are evaluated over at once; by default
+    /// they take the reader's batches whole.
+    pub fn with_parse_batch_rows(mut self, rows: usize) -> Self {
+        self.parse_batch_rows = Some(rows.max(1));
+        self
+    }
+
+    pub fn parse_batch_rows(&self) -> Option<usize> {
+        self.parse_batch_rows
+    }
+
+    /// Only reduce the batches of files needing at least `casts` string-parse
+    /// casts; one by default.
+    pub fn with_min_parse_casts(mut self, casts: usize) -> Self {
+        self.min_parse_casts = casts.max(1);
+        self
+    }
+
+    pub fn min_parse_casts(&self) -> usize {
+        self.min_parse_casts
+    }
+
+    /// The batch size for the casts of a file needing `parse_casts`
+    /// string-parse casts, if reduced.
+    pub fn reduced_batch_rows(&self, parse_casts: usize) -> Option<usize> {
+        self.parse_batch_rows
+            .filter(|_| parse_casts >= self.min_parse_casts)
+    }
+}
+
+/// Whether casting from `source` with `coercion` parses strings.
+pub(crate) fn is_parse_cast(source: &DataType, coercion: Coercion) -> bool {
+    match coercion {
+        Coercion::ViaString => true,
+        Coercion::Lenient => matches!(
+            source,
+            DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+        ),
+        Coercion::Identity | Coercion::Widen => false,
+    }
+}
+
+/// Evaluate `slice` over consecutive slices of at most `rows` of `len` rows,
+/// given as offset and length, and concatenate the results.
+pub(crate) fn evaluate_in_slices(
+    len: usize,
+    rows: usize,
+    mut slice: impl FnMut(usize, usize) -> Result<ArrayRef>,
+) -> Result<ArrayRef> {
+    if len <= rows {
+        return slice(0, len);
+    }
+    let slices = (0..len)
+        .step_by(rows)
+        .map(|offset| slice(offset, rows.min(len - offset)))
+        .collect::<Result<Vec<_>>>()?;
+    let slices: Vec<&dyn Array> = slices.iter().map(|array| array.as_ref()).collect();
+    Ok(concat(&slices)?)
+}
+
+/// Evaluates an expression over slices of at most `rows` rows of each batch
+/// and concatenates the results, so that the buffers a cast allocates on the
+/// way are bounded by the slice rather than the batch. Statistics pruning does
+/// not look through it.
+#[derive(Debug, Clone, Eq)]
+pub struct ReducedBatchExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    rows: usize,
+}
+
+impl ReducedBatchExpr {
+    pub fn new(expr: Arc<dyn PhysicalExpr>, rows: usize) -> Self {
+        Self {
+            expr,
+            rows: rows.max(1),
+        }
+    }
+
+    pub fn expr(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.expr
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+}
+
+impl PartialEq for ReducedBatchExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.expr.eq(&other.expr) && self.rows == other.rows
+    }
+}
+
+impl Hash for ReducedBatchExpr {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.expr.hash(state);
+        self.rows.hash(state);
+    }
+}
+
+impl fmt::Display for ReducedBatchExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "REDUCED_BATCH({}, {})", self.expr, self.rows)
+    }
+}
+
+impl PhysicalExpr for ReducedBatchExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        self.expr.data_type(input_schema)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        self.expr.nullable(input_schema)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        if batch.num_rows() <= self.rows {
+            return self.expr.evaluate(batch);
+        }
+        let array = evaluate_in_slices(batch.num_rows(), self.rows, |offset, len| {
+            self.expr
+                .evaluate(&batch.slice(offset, len))?
+                .into_array(len)
+        })?;
+        Ok(ColumnarValue::Array(array))
+    }
+
+    fn return_field(&self, input_schema: &Schema) -> Result<FieldRef> {
+        self.expr.return_field(input_schema)
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn PhysicalExpr>> {
+        vec![&self.expr]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let [expr] = <[_; 1]>::try_from(children)
+            .or_else(|_| exec_err!("ReducedBatchExpr expects one child"))?;
+        Ok(Arc::new(Self::new(expr, self.rows)))
+    }
+
+    fn fmt_sql(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
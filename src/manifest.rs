@@ -0,0 +1,688 @@
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::common::{DataFusionError, Result, ScalarValue, config_err};
+use serde::{Deserialize, Serialize};
+
+use crate::file_index::ManifestFileIndex;
+use crate::format::FileContext;
+use crate::policy::can_cast;
+
+/// Schema versions of a dataset and the migrations between them, declared
+/// instead of inferred from the file footers.
+///
+/// The manifest is JSON; every version lists its columns, the steps that turn
+/// the previous version into it, and which files were written with it:
+///
+/// ```json
+/// {"versions": [
+///   {"version": 1, "files": {"path": "*/v1/*"},
+///    "columns": [{"name": "uid", "type": "Int32"}, {"name": "status", "type": "Int32"},
+///                {"name": "latency", "type": "Int64"}]},
+///   {"version": 2, "files": {"metadata": {"key": "schema_version", "value": "2"}},
+///    "columns": [{"name": "user_id", "type": "Int64"}, {"name": "latency", "type": "Int64"},
+///                {"name": "region", "type": "Utf8"}],
+///    "migration": [
+///      {"op": "rename", "from": "uid", "to": "user_id"},
+///      {"op": "cast", "column": "user_id", "type": "Int64"},
+///      {"op": "drop", "column": "status"},
+///      {"op": "scale", "column": "latency", "factor": 0.001},
+///      {"op": "add", "column": "region", "type": "Utf8", "default": "eu"}]}
+/// ]}
+/// ```
+///
+/// The last version is the table schema. Passed to
+/// [`SchemaEvolutionAdapterFactory::with_manifest`](crate::adapter::SchemaEvolutionAdapterFactory::with_manifest),
+/// each file is read by applying the migrations declared after its version,
+/// and files that match no version fail the scan.
+#[derive(Debug, Clone)]
+pub struct EvolutionManifest {
+    versions: Vec<SchemaVersion>,
+    file_index: Option<Arc<ManifestFileIndex>>,
+}
+
+/// One version of an [`EvolutionManifest`].
+#[derive(Debug, Clone)]
+pub struct SchemaVersion {
+    pub version: u64,
+    pub schema: SchemaRef,
+    /// The steps from the previous version to this one.
+    pub migration: Vec<MigrationStep>,
+    pub files: VersionRule,
+}
+
+/// One step of a migration between two [`SchemaVersion`]s.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationStep {
+    Rename {
+        from: String,
+        to: String,
+    },
+    /// The column changed type; older files are cast whatever the policy says.
+    Cast {
+        column: String,
+        data_type: DataType,
+    },
+    Drop {
+        column: String,
+    },
+    /// The column changed units; values of older files are multiplied by
+    /// `factor`, e.g. `0.001` from milliseconds to seconds or `0.01` from cents
+    /// to dollars.
+    Scale {
+        column: String,
+        factor: f64,
+    },
+    /// A new column, read as `default` from older files.
+    Add {
+        column: String,
+        data_type: DataType,
+        default: ScalarValue,
+    },
+}
+
+/// Which files were written with a [`SchemaVersion`].
+#[derive(Debug, Clone)]
+pub enum VersionRule {
+    /// Files whose path, relative to the object store root, matches the glob.
+    Path(glob::Pattern),
+    /// Files whose footer has the key-value metadata entry.
+    Metadata { key: String, value: String },
+    /// Files listed by path, relative to the object store root.
+    Paths(BTreeSet<String>),
+}
+
+impl VersionRule {
+    pub fn matches(&self, file: Option<&FileContext>, file_schema: &Schema) -> bool {
+        match self {
+            Self::Path(pattern) => {
+                file.is_some_and(|file| pattern.matches(file.path.trim_start_matches('/')))
+            }
+            Self::Metadata { key, value } => file_schema.metadata().get(key) == Some(value),
+            Self::Paths(paths) => {
+                file.is_some_and(|file| paths.contains(file.path.trim_start_matches('/')))
+            }
+        }
+    }
+}
+
+/// What it takes to read a file of one version as the last version.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MigrationPlan {
+    pub version: u64,
+    /// File columns read under another name, as `(stored, current)`.
+    pub renames: Vec<(String, String)>,
+    /// Current names of columns cast by a [`MigrationStep::Cast`].
+    pub casts: Vec<String>,
+    /// Current names of columns in other units, and the factor converting
+    /// them to the current units.
+    pub scales: Vec<(String, f64)>,
+    /// File columns dropped since, which must not be read even if a later
+    /// version adds a column of the same name.
+    pub dropped: HashSet<String>,
+    /// Columns added since, with their defaults.
+    pub defaults: Vec<(String, ScalarValue)>,
+}
+
+impl EvolutionManifest {
+    /// Read a manifest from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+            .map_err(|err| err.context(format!("Invalid manifest {}", path.display())))
+    }
+
+    /// The manifest as JSON, which [`Self::from_json`] reads back.
+    pub fn to_json(&self) -> Result<String> {
+        let manifest = ManifestJson {
+            versions: self
+                .versions
+                .iter()
+                .map(VersionJson::try_from)
+                .collect::<Result<_>>()?,
+        };
+        serde_json::to_string_pretty(&manifest)
+            .map_err(|err| DataFusionError::External(Box::new(err)))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let manifest: ManifestJson = match serde_json::from_str(json) {
+            Ok(manifest) => manifest,
+            Err(err) => return config_err!("{err}"),
+        };
+        let versions = manifest
+            .versions
+            .into_iter()
+            .map(SchemaVersion::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        Self::try_new(versions)
+    }
+
+    /// Check that the versions are in increasing order and that each migration
+    /// turns the columns of the previous version into those of its own, casting
+    /// only to the declared types and only where arrow can cast.
+    pub fn try_new(versions: Vec<SchemaVersion>) -> Result<Self> {
+        if versions.is_empty() {
+            return config_err!("A manifest needs at least one version");
+        }
+        for pair in versions.windows(2) {
+            let (previous, version) = (&pair[0], &pair[1]);
+            if version.version <= previous.version {
+                return config_err!(
+                    "Version {} follows version {}; versions must increase",
+                    version.version,
+                    previous.version
+                );
+            }
+            let mut columns: Vec<(String, DataType)> = previous
+                .schema
+                .fields()
+                .iter()
+                .map(|field| (field.name().clone(), field.data_type().clone()))
+                .collect();
+            for step in &version.migration {
+                match step {
+                    MigrationStep::Rename { from, to } => {
+                        match columns.iter_mut().find(|(c, _)| *c == *from) {
+                            Some((column, _)) => *column = to.clone(),
+                            None => {
+                                return config_err!(
+                                    "Version {} renames '{from}', which version {} does not have",
+                                    version.version,
+                                    previous.version
+                                );
+                            }
+                        }
+                    }
+                    MigrationStep::Cast { column, data_type } => {
+                        let Some((_, from)) = columns.iter_mut().find(|(c, _)| c == column) else {
+                            return config_err!(
+                                "Version {} casts '{column}', which it does not have",
+                                version.version
+                            );
+                        };
+                        if let Ok(declared) = version.schema.field_with_name(column)
+                            && declared.data_type() != data_type
+                        {
+                            return config_err!(
+                                "Version {} casts '{column}' to {data_type}, but declares it as {}",
+                                version.version,
+                                declared.data_type()
+                            );
+                        }
+                        if !can_cast(from, data_type) {
+                            return config_err!(
+                                "Version {} casts '{column}' from {from} to {data_type}, which arrow cannot do",
+                                version.version
+                            );
+                        }
+                        *from = data_type.clone();
+                    }
+                    MigrationStep::Scale { column, .. }
+                        if !columns.iter().any(|(c, _)| c == column) =>
+                    {
+                        return config_err!(
+                            "Version {} scales '{column}', which it does not have",
+                            version.version
+                        );
+                    }
+                    MigrationStep::Scale { column, factor }
+                        if !factor.is_finite() || *factor <= 0.0 =>
+                    {
+                        return config_err!(
+                            "Version {} scales '{column}' by {factor}; factors must be positive",
+                            version.version
+                        );
+                    }
+                    MigrationStep::Scale { .. } => {}
+                    MigrationStep::Drop { column } => {
+                        let before = columns.len();
+                        columns.retain(|(c, _)| c != column);
+                        if columns.len() == before {
+                            return config_err!(
+                                "Version {} drops '{column}', which it does not have",
+                                version.version
+                            );
+                        }
+                    }
+                    MigrationStep::Add {
+                        column, data_type, ..
+                    } => columns.push((column.clone(), data_type.clone())),
+                }
+            }
+            let columns: Vec<_> = columns.into_iter().map(|(column, _)| column).collect();
+            let expected: HashSet<_> = columns.iter().collect();
+            let declared: HashSet<_> = version.schema.fields().iter().map(|f| f.name()).collect();
+            if expected != declared {
+                return config_err!(
+                    "The migration to version {} yields columns {columns:?}, but it declares {:?}",
+                    version.version,
+                    version
+                        .schema
+                        .fields()
+                        .iter()
+                        .map(|f| f.name())
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+        Ok(Self {
+            versions,
+            file_index: None,
+        })
+    }
+
+    pub fn versions(&self) -> &[SchemaVersion] {
+        &self.versions
+    }
+
+    /// The schema of the last version, which files are read as.
+    pub fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.versions.last().unwrap().schema)
+    }
+
+    /// Look up the version of files in `index` before trying the rules. The
+    /// chunks of the files planned must be loaded, which
+    /// [`EvolvingFormat`](crate::format::EvolvingFormat) does for the files it
+    /// scans.
+    pub fn with_file_index(mut self, index: Arc<ManifestFileIndex>) -> Self {
+        self.file_index = Some(index);
+        self
+    }
+
+    pub fn file_index(&self) -> Option<&Arc<ManifestFileIndex>> {
+        self.file_index.as_ref()
+    }
+
+    /// The version of a file, from the file index or by the first rule it
+    /// matches.
+    pub fn version_of(
+        &self,
+        file: Option<&FileContext>,
+        file_schema: &Schema,
+    ) -> Result<Option<&SchemaVersion>> {
+        Ok(self
+            .position(file, file_schema)?
+            .map(|position| &self.versions[position]))
+    }
+
+    fn position(&self, file: Option<&FileContext>, file_schema: &Schema) -> Result<Option<usize>> {
+        if let (Some(index), Some(file)) = (&self.file_index, file)
+            && let Some(version) = index.version_of(&file.path)?
+        {
+            return match self.versions.iter().position(|v| v.version == version) {
+                Some(position) => Ok(Some(position)),
+                None => config_err!(
+                    "The file index lists {} as version {version}, which the manifest does not have",
+                    file.path
+                ),
+            };
+        }
+        Ok(self
+            .versions
+            .iter()
+            .position(|version| version.files.matches(file, file_schema)))
+    }
+
+    /// How to read a file as the last version, or an error naming the file if
+    /// it matches no version.
+    pub fn plan(&self, file: Option<&FileContext>, file_schema: &Schema) -> Result<MigrationPlan> {
+        let Some(position) = self.position(file, file_schema)? else {
+            return config_err!(
+                "File {} matches no version of the evolution manifest",
+                file.map_or("<unknown>", |file| file.path.as_str())
+            );
+        };
+
+        // Follow each column of the file's version through the later steps
+        struct Column {
+            stored: Option<String>,
+            current: String,
+            cast: bool,
+            scale: f64,
+            default: Option<ScalarValue>,
+        }
+        let mut columns: Vec<Column> = self.versions[position]
+            .schema
+            .fields()
+            .iter()
+            .map(|field| Column {
+                stored: Some(field.name().clone()),
+                current: field.name().clone(),
+                cast: false,
+                scale: 1.0,
+                default: None,
+            })
+            .collect();
+        let mut dropped = HashSet::new();
+        for version in &self.versions[position + 1..] {
+            for step in &version.migration {
+                match step {
+                    MigrationStep::Rename { from, to } => {
+                        if let Some(column) = columns.iter_mut().find(|c| c.current == *from) {
+                            column.current = to.clone();
+                        }
+                    }
+                    MigrationStep::Cast { column, .. } => {
+                        if let Some(column) = columns.iter_mut().find(|c| c.current == *column) {
+                            column.cast = true;
+                        }
+                    }
+                    MigrationStep::Scale { column, factor } => {
+                        if let Some(column) = columns.iter_mut().find(|c| c.current == *column) {
+                            column.scale *= factor;
+                        }
+                    }
+                    MigrationStep::Drop { column } => {
+                        if let Some(position) = columns.iter().position(|c| c.current == *column) {
+                            dropped.extend(columns.remove(position).stored);
+                        }
+                    }
+                    MigrationStep::Add {
+                        column, default, ..
+                    } => columns.push(Column {
+                        stored: None,
+                        current: column.clone(),
+                        cast: false,
+                        scale: 1.0,
+                        default: Some(default.clone()),
+                    }),
+                }
+            }
+        }
+
+        let mut plan = MigrationPlan {
+            version: self.versions[position].version,
+            dropped,
+            ..Default::default()
+        };
+        for column in columns {
+            match column.stored {
+                Some(stored) if stored != column.current => {
+                    // A column renamed away is not dropped
+                    plan.dropped.remove(&stored);
+                    plan.renames.push((stored, column.current.clone()));
+                }
+                Some(stored) => {
+                    plan.dropped.remove(&stored);
+                }
+                None => {}
+            }
+            if column.cast {
+                plan.casts.push(column.current.clone());
+            }
+            // Defaults are declared in the units of the version adding them,
+            // and scaled like the values stored in that version
+            let default = match column.default {
+                Some(default) if column.scale != 1.0 => {
+                    Some(scale_default(&column.current, &default, column.scale)?)
+                }
+                Some(default) => Some(default),
+                None => {
+                    if column.scale != 1.0 {
+                        plan.scales.push((column.current.clone(), column.scale));
+                    }
+                    None
+                }
+            };
+            if let Some(default) = default {
+                plan.defaults.push((column.current, default));
+            }
+        }
+        Ok(plan)
+    }
+}
+
+/// The default of the added column `name` multiplied by `factor`, in its own
+/// type; integers are divided by the inverse of factors below one and
+/// truncated, like the values of scaled integer columns.
+fn scale_default(name: &str, default: &ScalarValue, factor: f64) -> Result<ScalarValue> {
+    let data_type = default.data_type();
+    if default.is_null() {
+        return Ok(default.clone());
+    }
+    let value = match default.cast_to(&DataType::Float64) {
+        Ok(ScalarValue::Float64(Some(value))) if data_type.is_numeric() => value,
+        _ => return config_err!("Cannot scale '{name}', whose default {default} is not a number"),
+    };
+    let scaled = if !data_type.is_integer() {
+        value * factor
+    } else if factor < 1.0 {
+        (value / (1.0 / factor).round()).trunc()
+    } else {
+        (value * factor).trunc()
+    };
+    ScalarValue::Float64(Some(scaled)).cast_to(&data_type)
+}
+
+impl TryFrom<VersionJson> for SchemaVersion {
+    type Error = datafusion::error::DataFusionError;
+
+    fn try_from(json: VersionJson) -> Result<Self> {
+        let fields = json
+            .columns
+            .into_iter()
+            .map(|column| {
+                Ok(Field::new(
+                    column.name,
+                    parse_type(&column.data_type)?,
+                    column.nullable,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let migration = json
+            .migration
+            .into_iter()
+            .map(|step| {
+                Ok(match step {
+                    StepJson::Rename { from, to } => MigrationStep::Rename { from, to },
+                    StepJson::Cast { column, data_type } => MigrationStep::Cast {
+                        column,
+                        data_type: parse_type(&data_type)?,
+                    },
+                    StepJson::Drop { column } => MigrationStep::Drop { column },
+                    StepJson::Scale { column, factor } => MigrationStep::Scale { column, factor },
+                    StepJson::Add {
+                        column,
+                        data_type,
+                        default,
+                    } => {
+                        let data_type = parse_type(&data_type)?;
+                        let default = json_scalar(&default).cast_to(&data_type)?;
+                        MigrationStep::Add {
+                            column,
+                            data_type,
+                            default,
+                        }
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let files = match json.files {
+            RuleJson::Path(pattern) => match glob::Pattern::new(&pattern) {
+                Ok(pattern) => VersionRule::Path(pattern),
+                Err(err) => return config_err!("Invalid path glob '{pattern}': {err}"),
+            },
+            RuleJson::Metadata { key, value } => VersionRule::Metadata { key, value },
+            RuleJson::Paths(paths) => VersionRule::Paths(paths.into_iter().collect()),
+        };
+        Ok(Self {
+            version: json.version,
+            schema: Arc::new(Schema::new(fields)),
+            migration,
+            files,
+        })
+    }
+}
+
+impl TryFrom<&SchemaVersion> for VersionJson {
+    type Error = DataFusionError;
+
+    fn try_from(version: &SchemaVersion) -> Result<Self> {
+        let columns = version
+            .schema
+            .fields()
+            .iter()
+            .map(|field| ColumnJson {
+                name: field.name().clone(),
+                data_type: field.data_type().to_string(),
+                nullable: field.is_nullable(),
+            })
+            .collect();
+        let migration = version
+            .migration
+            .iter()
+            .map(|step| {
+                Ok(match step {
+                    MigrationStep::Rename { from, to } => StepJson::Rename {
+                        from: from.clone(),
+                        to: to.clone(),
+                    },
+                    MigrationStep::Cast { column, data_type } => StepJson::Cast {
+                        column: column.clone(),
+                        data_type: data_type.to_string(),
+                    },
+                    MigrationStep::Drop { column } => StepJson::Drop {
+                        column: column.clone(),
+                    },
+                    MigrationStep::Scale { column, factor } => StepJson::Scale {
+                        column: column.clone(),
+                        factor: *factor,
+                    },
+                    MigrationStep::Add {
+                        column,
+                        data_type,
+                        default,
+                    } => StepJson::Add {
+                        column: column.clone(),
+                        data_type: data_type.to_string(),
+                        default: scalar_json(default)?,
+                    },
+                })
+            })
+            .collect::<Result<_>>()?;
+        let files = match &version.files {
+            VersionRule::Path(pattern) => RuleJson::Path(pattern.as_str().to_string()),
+            VersionRule::Metadata { key, value } => RuleJson::Metadata {
+                key: key.clone(),
+                value: value.clone(),
+            },
+            VersionRule::Paths(paths) => RuleJson::Paths(paths.iter().cloned().collect()),
+        };
+        Ok(Self {
+            version: version.version,
+            columns,
+            migration,
+            files,
+        })
+    }
+}
+
+fn parse_type(data_type: &str) -> Result<DataType> {
+    match DataType::from_str(data_type) {
+        Ok(data_type) => Ok(data_type),
+        Err(err) => config_err!("Invalid type '{data_type}': {err}"),
+    }
+}
+
+fn json_scalar(value: &serde_json::Value) -> ScalarValue {
+    match value {
+        serde_json::Value::Bool(value) => ScalarValue::Boolean(Some(*value)),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(value) => ScalarValue::Int64(Some(value)),
+            None => ScalarValue::Float64(number.as_f64()),
+        },
+        serde_json::Value::String(value) => ScalarValue::Utf8(Some(value.clone())),
+        _ => ScalarValue::Null,
+    }
+}
+
+/// The inverse of [`json_scalar`]: values of other types are written as
+/// strings, which `from_json` casts back to the column's type.
+fn scalar_json(value: &ScalarValue) -> Result<serde_json::Value> {
+    let data_type = value.data_type();
+    Ok(if value.is_null() {
+        serde_json::Value::Null
+    } else if let ScalarValue::Boolean(Some(value)) = value {
+        serde_json::Value::Bool(*value)
+    } else if data_type.is_integer() {
+        match value.cast_to(&DataType::Int64)? {
+            ScalarValue::Int64(Some(value)) => value.into(),
+            _ => serde_json::Value::Null,
+        }
+    } else if data_type.is_floating() {
+        match value.cast_to(&DataType::Float64)? {
+            ScalarValue::Float64(Some(value)) => value.into(),
+            _ => serde_json::Value::Null,
+        }
+    } else {
+        value.to_string().into()
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestJson {
+    versions: Vec<VersionJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionJson {
+    version: u64,
+    columns: Vec<ColumnJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    migration: Vec<StepJson>,
+    files: RuleJson,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ColumnJson {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+    #[serde(default = "nullable_default")]
+    nullable: bool,
+}
+
+fn nullable_default() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum StepJson {
+    Rename {
+        from: String,
+        to: String,
+    },
+    Cast {
+        column: String,
+        #[serde(rename = "type")]
+        data_type: String,
+    },
+    Drop {
+        column: String,
+    },
+    Scale {
+        column: String,
+        factor: f64,
+    },
+    Add {
+        column: String,
+        #[serde(rename = "type")]
+        data_type: String,
+        #[serde(default)]
+        default: serde_json::Value,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RuleJson {
+    Path(String),
+    Metadata { key: String, value: String },
+    Paths(Vec<String>),
+}
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use datafusion::common::{Result, config_err};
+
+use crate::format::FileContext;
+
+/// Columns that were renamed during the life of a dataset.
+///
+/// Files written before a rename store the column under its historical name; the
+/// mapping lets them be read under the current table name:
+///
+/// ```ignore
+/// let mapping = FieldMapping::new()
+///     .with_rename("customer_id", "cust_id")
+///     .with_scoped_rename("uid", "user_id", MappingScope::path_prefix("events/2023/"));
+/// ```
+///
+/// A rename only applies to files that lack the current name but have the
+/// historical one, and whose path and modification time fall in its
+/// [`MappingScope`]. Renames can be chained (`a -> b`, then `b -> c`).
+///
+/// Only top-level columns can be renamed. A rename of a field inside a struct
+/// column, such as `payload.meta.old -> payload.meta.new`, is rejected by
+/// [`Self::validate`]; the field is read as missing from older files.
+#[derive(Debug, Clone, Default)]
+pub struct FieldMapping {
+    renames: Vec<Rename>,
+}
+
+impl FieldMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the column `from` of every file as `to`.
+    pub fn with_rename(self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.with_scoped_rename(from, to, MappingScope::All)
+    }
+
+    /// Read the column `from` as `to` in the files within `scope`.
+    pub fn with_scoped_rename(
+        mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        scope: MappingScope,
+    ) -> Self {
+        self.renames.push(Rename {
+            from: from.into(),
+            to: to.into(),
+            scope,
+        });
+        self
+    }
+
+    pub fn renames(&self) -> &[Rename] {
+        &self.renames
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.renames.is_empty()
+    }
+
+    /// Check that every rename targets a top-level column of `table_schema`
+    /// rather than a field nested in one, e.g. `payload.meta.new` inside the
+    /// struct column `payload`, which is not supported.
+    pub fn validate(&self, table_schema: &Schema) -> Result<()> {
+        for rename in &self.renames {
+            if table_schema.field_with_name(&rename.to).is_ok() {
+                continue;
+            }
+            let parent = rename
+                .to
+                .match_indices('.')
+                .map(|(end, _)| &rename.to[..end])
+                .find(|parent| {
+                    table_schema
+                        .field_with_name(parent)
+                        .is_ok_and(|field| field.data_type().is_nested())
+                });
+            if let Some(parent) = parent {
+                return config_err!(
+                    "Cannot rename '{}' to '{}': renames of fields nested in '{parent}' are not supported, only of top-level columns",
+                    rename.from,
+                    rename.to
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The name the table column `name` is stored under in a file with
+    /// `file_schema`, if the file has it under the current or a historical name.
+    ///
+    /// `file` is the file being read, when known; renames with a narrower scope
+    /// than [`MappingScope::All`] are skipped without it.
+    pub fn resolve<'a>(
+        &'a self,
+        name: &'a str,
+        file_schema: &Schema,
+        file: Option<&FileContext>,
+    ) -> Option<&'a str> {
+        let mut visited = HashSet::new();
+        let mut pending = vec![name];
+        while let Some(current) = pending.pop() {
+            if !visited.insert(current) {
+                continue;
+            }
+            if file_schema.field_with_name(current).is_ok() {
+                return Some(current);
+            }
+            pending.extend(
+                self.renames
+                    .iter()
+                    .filter(|rename| rename.to == current && rename.scope.matches(file))
+                    .map(|rename| rename.from.as_str()),
+            );
+        }
+        None
+    }
+
+    /// `file_schema` with the columns stored under historical names renamed to
+    /// their current names.
+    pub fn apply(&self, file_schema: &SchemaRef, file: Option<&FileContext>) -> SchemaRef {
+        let mut renamed = false;
+        let fields: Vec<Field> = file_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let current = self.current_name(field.name(), file_schema, file);
+                if current == field.name() {
+                    return field.as_ref().clone();
+                }
+                renamed = true;
+                field.as_ref().clone().with_name(current)
+            })
+            .collect();
+        if !renamed {
+            return Arc::clone(file_schema);
+        }
+        Arc::new(Schema::new_with_metadata(
+            fields,
+            file_schema.metadata().clone(),
+        ))
+    }
+
+    /// Follow the renames of the file column `name` as long as the file does not
+    /// also have the new name.
+    fn current_name<'a>(
+        &'a self,
+        name: &'a str,
+        file_schema: &Schema,
+        file: Option<&FileContext>,
+    ) -> &'a str {
+        let mut visited = HashSet::new();
+        let mut current = name;
+        while visited.insert(current) {
+            let Some(rename) = self.renames.iter().find(|rename| {
+                rename.from == current
+                    && rename.scope.matches(file)
+                    && file_schema.field_with_name(&rename.to).is_err()
+            }) else {
+                break;
+            };
+            current = &rename.to;
+        }
+        current
+    }
+}
+
+/// One entry of a [`FieldMapping`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    /// The name older files store the column under.
+    pub from: String,
+    /// The name the table exposes.
+    pub to: String,
+    pub scope: MappingScope,
+}
+
+/// The files a [`Rename`] applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MappingScope {
+    /// Every file.
+    All,
+    /// Files whose path, relative to the object store root, starts with the prefix.
+    PathPrefix(String),
+    /// Files last modified within the range.
+    Modified(Range<SystemTime>),
+}
+
+impl MappingScope {
+    pub fn path_prefix(prefix: impl Into<String>) -> Self {
+        Self::PathPrefix(prefix.into())
+    }
+
+    /// Files last modified before `time`.
+    pub fn modified_before(time: SystemTime) -> Self {
+        Self::Modified(SystemTime::UNIX_EPOCH..time)
+    }
+
+    /// Whether the scope covers `file`. Only [`MappingScope::All`] covers a file
+    /// that is not known.
+    pub fn matches(&self, file: Option<&FileContext>) -> bool {
+        match (self, file) {
+            (Self::All, _) => true,
+            (Self::PathPrefix(prefix), Some(file)) => file
+                .path
+                .trim_start_matches('/')
+                .starts_with(prefix.trim_start_matches('/')),
+            (Self::Modified(range), Some(file)) => file
+                .last_modified
+                .is_some_and(|modified| range.contains(&modified)),
+            (_, None) => false,
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::common::Result;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+
+use crate::canonical::{CanonicalizeOptions, canonicalize_field, canonicalize_type};
+use crate::discovery::SchemaDiscovery;
+use crate::encoding::StringEncoding;
+use crate::format::FileContext;
+use crate::mapping::FieldMapping;
+use crate::nested::nested_supertype;
+use crate::policy::{
+    Coercion, CoercionError, CoercionMode, CoercionPolicy, can_cast, integer_digits,
+    is_lossless_widening, is_same_logical_type,
+};
+use crate::provider::SchemaResolution;
+use crate::temporal::temporal_supertype;
+
+/// Computes one table schema that every file of a dataset can be adapted to.
+///
+/// Columns are taken in the order they first appear (files are visited in path
+/// order), each column's type is the narrowest type all files' types coerce to
+/// under the [`CoercionPolicy`], and a column is nullable if any file has it
+/// nullable, lacks it, or needs a cast that may null out values. Columns stored under a historical name of a
+/// [`FieldMapping`] are merged under the current name.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaUnifier {
+    policy: CoercionPolicy,
+    mapping: FieldMapping,
+    discovery: SchemaDiscovery,
+    encoding: StringEncoding,
+}
+
+impl SchemaUnifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(mut self, policy: CoercionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &CoercionPolicy {
+        &self.policy
+    }
+
+    pub fn with_field_mapping(mut self, mapping: FieldMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    pub fn field_mapping(&self) -> &FieldMapping {
+        &self.mapping
+    }
+
+    /// How [`Self::unify_url`] reads the file schemas.
+    pub fn with_discovery(mut self, discovery: SchemaDiscovery) -> Self {
+        self.discovery = discovery;
+        self
+    }
+
+    pub fn discovery(&self) -> &SchemaDiscovery {
+        &self.discovery
+    }
+
+    /// The type of string and binary columns stored in different encodings;
+    /// plain `Utf8` or `Binary` by default.
+    pub fn with_string_encoding(mut self, encoding: StringEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn string_encoding(&self) -> StringEncoding {
+        self.encoding
+    }
+
+    /// List the files under `table_url`, read each file's schema from its footer
+    /// with the format in `options`, and unify them.
+    ///
+    /// Files the [`SchemaDiscovery`] skips are not in the report.
+    pub async fn unify_url(
+        &self,
+        state: &dyn Session,
+        table_url: &ListingTableUrl,
+        options: &ListingOptions,
+    ) -> Result<UnifiedSchema> {
+        let discovered = self.discovery.discover(state, table_url, options).await?;
+        Ok(self.unify_files(discovered.files))
+    }
+
+    /// Unify already known file schemas, given as `(path, schema)` pairs.
+    ///
+    /// Renames scoped by modification time do not apply, as the times are not
+    /// known; use [`Self::unify_url`] for those.
+    pub fn unify(&self, files: Vec<(String, SchemaRef)>) -> UnifiedSchema {
+        self.unify_files(
+            files
+                .into_iter()
+                .map(|(path, schema)| (FileContext::new(path), schema))
+                .collect(),
+        )
+    }
+
+    fn unify_files(&self, mut files: Vec<(FileContext, SchemaRef)>) -> UnifiedSchema {
+        // Listing order is not deterministic on every object store
+        files.sort_by(|(left, _), (right, _)| left.path.cmp(&right.path));
+        let files: Vec<_> = files
+            .into_iter()
+            .map(|(file, schema)| {
+                let renamed = self.mapping.apply(&schema, Some(&file));
+                (file.path, schema, renamed)
+            })
+            .collect();
+
+        // The writer metadata of the first file is not the table's
+        let metadata_only = CanonicalizeOptions::metadata_only();
+        let mut fields: Vec<Field> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for (_, _, schema) in &files {
+            for field in schema.fields() {
+                match positions.get(field.name()) {
+                    Some(&position) => {
+                        let merged = &mut fields[position];
+                        let mode = self.policy.mode_for(field.name());
+                        if let Some(data_type) =
+                            common_type(merged.data_type(), field.data_type(), mode)
+                        {
+                            merged.set_data_type(data_type);
+                        }
+                        if field.is_nullable() {
+                            merged.set_nullable(true);
+                        }
+                    }
+                    None => {
+                        positions.insert(field.name().clone(), fields.len());
+                        fields.push(canonicalize_field(field, &metadata_only).as_ref().clone());
+                    }
+                }
+            }
+        }
+        for field in &mut fields {
+            let file_types: Vec<_> = files
+                .iter()
+                .filter_map(|(_, _, schema)| schema.field_with_name(field.name()).ok())
+                .map(|file_field| file_field.data_type())
+                .collect();
+            field.set_data_type(self.encoding.apply(field.data_type(), &file_types));
+
+            let nullable = files.iter().any(|(_, _, schema)| {
+                let Ok(file_field) = schema.field_with_name(field.name()) else {
+                    return true;
+                };
+                matches!(
+                    self.policy
+                        .resolve(field.name(), file_field.data_type(), field.data_type()),
+                    Ok(Coercion::Lenient | Coercion::ViaString)
+                )
+            });
+            if nullable {
+                field.set_nullable(true);
+            }
+        }
+
+        let schema = Arc::new(Schema::new(fields));
+        let files = files
+            .into_iter()
+            .map(|(path, file_schema, renamed)| {
+                let deviations = file_schema
+                    .fields()
+                    .iter()
+                    .zip(renamed.fields())
+                    .filter(|(stored, current)| stored.name() != current.name())
+                    .map(|(stored, current)| Deviation::Renamed {
+                        column: current.name().clone(),
+                        file_column: stored.name().clone(),
+                    })
+                    .chain(self.deviations(&path, &renamed, &schema))
+                    .collect();
+                FileReport {
+                    path,
+                    schema: file_schema,
+                    deviations,
+                }
+            })
+            .collect();
+
+        UnifiedSchema {
+            schema,
+            report: MergeReport {
+                files,
+                resolution: None,
+            },
+        }
+    }
+
+    fn deviations(
+        &self,
+        path: &str,
+        file_schema: &Schema,
+        table_schema: &Schema,
+    ) -> Vec<Deviation> {
+        table_schema
+            .fields()
+            .iter()
+            .filter_map(|table_field| {
+                let Ok(file_field) = file_schema.field_with_name(table_field.name()) else {
+                    return Some(Deviation::MissingColumn {
+                        column: table_field.name().clone(),
+                    });
+                };
+                match self.policy.resolve(
+                    table_field.name(),
+                    file_field.data_type(),
+                    table_field.data_type(),
+                ) {
+                    Ok(Coercion::Identity) => None,
+                    Ok(coercion) => Some(Deviation::TypeMismatch {
+                        column: table_field.name().clone(),
+                        file_type: file_field.data_type().clone(),
+                        table_type: table_field.data_type().clone(),
+                        coercion,
+                    }),
+                    Err(err) => Some(Deviation::Conflict(err.with_file(path))),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Unify the schemas of all files under `table_url` with the default policy.
+pub async fn merge_schemas(
+    state: &dyn Session,
+    table_url: &ListingTableUrl,
+    options: &ListingOptions,
+) -> Result<UnifiedSchema> {
+    SchemaUnifier::new()
+        .unify_url(state, table_url, options)
+        .await
+}
+
+/// The result of [`SchemaUnifier`]: the table schema and how each file differs.
+#[derive(Debug, Clone)]
+pub struct UnifiedSchema {
+    pub schema: SchemaRef,
+    pub report: MergeReport,
+}
+
+/// How each file's schema deviates from a unified schema.
+#[derive(Debug, Clone, Default)]
+pub struct MergeReport {
+    pub files: Vec<FileReport>,
+    /// Where a provider took the table schema from; `None` for a report of
+    /// the unifier alone.
+    pub resolution: Option<SchemaResolution>,
+}
+
+impl MergeReport {
+    /// Files with at least one deviation.
+    pub fn deviating_files(&self) -> impl Iterator<Item = &FileReport> {
+        self.files.iter().filter(|file| !file.deviations.is_empty())
+    }
+
+    /// Columns that cannot be coerced, across all files.
+    pub fn conflicts(&self) -> impl Iterator<Item = &CoercionError> {
+        self.files
+            .iter()
+            .flat_map(|file| &file.deviations)
+            .filter_map(|deviation| match deviation {
+                Deviation::Conflict(err) => Some(err),
+                _ => None,
+            })
+    }
+
+    /// Whether every file can be adapted to the unified schema.
+    pub fn is_compatible(&self) -> bool {
+        self.conflicts().next().is_none()
+    }
+}
+
+impl fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(resolution) = &self.resolution {
+            writeln!(f, "{resolution}")?;
+        }
+        for file in self.deviating_files() {
+            writeln!(f, "{}", file.path)?;
+            for deviation in &file.deviations {
+                writeln!(f, "  {deviation}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The schema of one file and how it deviates from the unified schema.
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub path: String,
+    pub schema: SchemaRef,
+    pub deviations: Vec<Deviation>,
+}
+
+/// One difference between a file's schema and the unified schema.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Deviation {
+    /// The file stores the column under a historical name.
+    Renamed { column: String, file_column: String },
+    /// The file lacks the column; it is read as null.
+    MissingColumn { column: String },
+    /// The file stores the column with another type that the policy coerces.
+    TypeMismatch {
+        column: String,
+        file_type: DataType,
+        table_type: DataType,
+        coercion: Coercion,
+    },
+    /// The file stores the column with a type the policy cannot coerce.
+    Conflict(CoercionError),
+}
+
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Renamed {
+                column,
+                file_column,
+            } => write!(f, "column '{column}' is stored as '{file_column}'"),
+            Self::MissingColumn { column } => write!(f, "missing column '{column}'"),
+            Self::TypeMismatch {
+                column,
+                file_type,
+                table_type,
+                coercion,
+            } => write!(
+                f,
+                "column '{column}' is {file_type}, coerced to {table_type} ({coercion:?})"
+            ),
+            Self::Conflict(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// The narrowest type both `left` and `right` can be coerced to under `mode`.
+pub fn common_type(left: &DataType, right: &DataType, mode: CoercionMode) -> Option<DataType> {
+    if left == right {
+        return Some(left.clone());
+    }
+    if is_same_logical_type(left, right) {
+        return Some(canonicalize_type(left, &CanonicalizeOptions::default()));
+    }
+    if is_lossless_widening(left, right) {
+        return Some(right.clone());
+    }
+    if is_lossless_widening(right, left) {
+        return Some(left.clone());
+    }
+
+    if left.is_nested() || right.is_nested() {
+        return match mode {
+            CoercionMode::Strict => None,
+            _ => nested_supertype(left, right, &|left, right| common_type(left, right, mode)),
+        };
+    }
+
+    if mode != CoercionMode::Strict
+        && let Some(supertype) = temporal_supertype(left, right)
+    {
+        return Some(supertype);
+    }
+
+    match mode {
+        CoercionMode::Strict => None,
+        CoercionMode::Widening => numeric_supertype(left, right, false),
+        CoercionMode::Lenient => numeric_supertype(left, right, true).or_else(|| {
+            (can_cast(left, &DataType::Utf8) && can_cast(right, &DataType::Utf8))
+                .then_some(DataType::Utf8)
+        }),
+    }
+}
+
+/// A numeric type holding every value of both types. With `lossy`, `Int64`
+/// or a decimal mixed with floats becomes `Float64` even though large
+/// integers and decimal fractions lose precision.
+fn numeric_supertype(left: &DataType, right: &DataType, lossy: bool) -> Option<DataType> {
+    use DataType::*;
+
+    match (left, right) {
+        (l, r) if l.is_integer() && r.is_integer() => {
+            // Mixed signedness: a signed type with room for the unsigned one
+            let digits = integer_digits(l)?.max(integer_digits(r)?);
+            [Int16, Int32, Int64]
+                .into_iter()
+                .find(|candidate| {
+                    is_lossless_widening(l, candidate) && is_lossless_widening(r, candidate)
+                })
+                .or_else(|| decimal_type(digits as u8, 0))
+        }
+        (l, r) if (l.is_integer() && r.is_floating()) || (l.is_floating() && r.is_integer()) => {
+            let candidate = Float64;
+            (lossy || (is_lossless_widening(l, &candidate) && is_lossless_widening(r, &candidate)))
+                .then_some(candidate)
+        }
+        // No decimal holds every float, and no float every decimal
+        (l, r) if (is_decimal(l) && r.is_floating()) || (l.is_floating() && is_decimal(r)) => {
+            lossy.then_some(Float64)
+        }
+        (l, r) if l.is_numeric() && r.is_numeric() => {
+            let (left_digits, left_scale) = decimal_shape(l)?;
+            let (right_digits, right_scale) = decimal_shape(r)?;
+            let scale = left_scale.max(right_scale);
+            let digits = left_digits.max(right_digits);
+            decimal_type(u8::try_from(digits + i16::from(scale)).ok()?, scale)
+        }
+        _ => None,
+    }
+}
+
+/// Integer digits and scale of a decimal or integer type.
+fn decimal_shape(data_type: &DataType) -> Option<(i16, i8)> {
+    match data_type {
+        DataType::Decimal32(precision, scale)
+        | DataType::Decimal64(precision, scale)
+        | DataType::Decimal128(precision, scale)
+        | DataType::Decimal256(precision, scale) => {
+            Some((i16::from(*precision) - i16::from(*scale), (*scale).max(0)))
+        }
+        other => integer_digits(other).map(|digits| (digits, 0)),
+    }
+}
+
+fn is_decimal(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Decimal32(..)
+            | DataType::Decimal64(..)
+            | DataType::Decimal128(..)
+            | DataType::Decimal256(..)
+    )
+}
+
+fn decimal_type(precision: u8, scale: i8) -> Option<DataType> {
+    match precision {
+        0..=38 => Some(DataType::Decimal128(precision, scale)),
+        39..=76 => Some(DataType::Decimal256(precision, scale)),
+        _ => None,
+    }
+}
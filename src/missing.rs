@@ -0,0 +1,226 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use arrow::array::{Array, ArrayRef, new_null_array};
+use arrow::datatypes::{DataType, Field, FieldRef, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DFSchema, Result, ScalarValue, exec_err};
+use datafusion::execution::context::ExecutionProps;
+use datafusion::logical_expr::{ColumnarValue, Expr, ExprSchemable};
+use datafusion::physical_expr::expressions::lit;
+use datafusion::physical_expr::{PhysicalExpr, create_physical_expr};
+
+use crate::format::FileContext;
+
+/// What a column reads as in files that do not have it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ColumnDefault {
+    #[default]
+    Null,
+    /// A constant, cast to the column's type.
+    Literal(ScalarValue),
+    /// An expression over other table columns, e.g. `concat(first, ' ', last)`.
+    ///
+    /// The expression is not type-coerced, only its result is cast to the
+    /// column's type; referenced columns are adapted from the file like any
+    /// other column, so they may be missing or defaulted themselves.
+    Expr(Expr),
+    /// The time the file was last modified, cast to the column's type. Only
+    /// known when reading through [`EvolvingFormat`](crate::format::EvolvingFormat).
+    FileModified,
+}
+
+/// Chooses the [`ColumnDefault`] of columns that older files lack. Columns
+/// without a configured default read as null.
+///
+/// ```ignore
+/// let policy = MissingColumnPolicy::new()
+///     .with_literal("region", "unknown")
+///     .with_column("created_at", ColumnDefault::FileModified);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MissingColumnPolicy {
+    columns: HashMap<String, ColumnDefault>,
+}
+
+impl MissingColumnPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_column(mut self, name: impl Into<String>, default: ColumnDefault) -> Self {
+        self.columns.insert(name.into(), default);
+        self
+    }
+
+    pub fn with_literal(self, name: impl Into<String>, value: impl Into<ScalarValue>) -> Self {
+        self.with_column(name, ColumnDefault::Literal(value.into()))
+    }
+
+    pub fn with_expr(self, name: impl Into<String>, expr: Expr) -> Self {
+        self.with_column(name, ColumnDefault::Expr(expr))
+    }
+
+    /// The default of the column `name`.
+    pub fn default_for(&self, name: &str) -> &ColumnDefault {
+        static NULL: ColumnDefault = ColumnDefault::Null;
+        self.columns.get(name).unwrap_or(&NULL)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+impl ColumnDefault {
+    /// The expression producing `target` in a file without it, over the table
+    /// schema `table_schema`. `file` is the file being read, when known.
+    pub(crate) fn to_physical(
+        &self,
+        target: &Field,
+        table_schema: &SchemaRef,
+        file: Option<&FileContext>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let value = match self {
+            Self::Null => ScalarValue::Null,
+            Self::Literal(value) => value.clone(),
+            Self::FileModified => {
+                let Some(modified) = file.and_then(|file| file.last_modified) else {
+                    return exec_err!(
+                        "Column '{}' defaults to the file modification time, which is only known \
+                         when reading through EvolvingFormat",
+                        target.name()
+                    );
+                };
+                file_modified_scalar(modified)
+            }
+            Self::Expr(expr) => {
+                let df_schema = DFSchema::try_from(table_schema.as_ref().clone())?;
+                let expr = if &expr.get_type(&df_schema)? == target.data_type() {
+                    expr.clone()
+                } else {
+                    expr.clone().cast_to(target.data_type(), &df_schema)?
+                };
+                return create_physical_expr(&expr, &df_schema, &ExecutionProps::new());
+            }
+        };
+        if value.is_null() && !target.is_nullable() {
+            return exec_err!(
+                "Non-nullable column '{}' is missing from the file",
+                target.name()
+            );
+        }
+        Ok(lit(value.cast_to(target.data_type())?))
+    }
+}
+
+fn file_modified_scalar(modified: SystemTime) -> ScalarValue {
+    let micros = match modified.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_micros() as i64,
+        Err(before) => -(before.duration().as_micros() as i64),
+    };
+    ScalarValue::TimestampMicrosecond(Some(micros), Some("UTC".into()))
+}
+
+/// A column of nulls that reuses one array across batches instead of
+/// allocating a new one each time, which adds up on wide tables where many
+/// columns are missing from older files.
+///
+/// The adapter only uses it for bare column references, i.e. projections;
+/// inside other expressions a missing column stays a null literal so that
+/// predicates over it can be folded and the file pruned.
+pub struct NullArrayExpr {
+    field: FieldRef,
+    /// The longest array produced so far; shorter batches get a slice of it.
+    cache: Mutex<Option<ArrayRef>>,
+}
+
+impl NullArrayExpr {
+    pub fn new(field: FieldRef) -> Self {
+        Self {
+            field,
+            cache: Mutex::new(None),
+        }
+    }
+
+    fn null_array(&self, len: usize) -> ArrayRef {
+        let mut cache = self.cache.lock().unwrap_or_else(|err| err.into_inner());
+        match cache.as_ref() {
+            Some(array) if array.len() >= len => array.slice(0, len),
+            _ => {
+                let array = new_null_array(self.field.data_type(), len);
+                *cache = Some(Arc::clone(&array));
+                array
+            }
+        }
+    }
+}
+
+impl fmt::Debug for NullArrayExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NullArrayExpr")
+            .field("field", &self.field)
+            .finish()
+    }
+}
+
+impl fmt::Display for NullArrayExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NULL({})", self.field.data_type())
+    }
+}
+
+impl PartialEq for NullArrayExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.field == other.field
+    }
+}
+
+impl Eq for NullArrayExpr {}
+
+impl Hash for NullArrayExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.field.hash(state);
+    }
+}
+
+impl PhysicalExpr for NullArrayExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.field.data_type().clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        Ok(ColumnarValue::Array(self.null_array(batch.num_rows())))
+    }
+
+    fn return_field(&self, _input_schema: &Schema) -> Result<FieldRef> {
+        Ok(Arc::clone(&self.field))
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(self)
+    }
+
+    fn fmt_sql(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,380 @@
+use std::any::Any;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, GenericListArray, MapArray, OffsetSizeTrait, StructArray,
+    new_null_array,
+};
+use arrow::buffer::OffsetBuffer;
+use arrow::compute::{CastOptions, can_cast_types, cast_with_options};
+use arrow::datatypes::{DataType, Field, FieldRef, Fields, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{Result, ScalarValue, exec_err};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+
+/// Cast `array` to `target`, reconciling nested types at every level: struct
+/// fields are matched by name, reordered, cast, and added as nulls when
+/// missing, and list elements and map entries are adapted recursively.
+///
+/// Arrow's cast only matches struct fields of equal count, so a list of structs
+/// whose element gained a field cannot be cast with it.
+pub fn cast_nested(array: &ArrayRef, target: &DataType, options: &CastOptions) -> Result<ArrayRef> {
+    if array.data_type() == target {
+        return Ok(Arc::clone(array));
+    }
+
+    match (array.data_type(), target) {
+        (DataType::Struct(_), DataType::Struct(target_fields)) => {
+            cast_struct(array.as_struct(), target_fields, options)
+        }
+        (DataType::List(_), DataType::List(target_field)) => {
+            cast_list::<i32, i32>(array.as_list(), target_field, options)
+        }
+        (DataType::List(_), DataType::LargeList(target_field)) => {
+            cast_list::<i32, i64>(array.as_list(), target_field, options)
+        }
+        (DataType::LargeList(_), DataType::List(target_field)) => {
+            cast_list::<i64, i32>(array.as_list(), target_field, options)
+        }
+        (DataType::LargeList(_), DataType::LargeList(target_field)) => {
+            cast_list::<i64, i64>(array.as_list(), target_field, options)
+        }
+        (DataType::Map(_, _), DataType::Map(target_entries, ordered)) => {
+            cast_map(array.as_map(), target_entries, *ordered, options)
+        }
+        (
+            DataType::ListView(source) | DataType::LargeListView(source),
+            DataType::List(_) | DataType::LargeList(_),
+        ) => {
+            // Materialize the view with the source element type first
+            let list_type = match target {
+                DataType::List(_) => DataType::List(Arc::clone(source)),
+                _ => DataType::LargeList(Arc::clone(source)),
+            };
+            let list = cast_with_options(array, &list_type, options)?;
+            cast_nested(&list, target, options)
+        }
+        _ => Ok(cast_with_options(array, target, options)?),
+    }
+}
+
+fn cast_struct(
+    array: &StructArray,
+    target_fields: &Fields,
+    options: &CastOptions,
+) -> Result<ArrayRef> {
+    let columns = target_fields
+        .iter()
+        .map(
+            |target_field| match array.column_by_name(target_field.name()) {
+                Some(column) => cast_nested(column, target_field.data_type(), options),
+                None if target_field.is_nullable() => {
+                    Ok(new_null_array(target_field.data_type(), array.len()))
+                }
+                None => exec_err!(
+                    "Non-nullable struct field '{}' is missing from the file",
+                    target_field.name()
+                ),
+            },
+        )
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Arc::new(StructArray::try_new(
+        target_fields.clone(),
+        columns,
+        array.nulls().cloned(),
+    )?))
+}
+
+fn cast_list<I: OffsetSizeTrait, O: OffsetSizeTrait>(
+    array: &GenericListArray<I>,
+    target_field: &FieldRef,
+    options: &CastOptions,
+) -> Result<ArrayRef> {
+    let values = cast_nested(array.values(), target_field.data_type(), options)?;
+    let offsets = array
+        .offsets()
+        .iter()
+        .map(|offset| {
+            O::from_usize(offset.as_usize()).ok_or_else(|| {
+                datafusion::error::DataFusionError::Execution(format!(
+                    "List offset {} does not fit the target list type",
+                    offset.as_usize()
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Arc::new(GenericListArray::<O>::try_new(
+        Arc::clone(target_field),
+        OffsetBuffer::new(offsets.into()),
+        values,
+        array.nulls().cloned(),
+    )?))
+}
+
+fn cast_map(
+    array: &MapArray,
+    target_entries: &FieldRef,
+    ordered: bool,
+    options: &CastOptions,
+) -> Result<ArrayRef> {
+    let DataType::Struct(target_fields) = target_entries.data_type() else {
+        return exec_err!(
+            "Map entries must be a struct, got {}",
+            target_entries.data_type()
+        );
+    };
+    // Entry fields are matched by position: writers disagree on their names
+    let entries = array.entries();
+    let columns = entries
+        .columns()
+        .iter()
+        .zip(target_fields.iter())
+        .map(|(column, target_field)| cast_nested(column, target_field.data_type(), options))
+        .collect::<Result<Vec<_>>>()?;
+    let entries = StructArray::try_new(target_fields.clone(), columns, entries.nulls().cloned())?;
+
+    Ok(Arc::new(MapArray::try_new(
+        Arc::clone(target_entries),
+        array.offsets().clone(),
+        entries,
+        array.nulls().cloned(),
+        ordered,
+    )?))
+}
+
+/// Whether [`cast_nested`] can cast `from` to `to`.
+pub fn can_cast_nested(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+
+    match (from, to) {
+        (Struct(from), Struct(to)) => {
+            to.iter().all(
+                |to_field| match from.iter().find(|f| f.name() == to_field.name()) {
+                    Some(from_field) => {
+                        (to_field.is_nullable() || !from_field.is_nullable())
+                            && can_cast_nested(from_field.data_type(), to_field.data_type())
+                    }
+                    None => to_field.is_nullable(),
+                },
+            )
+        }
+        (Struct(_), _) | (_, Struct(_)) => false,
+        (
+            List(from) | LargeList(from) | ListView(from) | LargeListView(from),
+            List(to) | LargeList(to),
+        ) => {
+            (to.is_nullable() || !from.is_nullable())
+                && can_cast_nested(from.data_type(), to.data_type())
+        }
+        (Map(from, _), Map(to, _)) => match (from.data_type(), to.data_type()) {
+            (Struct(from), Struct(to)) => {
+                from.len() == to.len()
+                    && from
+                        .iter()
+                        .zip(to.iter())
+                        .all(|(from, to)| can_cast_nested(from.data_type(), to.data_type()))
+            }
+            _ => false,
+        },
+        _ => can_cast_types(from, to),
+    }
+}
+
+/// Casts its child with [`cast_nested`]. Used by the adapter instead of
+/// `CastColumnExpr` for nested columns, and for casts that do not keep the
+/// order of the values: unlike `CastColumnExpr`, statistics pruning does not
+/// look through it.
+#[derive(Debug, Clone, Eq)]
+pub struct NestedCastExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    target_field: FieldRef,
+    cast_options: CastOptions<'static>,
+}
+
+impl NestedCastExpr {
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        target_field: FieldRef,
+        cast_options: CastOptions<'static>,
+    ) -> Self {
+        Self {
+            expr,
+            target_field,
+            cast_options,
+        }
+    }
+
+    pub fn expr(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.expr
+    }
+
+    pub fn target_field(&self) -> &FieldRef {
+        &self.target_field
+    }
+}
+
+impl PartialEq for NestedCastExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.expr.eq(&other.expr)
+            && self.target_field == other.target_field
+            && self.cast_options == other.cast_options
+    }
+}
+
+impl Hash for NestedCastExpr {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.expr.hash(state);
+        self.target_field.hash(state);
+        self.cast_options.hash(state);
+    }
+}
+
+impl fmt::Display for NestedCastExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CAST_NESTED({} AS {})",
+            self.expr,
+            self.target_field.data_type()
+        )
+    }
+}
+
+impl PhysicalExpr for NestedCastExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.target_field.data_type().clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(self.target_field.is_nullable())
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let target = self.target_field.data_type();
+        match self.expr.evaluate(batch)? {
+            ColumnarValue::Array(array) => Ok(ColumnarValue::Array(cast_nested(
+                &array,
+                target,
+                &self.cast_options,
+            )?)),
+            ColumnarValue::Scalar(scalar) => {
+                let array = cast_nested(&scalar.to_array()?, target, &self.cast_options)?;
+                Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                    &array, 0,
+                )?))
+            }
+        }
+    }
+
+    fn return_field(&self, _input_schema: &Schema) -> Result<FieldRef> {
+        Ok(Arc::clone(&self.target_field))
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn PhysicalExpr>> {
+        vec![&self.expr]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let Some(child) = children.pop() else {
+            return exec_err!("NestedCastExpr expects one child");
+        };
+        Ok(Arc::new(Self::new(
+            child,
+            Arc::clone(&self.target_field),
+            self.cast_options.clone(),
+        )))
+    }
+
+    fn fmt_sql(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A nested type both `left` and `right` reconcile to: the union of struct fields
+/// by name, with fields only one side has made nullable, and element, key and
+/// value types merged with `merge_leaf`.
+pub(crate) fn nested_supertype(
+    left: &DataType,
+    right: &DataType,
+    merge_leaf: &dyn Fn(&DataType, &DataType) -> Option<DataType>,
+) -> Option<DataType> {
+    use DataType::*;
+
+    let merge_field = |left: &Field, right: &Field| -> Option<Field> {
+        let data_type = merge(left.data_type(), right.data_type(), merge_leaf)?;
+        Some(
+            left.clone()
+                .with_data_type(data_type)
+                .with_nullable(left.is_nullable() || right.is_nullable()),
+        )
+    };
+
+    match (left, right) {
+        (Struct(left), Struct(right)) => {
+            let mut fields: Vec<Field> = Vec::with_capacity(left.len().max(right.len()));
+            for left_field in left {
+                match right.iter().find(|f| f.name() == left_field.name()) {
+                    Some(right_field) => fields.push(merge_field(left_field, right_field)?),
+                    None => fields.push(left_field.as_ref().clone().with_nullable(true)),
+                }
+            }
+            for right_field in right {
+                if left.find(right_field.name()).is_none() {
+                    fields.push(right_field.as_ref().clone().with_nullable(true));
+                }
+            }
+            Some(Struct(fields.into()))
+        }
+        (List(l) | LargeList(l), List(r) | LargeList(r)) => {
+            let field = Arc::new(merge_field(l, r)?);
+            // Keep 64-bit offsets if either side needed them
+            Some(
+                if matches!(left, LargeList(_)) || matches!(right, LargeList(_)) {
+                    LargeList(field)
+                } else {
+                    List(field)
+                },
+            )
+        }
+        (Map(l, ordered), Map(r, _)) => {
+            let (Struct(l_fields), Struct(r_fields)) = (l.data_type(), r.data_type()) else {
+                return None;
+            };
+            if l_fields.len() != r_fields.len() {
+                return None;
+            }
+            let fields = l_fields
+                .iter()
+                .zip(r_fields.iter())
+                .map(|(l, r)| merge_field(l, r))
+                .collect::<Option<Vec<_>>>()?;
+            let entries = l.as_ref().clone().with_data_type(Struct(fields.into()));
+            Some(Map(Arc::new(entries), *ordered))
+        }
+        _ => None,
+    }
+}
+
+fn merge(
+    left: &DataType,
+    right: &DataType,
+    merge_leaf: &dyn Fn(&DataType, &DataType) -> Option<DataType>,
+) -> Option<DataType> {
+    if left.is_nested() || right.is_nested() {
+        nested_supertype(left, right, merge_leaf)
+    } else {
+        merge_leaf(left, right)
+    }
+}
@@ -0,0 +1,255 @@
+use std::any::Any;
+use std::borrow::Cow;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, AsArray, GenericStringArray, OffsetSizeTrait, StringViewArray};
+use arrow::datatypes::{DataType, FieldRef, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{Result, ScalarValue, exec_err};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+use icu_normalizer::ComposingNormalizerBorrowed;
+
+/// How the values of a string column are normalized when read, so that values
+/// different writers spelled differently compare equal, e.g. in joins between
+/// old and new data.
+///
+/// Steps apply in the order Unicode normalization, case folding, trimming:
+///
+/// ```ignore
+/// let policy = CoercionPolicy::lenient().with_normalization(
+///     "country",
+///     StringNormalization::new().with_trim().with_case(CaseFold::Upper),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct StringNormalization {
+    pub unicode: Option<UnicodeForm>,
+    pub case: Option<CaseFold>,
+    /// Strip leading and trailing whitespace.
+    pub trim: bool,
+}
+
+/// A Unicode normalization form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnicodeForm {
+    /// Canonical composition: `e` followed by a combining acute accent becomes
+    /// `é`.
+    Nfc,
+    /// Compatibility composition: additionally folds variants such as the `ﬁ`
+    /// ligature or full-width digits.
+    Nfkc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaseFold {
+    Lower,
+    Upper,
+}
+
+impl StringNormalization {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_unicode(mut self, form: UnicodeForm) -> Self {
+        self.unicode = Some(form);
+        self
+    }
+
+    pub fn with_case(mut self, case: CaseFold) -> Self {
+        self.case = Some(case);
+        self
+    }
+
+    pub fn with_trim(mut self) -> Self {
+        self.trim = true;
+        self
+    }
+
+    /// Whether normalizing changes nothing.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Normalize one value.
+    pub fn normalize<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        let mut value = match self.unicode {
+            Some(UnicodeForm::Nfc) => ComposingNormalizerBorrowed::new_nfc().normalize(value),
+            Some(UnicodeForm::Nfkc) => ComposingNormalizerBorrowed::new_nfkc().normalize(value),
+            None => Cow::Borrowed(value),
+        };
+        match self.case {
+            Some(CaseFold::Lower) if value.chars().any(char::is_uppercase) => {
+                value = Cow::Owned(value.to_lowercase());
+            }
+            Some(CaseFold::Upper) if value.chars().any(char::is_lowercase) => {
+                value = Cow::Owned(value.to_uppercase());
+            }
+            _ => {}
+        }
+        if self.trim {
+            value = match value {
+                Cow::Borrowed(value) => Cow::Borrowed(value.trim()),
+                Cow::Owned(value) if value.trim().len() == value.len() => Cow::Owned(value),
+                Cow::Owned(value) => Cow::Owned(value.trim().to_string()),
+            };
+        }
+        value
+    }
+
+    /// Normalize the values of a string array, or of the values of a
+    /// dictionary of strings. Other arrays are returned as they are.
+    pub fn normalize_array(&self, array: &ArrayRef) -> Result<ArrayRef> {
+        if self.is_identity() {
+            return Ok(Arc::clone(array));
+        }
+        Ok(match array.data_type() {
+            DataType::Utf8 => self.normalize_strings(array.as_string::<i32>()),
+            DataType::LargeUtf8 => self.normalize_strings(array.as_string::<i64>()),
+            DataType::Utf8View => Arc::new(
+                array
+                    .as_string_view()
+                    .iter()
+                    .map(|value| value.map(|value| self.normalize(value)))
+                    .collect::<StringViewArray>(),
+            ),
+            DataType::Dictionary(_, _) => {
+                let dictionary = array.as_any_dictionary();
+                let values = self.normalize_array(dictionary.values())?;
+                dictionary.with_values(values)
+            }
+            _ => Arc::clone(array),
+        })
+    }
+
+    fn normalize_strings<O: OffsetSizeTrait>(&self, array: &GenericStringArray<O>) -> ArrayRef {
+        Arc::new(
+            array
+                .iter()
+                .map(|value| value.map(|value| self.normalize(value)))
+                .collect::<GenericStringArray<O>>(),
+        )
+    }
+}
+
+impl fmt::Display for StringNormalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut steps = Vec::new();
+        match self.unicode {
+            Some(UnicodeForm::Nfc) => steps.push("nfc"),
+            Some(UnicodeForm::Nfkc) => steps.push("nfkc"),
+            None => {}
+        }
+        match self.case {
+            Some(CaseFold::Lower) => steps.push("lower"),
+            Some(CaseFold::Upper) => steps.push("upper"),
+            None => {}
+        }
+        if self.trim {
+            steps.push("trim");
+        }
+        if steps.is_empty() {
+            steps.push("none");
+        }
+        f.write_str(&steps.join(", "))
+    }
+}
+
+/// Normalizes the strings its child evaluates to. Used by the adapter for
+/// columns the [`CoercionPolicy`](crate::CoercionPolicy) normalizes; statistics
+/// pruning does not look through it, since normalizing changes the order of the
+/// values.
+#[derive(Debug, Clone, Eq)]
+pub struct NormalizeExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    normalization: StringNormalization,
+}
+
+impl NormalizeExpr {
+    pub fn new(expr: Arc<dyn PhysicalExpr>, normalization: StringNormalization) -> Self {
+        Self {
+            expr,
+            normalization,
+        }
+    }
+
+    pub fn expr(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.expr
+    }
+
+    pub fn normalization(&self) -> &StringNormalization {
+        &self.normalization
+    }
+}
+
+impl PartialEq for NormalizeExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.expr.eq(&other.expr) && self.normalization == other.normalization
+    }
+}
+
+impl Hash for NormalizeExpr {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.expr.hash(state);
+        self.normalization.hash(state);
+    }
+}
+
+impl fmt::Display for NormalizeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NORMALIZE({}, {})", self.expr, self.normalization)
+    }
+}
+
+impl PhysicalExpr for NormalizeExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        self.expr.data_type(input_schema)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        self.expr.nullable(input_schema)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        match self.expr.evaluate(batch)? {
+            ColumnarValue::Array(array) => Ok(ColumnarValue::Array(
+                self.normalization.normalize_array(&array)?,
+            )),
+            ColumnarValue::Scalar(scalar) => {
+                let array = self.normalization.normalize_array(&scalar.to_array()?)?;
+                Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                    &array, 0,
+                )?))
+            }
+        }
+    }
+
+    fn return_field(&self, input_schema: &Schema) -> Result<FieldRef> {
+        self.expr.return_field(input_schema)
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn PhysicalExpr>> {
+        vec![&self.expr]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let Some(child) = children.pop() else {
+            return exec_err!("NormalizeExpr expects one child");
+        };
+        Ok(Arc::new(Self::new(child, self.normalization)))
+    }
+
+    fn fmt_sql(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::SystemTime;
+
+use datafusion::common::{DataFusionError, Result};
+use datafusion::object_store::ObjectMeta;
+use datafusion::object_store::path::Path;
+use futures::stream::BoxStream;
+use futures::{StreamExt, future};
+use tokio::sync::broadcast;
+
+use crate::discovery::object_version;
+
+/// A change to a file of a dataset, as reported by an object store
+/// notification such as an S3 event delivered through SQS.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileEvent {
+    /// The file was written, or overwritten.
+    Created(ObjectMeta),
+    /// The file was deleted at `at`.
+    Removed { location: Path, at: SystemTime },
+}
+
+impl FileEvent {
+    pub fn location(&self) -> &Path {
+        match self {
+            Self::Created(object) => &object.location,
+            Self::Removed { location, .. } => location,
+        }
+    }
+}
+
+/// A source of [`FileEvent`]s, so that new files are picked up as they are
+/// written instead of by listing the dataset again.
+///
+/// Delivery may be at least once and out of order, as with S3 events; wrap
+/// the events in [`deduplicated`] to drop repeated and stale ones.
+pub trait FileNotifications: fmt::Debug + Send + Sync {
+    /// The events from now on.
+    fn subscribe(&self) -> BoxStream<'static, Result<FileEvent>>;
+}
+
+/// [`FileNotifications`] fed by hand, e.g. from a queue consumer.
+///
+/// ```ignore
+/// let notifications = NotificationChannel::new(1024);
+/// let sender = notifications.clone();
+/// tokio::spawn(async move {
+///     while let Some(message) = queue.receive().await {
+///         sender.send(FileEvent::Created(message.object_meta()));
+///     }
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct NotificationChannel {
+    sender: broadcast::Sender<FileEvent>,
+}
+
+impl NotificationChannel {
+    /// A channel buffering up to `capacity` events for slow subscribers; a
+    /// subscriber that falls further behind fails.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            sender: broadcast::channel(capacity.max(1)).0,
+        }
+    }
+
+    /// Deliver `event` to the current subscribers.
+    pub fn send(&self, event: FileEvent) {
+        // Without subscribers there is nobody to tell
+        let _ = self.sender.send(event);
+    }
+}
+
+impl FileNotifications for NotificationChannel {
+    fn subscribe(&self) -> BoxStream<'static, Result<FileEvent>> {
+        futures::stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+            match receiver.recv().await {
+                Ok(event) => Some((Ok(event), receiver)),
+                Err(broadcast::error::RecvError::Closed) => None,
+                Err(err @ broadcast::error::RecvError::Lagged(_)) => {
+                    Some((Err(DataFusionError::External(Box::new(err))), receiver))
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Drops events already seen and events older than the latest one seen for
+/// the same file, which at-least-once, unordered delivery produces.
+///
+/// Events are ordered per file by the modification time of created files and
+/// the time of removals; a file is the same while its version, its entity tag
+/// or its size and modification time, is.
+#[derive(Debug, Default)]
+pub struct FileEventDeduplicator {
+    latest: HashMap<Path, Latest>,
+}
+
+#[derive(Debug)]
+struct Latest {
+    /// The version of the created file; `None` once removed.
+    version: Option<String>,
+    at: SystemTime,
+}
+
+impl FileEventDeduplicator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `event` is new, recording it if so.
+    pub fn accept(&mut self, event: &FileEvent) -> bool {
+        let (version, at) = match event {
+            FileEvent::Created(object) => (
+                Some(object_version(object)),
+                SystemTime::from(object.last_modified),
+            ),
+            FileEvent::Removed { at, .. } => (None, *at),
+        };
+        if let Some(latest) = self.latest.get(event.location())
+            && (latest.at > at || (latest.at == at && latest.version == version))
+        {
+            return false;
+        }
+        self.latest
+            .insert(event.location().clone(), Latest { version, at });
+        true
+    }
+}
+
+/// `events` without the repeated and stale ones.
+pub fn deduplicated(
+    events: BoxStream<'static, Result<FileEvent>>,
+) -> BoxStream<'static, Result<FileEvent>> {
+    let mut deduplicator = FileEventDeduplicator::new();
+    events
+        .filter(move |event| {
+            future::ready(match event {
+                Ok(event) => deduplicator.accept(event),
+                Err(_) => true,
+            })
+        })
+        .boxed()
+}
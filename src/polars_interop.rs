@@ -0,0 +1,55 @@
+use std::io::Cursor;
+use std::sync::Arc;
+
+use arrow::ipc::writer::StreamWriter;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::prelude::{DataFrame, SessionContext};
+use futures::TryStreamExt;
+use polars::prelude::{IntoLazy, IpcStreamReader, LazyFrame, SerReader};
+
+use crate::provider::{EvolutionOptions, SchemaEvolutionTableProvider};
+
+/// Read the files under `table_path` into one schema, as
+/// [`SchemaEvolutionTableProvider`] does, and collect them into a polars
+/// [`LazyFrame`].
+///
+/// The whole table is read into memory first, as with
+/// [`collect_into_lazy_frame`]: filter and project it with a `DataFrame` and
+/// pass that instead when the table is large.
+///
+/// ```ignore
+/// let events = collect_lazy_frame("s3://bucket/events/", Arc::new(ParquetFormat::default()), EvolutionOptions::new())
+///     .await?
+///     .filter(col("level").eq(lit("error")))
+///     .collect()?;
+/// ```
+pub async fn collect_lazy_frame(
+    table_path: &str,
+    format: Arc<dyn FileFormat>,
+    options: EvolutionOptions,
+) -> Result<LazyFrame> {
+    let ctx = SessionContext::new();
+    let provider =
+        SchemaEvolutionTableProvider::try_new(&ctx.state(), table_path, format, options).await?;
+    collect_into_lazy_frame(ctx.read_table(Arc::new(provider))?).await
+}
+
+/// Run `df` to completion and hand its batches to polars through an Arrow
+/// IPC stream.
+///
+/// Every batch is buffered before polars reads any: the result is held in
+/// memory twice at its peak, as IPC bytes and as polars columns, since polars
+/// builds on its own Arrow implementation. Only the polars side is lazy.
+pub async fn collect_into_lazy_frame(df: DataFrame) -> Result<LazyFrame> {
+    let mut stream = df.execute_stream().await?;
+    let mut writer = StreamWriter::try_new(Vec::new(), &stream.schema())?;
+    while let Some(batch) = stream.try_next().await? {
+        writer.write(&batch)?;
+    }
+    let bytes = writer.into_inner()?;
+    let frame = IpcStreamReader::new(Cursor::new(bytes))
+        .finish()
+        .map_err(|err| DataFusionError::External(Box::new(err)))?;
+    Ok(frame.lazy())
+}
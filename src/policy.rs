@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use arrow::datatypes::{DataType, Fields, Schema};
+use datafusion::common::ScalarValue;
+
+use crate::canonical::{CanonicalizeOptions, canonicalize_type};
+use crate::nested::can_cast_nested;
+use crate::normalize::StringNormalization;
+use crate::temporal::{NaiveTimestamps, is_temporal_widening};
+
+/// How far a column's file type may differ from the table type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CoercionMode {
+    /// Only the same logical type is accepted; physical encodings may differ
+    /// (`Utf8` vs `Utf8View`, dictionaries, ...).
+    Strict,
+    /// Additionally accept conversions that never lose information, e.g.
+    /// `Int32 -> Int64` or `Decimal(10, 2) -> Decimal(20, 4)`.
+    Widening,
+    /// Accept any conversion Arrow can cast, going through a string when there is
+    /// no direct cast. Values that cannot be converted become null.
+    #[default]
+    Lenient,
+}
+
+impl fmt::Display for CoercionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Strict => "strict",
+            Self::Widening => "widening",
+            Self::Lenient => "lenient",
+        })
+    }
+}
+
+/// What it takes to turn a file value into a table value, as decided by a
+/// [`CoercionPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coercion {
+    /// Same type; nothing to do.
+    Identity,
+    /// Same logical type in another encoding, or a lossless widening.
+    Widen,
+    /// A cast that may fail on individual values, which then become null.
+    Lenient,
+    /// No direct cast exists: format the value as a string and parse that.
+    ViaString,
+}
+
+/// What a scan does with a null read into a column the table schema declares
+/// non-nullable, e.g. from a file written before the column became required,
+/// or from a lenient cast that failed.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum NullViolation {
+    /// Fail the scan, naming the column, the file and the row, counted in the
+    /// rows read from the file's scanned range.
+    #[default]
+    Error,
+    /// Replace the null with a value, cast to the column's type.
+    Fill(ScalarValue),
+    /// Skip the rows with a null in the column.
+    DropRow,
+}
+
+/// What a lenient cast makes of a file value it cannot convert, e.g. `'A100'`
+/// read into an integer column. Engines differ here, so the choice is explicit
+/// and applies the same to every format.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum UncoercibleValue {
+    /// Null, like Arrow's safe casts: the row matches `IS NULL`, no comparison
+    /// is true for it, and inner joins on the column drop it.
+    #[default]
+    Null,
+    /// A value standing for every unconvertible one, cast to the column's
+    /// type, e.g. `-1`: the row matches filters and join keys equal to it, and
+    /// stays apart from values that were null in the file.
+    Sentinel(ScalarValue),
+}
+
+/// Chooses a [`CoercionMode`] per column.
+///
+/// ```ignore
+/// let policy = CoercionPolicy::widening().with_column("code", CoercionMode::Lenient);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CoercionPolicy {
+    mode: CoercionMode,
+    columns: HashMap<String, CoercionMode>,
+    normalizations: HashMap<String, StringNormalization>,
+    null_violation: NullViolation,
+    null_violations: HashMap<String, NullViolation>,
+    uncoercible: UncoercibleValue,
+    uncoercibles: HashMap<String, UncoercibleValue>,
+    naive_timestamps: NaiveTimestamps,
+}
+
+impl CoercionPolicy {
+    pub fn new(mode: CoercionMode) -> Self {
+        Self {
+            mode,
+            columns: HashMap::new(),
+            normalizations: HashMap::new(),
+            null_violation: NullViolation::default(),
+            null_violations: HashMap::new(),
+            uncoercible: UncoercibleValue::default(),
+            uncoercibles: HashMap::new(),
+            naive_timestamps: NaiveTimestamps::default(),
+        }
+    }
+
+    pub fn strict() -> Self {
+        Self::new(CoercionMode::Strict)
+    }
+
+    pub fn widening() -> Self {
+        Self::new(CoercionMode::Widening)
+    }
+
+    pub fn lenient() -> Self {
+        Self::new(CoercionMode::Lenient)
+    }
+
+    /// Use `mode` for the column `name` instead of the policy's default.
+    pub fn with_column(mut self, name: impl Into<String>, mode: CoercionMode) -> Self {
+        self.columns.insert(name.into(), mode);
+        self
+    }
+
+    /// Normalize the strings of the column `name` when reading it from any
+    /// file, e.g. to trim and case-fold codes some writers padded.
+    pub fn with_normalization(
+        mut self,
+        name: impl Into<String>,
+        normalization: StringNormalization,
+    ) -> Self {
+        self.normalizations.insert(name.into(), normalization);
+        self
+    }
+
+    /// The normalization of the string column `name`, if any.
+    pub fn normalization_for(&self, name: &str) -> Option<&StringNormalization> {
+        self.normalizations
+            .get(name)
+            .filter(|normalization| !normalization.is_identity())
+    }
+
+    /// What to do with nulls in non-nullable columns, unless set per column.
+    pub fn with_null_violation(mut self, violation: NullViolation) -> Self {
+        self.null_violation = violation;
+        self
+    }
+
+    /// What to do with nulls in the non-nullable column `name`.
+    pub fn with_column_null_violation(
+        mut self,
+        name: impl Into<String>,
+        violation: NullViolation,
+    ) -> Self {
+        self.null_violations.insert(name.into(), violation);
+        self
+    }
+
+    /// What to do with nulls in the non-nullable column `name`.
+    pub fn null_violation_for(&self, name: &str) -> &NullViolation {
+        self.null_violations
+            .get(name)
+            .unwrap_or(&self.null_violation)
+    }
+
+    /// What lenient casts make of values they cannot convert, unless set per
+    /// column.
+    pub fn with_uncoercible(mut self, value: UncoercibleValue) -> Self {
+        self.uncoercible = value;
+        self
+    }
+
+    /// What lenient casts make of the values of column `name` they cannot
+    /// convert.
+    pub fn with_column_uncoercible(
+        mut self,
+        name: impl Into<String>,
+        value: UncoercibleValue,
+    ) -> Self {
+        self.uncoercibles.insert(name.into(), value);
+        self
+    }
+
+    /// What lenient casts make of the values of column `name` they cannot
+    /// convert.
+    pub fn uncoercible_for(&self, name: &str) -> &UncoercibleValue {
+        self.uncoercibles.get(name).unwrap_or(&self.uncoercible)
+    }
+
+    /// How timestamps stored without a timezone are read into columns with
+    /// one; as UTC instants by default.
+    pub fn with_naive_timestamps(mut self, naive: NaiveTimestamps) -> Self {
+        self.naive_timestamps = naive;
+        self
+    }
+
+    pub fn naive_timestamps(&self) -> NaiveTimestamps {
+        self.naive_timestamps
+    }
+
+    /// The mode that applies to the column `name`.
+    pub fn mode_for(&self, name: &str) -> CoercionMode {
+        self.columns.get(name).copied().unwrap_or(self.mode)
+    }
+
+    /// Decide how the column `name` is coerced from `file_type` to `table_type`.
+    pub fn resolve(
+        &self,
+        name: &str,
+        file_type: &DataType,
+        table_type: &DataType,
+    ) -> Result<Coercion, CoercionError> {
+        if file_type == table_type {
+            return Ok(Coercion::Identity);
+        }
+
+        let mode = self.mode_for(name);
+        let coercion = if is_same_logical_type(file_type, table_type) {
+            Some(Coercion::Widen)
+        } else {
+            match mode {
+                CoercionMode::Strict => None,
+                CoercionMode::Widening => {
+                    is_lossless_widening(file_type, table_type).then_some(Coercion::Widen)
+                }
+                CoercionMode::Lenient => {
+                    if is_lossless_widening(file_type, table_type) {
+                        Some(Coercion::Widen)
+                    } else if can_cast(file_type, table_type) {
+                        Some(Coercion::Lenient)
+                    } else if can_cast(file_type, &DataType::Utf8)
+                        && can_cast(&DataType::Utf8, table_type)
+                    {
+                        Some(Coercion::ViaString)
+                    } else {
+                        None
+                    }
+                }
+            }
+        };
+
+        coercion.ok_or_else(|| CoercionError {
+            file: None,
+            column: name.to_string(),
+            file_type: file_type.clone(),
+            table_type: table_type.clone(),
+            mode,
+        })
+    }
+
+    /// Check every table column present in `file_schema`, returning all conflicts.
+    pub fn check(&self, file_schema: &Schema, table_schema: &Schema) -> Vec<CoercionError> {
+        table_schema
+            .fields()
+            .iter()
+            .filter_map(|table_field| {
+                let file_field = file_schema.field_with_name(table_field.name()).ok()?;
+                self.resolve(
+                    table_field.name(),
+                    file_field.data_type(),
+                    table_field.data_type(),
+                )
+                .err()
+            })
+            .collect()
+    }
+}
+
+/// A column whose file type cannot be coerced to the table type under the
+/// configured policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoercionError {
+    /// The offending file, when known: scans through
+    /// [`EvolvingFormat`](crate::format::EvolvingFormat) and checks that walk the files
+    /// themselves fill it in, while [`CoercionPolicy::check`] and
+    /// [`CoercionPolicy::resolve`] leave it empty.
+    pub file: Option<String>,
+    pub column: String,
+    pub file_type: DataType,
+    pub table_type: DataType,
+    pub mode: CoercionMode,
+}
+
+impl CoercionError {
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+}
+
+impl fmt::Display for CoercionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot coerce column '{}' from {} to {} under the {} policy",
+            self.column, self.file_type, self.table_type, self.mode
+        )?;
+        if let Some(file) = &self.file {
+            write!(f, " (file: {file})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CoercionError {}
+
+/// Whether `from` and `to` only differ in physical encoding.
+pub fn is_same_logical_type(from: &DataType, to: &DataType) -> bool {
+    let options = CanonicalizeOptions::default();
+    canonicalize_type(from, &options) == canonicalize_type(to, &options)
+}
+
+/// Whether every value of type `from` can be represented exactly as `to`.
+pub fn is_lossless_widening(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+
+    if is_same_logical_type(from, to) {
+        return true;
+    }
+
+    match (from, to) {
+        (Null, _) => true,
+        (Dictionary(_, value), _) => is_lossless_widening(value, to),
+        (_, Dictionary(_, value)) => is_lossless_widening(from, value),
+
+        (Int8, Int16 | Int32 | Int64)
+        | (Int16, Int32 | Int64)
+        | (Int32, Int64)
+        | (UInt8, UInt16 | UInt32 | UInt64 | Int16 | Int32 | Int64)
+        | (UInt16, UInt32 | UInt64 | Int32 | Int64)
+        | (UInt32, UInt64 | Int64) => true,
+
+        (Int8 | Int16 | UInt8 | UInt16, Float32) => true,
+        (Int8 | Int16 | Int32 | UInt8 | UInt16 | UInt32, Float64) => true,
+        (Float16, Float32 | Float64) | (Float32, Float64) => true,
+
+        (
+            Decimal32(fp, fs) | Decimal64(fp, fs) | Decimal128(fp, fs) | Decimal256(fp, fs),
+            Decimal32(tp, ts) | Decimal64(tp, ts) | Decimal128(tp, ts) | Decimal256(tp, ts),
+        ) => ts >= fs && i16::from(*tp) - i16::from(*ts) >= i16::from(*fp) - i16::from(*fs),
+        (
+            _,
+            Decimal32(precision, scale)
+            | Decimal64(precision, scale)
+            | Decimal128(precision, scale)
+            | Decimal256(precision, scale),
+        ) => integer_digits(from)
+            .is_some_and(|digits| i16::from(*precision) - i16::from(*scale) >= digits),
+
+        (Date32, Date64) => true,
+        (Date32 | Date64 | Timestamp(..), Timestamp(..)) => is_temporal_widening(from, to),
+
+        (List(from), List(to) | LargeList(to))
+        | (LargeList(from), List(to) | LargeList(to))
+        | (ListView(from), List(to) | LargeList(to))
+        | (LargeListView(from), List(to) | LargeList(to)) => {
+            (to.is_nullable() || !from.is_nullable())
+                && is_lossless_widening(from.data_type(), to.data_type())
+        }
+        (Struct(from), Struct(to)) => is_struct_widening(from, to),
+        (Map(from, _), Map(to, _)) => match (from.data_type(), to.data_type()) {
+            // Entries are matched by position, their names vary between writers
+            (Struct(from), Struct(to)) => {
+                from.len() == to.len()
+                    && from.iter().zip(to.iter()).all(|(from, to)| {
+                        (to.is_nullable() || !from.is_nullable())
+                            && is_lossless_widening(from.data_type(), to.data_type())
+                    })
+            }
+            _ => false,
+        },
+
+        _ => false,
+    }
+}
+
+/// Whether casting `from` to `to` keeps the order of the values, so that the
+/// cast min/max of some values bound the cast values. Lossless widenings do;
+/// of the lossy casts, only those between numbers and points in time do, while
+/// e.g. integers compare differently as strings and `-1 < 0 < 1` all but `0`
+/// become `true`.
+pub(crate) fn is_order_preserving(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+
+    let is_number = |t: &DataType| t.is_numeric() || matches!(t, Timestamp(..) | Date32 | Date64);
+    match (from, to) {
+        _ if is_lossless_widening(from, to) => true,
+        (Dictionary(_, value), _) => is_order_preserving(value, to),
+        (_, Dictionary(_, value)) => is_order_preserving(from, value),
+        (Boolean, _) => is_number(to),
+        (Duration(_), Duration(_)) | (Time32(_) | Time64(_), Time32(_) | Time64(_)) => true,
+        _ => is_number(from) && is_number(to),
+    }
+}
+
+/// Every field of `from` is kept and widens, and every field only the target
+/// has is nullable so it can be filled with nulls. Dropping a field loses its
+/// values, which only [`CoercionMode::Lenient`] accepts.
+fn is_struct_widening(from: &Fields, to: &Fields) -> bool {
+    let kept = from
+        .iter()
+        .all(|from_field| to.iter().any(|f| f.name() == from_field.name()));
+    kept && to.iter().all(
+        |to_field| match from.iter().find(|f| f.name() == to_field.name()) {
+            Some(from_field) => {
+                (to_field.is_nullable() || !from_field.is_nullable())
+                    && is_lossless_widening(from_field.data_type(), to_field.data_type())
+            }
+            None => to_field.is_nullable(),
+        },
+    )
+}
+
+/// Decimal digits needed for the integer part of values of an integer type.
+pub(crate) fn integer_digits(data_type: &DataType) -> Option<i16> {
+    match data_type {
+        DataType::Int8 | DataType::UInt8 => Some(3),
+        DataType::Int16 | DataType::UInt16 => Some(5),
+        DataType::Int32 | DataType::UInt32 => Some(10),
+        DataType::Int64 => Some(19),
+        DataType::UInt64 => Some(20),
+        _ => None,
+    }
+}
+
+pub(crate) fn can_cast(from: &DataType, to: &DataType) -> bool {
+    can_cast_nested(from, to)
+}
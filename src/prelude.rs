@@ -0,0 +1,36 @@
+//! The stable API: registering evolved tables, the policies that reconcile
+//! their files, unifying schemas and reporting on drift.
+//!
+//! Everything re-exported here, and from the crate root, follows semver.
+//! Breaking changes to these items only come with a new major version, even
+//! when DataFusion, Arrow or Vortex are upgraded underneath. The physical
+//! expressions, file sources and analyzer rules the crate is built from live
+//! in modules hidden from the documentation, whose signatures follow the
+//! DataFusion and Vortex releases the crate depends on.
+//!
+//! ```ignore
+//! use schema_evolution::prelude::*;
+//!
+//! let provider = SchemaEvolutionTableProvider::try_new(
+//!     &ctx.state(),
+//!     "s3://bucket/events/",
+//!     Arc::new(ParquetFormat::default()),
+//!     EvolutionOptions::new().with_policy(CoercionPolicy::lenient()),
+//! )
+//! .await?;
+//! ```
+
+pub use crate::drift::DriftReport;
+pub use crate::mapping::FieldMapping;
+pub use crate::merge::{MergeReport, SchemaUnifier, UnifiedSchema};
+pub use crate::missing::{ColumnDefault, MissingColumnPolicy};
+pub use crate::policy::{
+    CoercionError, CoercionMode, CoercionPolicy, NullViolation, UncoercibleValue,
+};
+pub use crate::provider::{
+    EvolutionOptions, SchemaEvolutionTableProvider, SchemaResolution, SchemaSource,
+};
+pub use crate::registry::SchemaRegistry;
+pub use crate::rewrite::{RewriteOptions, RewriteSummary, rewrite_to_schema};
+pub use crate::single_file::adapt_file_to_schema;
+pub use tokio_util::sync::CancellationToken;
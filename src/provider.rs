@@ -0,0 +1,400 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::catalog::{ScanArgs, ScanResult, Session, TableProvider};
+use datafusion::common::{Constraints, Result, Statistics, config_err};
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
+use datafusion::logical_expr::dml::InsertOp;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown, TableType};
+use datafusion::physical_plan::ExecutionPlan;
+
+use crate::adapter::SchemaEvolutionAdapterFactory;
+use crate::discovery::SchemaDiscovery;
+use crate::encoding::StringEncoding;
+use crate::format::EvolvingFormat;
+use crate::limits::MemoryLimits;
+use crate::manifest::EvolutionManifest;
+use crate::mapping::FieldMapping;
+use crate::merge::{MergeReport, SchemaUnifier};
+use crate::missing::MissingColumnPolicy;
+use crate::policy::CoercionPolicy;
+use crate::registry::SchemaRegistry;
+use crate::row_id::{ROW_ID_COLUMN, RowIdGenerator, row_id_field};
+
+/// How a [`SchemaEvolutionTableProvider`] reconciles the files of its dataset.
+#[derive(Debug, Clone, Default)]
+pub struct EvolutionOptions {
+    pub policy: CoercionPolicy,
+    pub field_mapping: FieldMapping,
+    pub missing_columns: MissingColumnPolicy,
+    /// Hive-style partition columns, e.g. `year` for `year=2024/` directories.
+    pub table_partition_cols: Vec<(String, DataType)>,
+    /// Use this table schema instead of unifying the file schemas.
+    pub schema: Option<SchemaRef>,
+    /// Only read files with this extension; defaults to the format's.
+    pub file_extension: Option<String>,
+    /// Collect file statistics when the table is registered.
+    pub collect_stat: bool,
+    /// How the file schemas are read when the table is registered.
+    pub discovery: SchemaDiscovery,
+    /// Declared schema versions; the table schema is the last one, and the
+    /// file schemas are not unified.
+    pub manifest: Option<Arc<EvolutionManifest>>,
+    /// Where the latest schema of the table is registered; the file schemas
+    /// are not unified when it has one.
+    pub registry: Option<Arc<dyn SchemaRegistry>>,
+    /// Add a [`ROW_ID_COLUMN`] generated by this generator to the table schema.
+    pub row_ids: Option<Arc<dyn RowIdGenerator>>,
+    /// The type of string and binary columns stored in different encodings.
+    pub string_encoding: StringEncoding,
+    /// Where the table schema is taken from, in order of preference; empty
+    /// for [`SchemaSource::DEFAULT_CHAIN`].
+    pub schema_sources: Vec<SchemaSource>,
+    /// Bounds on the memory adapting the files may take.
+    pub memory_limits: MemoryLimits,
+}
+
+impl EvolutionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(mut self, policy: CoercionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_field_mapping(mut self, field_mapping: FieldMapping) -> Self {
+        self.field_mapping = field_mapping;
+        self
+    }
+
+    pub fn with_missing_columns(mut self, missing_columns: MissingColumnPolicy) -> Self {
+        self.missing_columns = missing_columns;
+        self
+    }
+
+    pub fn with_table_partition_cols(mut self, cols: Vec<(String, DataType)>) -> Self {
+        self.table_partition_cols = cols;
+        self
+    }
+
+    pub fn with_schema(mut self, schema: SchemaRef) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    pub fn with_file_extension(mut self, file_extension: impl Into<String>) -> Self {
+        self.file_extension = Some(file_extension.into());
+        self
+    }
+
+    pub fn with_collect_stat(mut self, collect_stat: bool) -> Self {
+        self.collect_stat = collect_stat;
+        self
+    }
+
+    pub fn with_discovery(mut self, discovery: SchemaDiscovery) -> Self {
+        self.discovery = discovery;
+        self
+    }
+
+    pub fn with_manifest(mut self, manifest: Arc<EvolutionManifest>) -> Self {
+        self.manifest = Some(manifest);
+        self
+    }
+
+    pub fn with_registry(mut self, registry: Arc<dyn SchemaRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    pub fn with_string_encoding(mut self, string_encoding: StringEncoding) -> Self {
+        self.string_encoding = string_encoding;
+        self
+    }
+
+    /// Try the `sources` of the table schema in this order, e.g. to refuse
+    /// inferring it: `vec![SchemaSource::Manifest]`.
+    pub fn with_schema_sources(mut self, sources: Vec<SchemaSource>) -> Self {
+        self.schema_sources = sources;
+        self
+    }
+
+    /// Add a row-id column, e.g. `Arc::new(FileOrdinalRowIds::new())`. Scans
+    /// then read each file whole and in order, without pushing filters into
+    /// it, so that the ids stay the same across reads.
+    pub fn with_row_ids(mut self, generator: Arc<dyn RowIdGenerator>) -> Self {
+        self.row_ids = Some(generator);
+        self
+    }
+
+    /// Evaluate the string-parse casts of files needing many of them in
+    /// smaller batches.
+    pub fn with_memory_limits(mut self, memory_limits: MemoryLimits) -> Self {
+        self.memory_limits = memory_limits;
+        self
+    }
+}
+
+/// A place the table schema can come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SchemaSource {
+    /// [`EvolutionOptions::schema`].
+    Explicit,
+    /// The latest schema of the table in [`EvolutionOptions::registry`].
+    Registry,
+    /// The last version of [`EvolutionOptions::manifest`].
+    Manifest,
+    /// Unifying the schemas read from the file footers.
+    Inference,
+}
+
+impl SchemaSource {
+    /// An explicit schema, else the registry, else the manifest, else
+    /// inference.
+    pub const DEFAULT_CHAIN: &[Self] = &[
+        Self::Explicit,
+        Self::Registry,
+        Self::Manifest,
+        Self::Inference,
+    ];
+}
+
+impl fmt::Display for SchemaSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Explicit => "explicit schema",
+            Self::Registry => "registry",
+            Self::Manifest => "manifest",
+            Self::Inference => "inference",
+        })
+    }
+}
+
+/// Which source of the chain the table schema was taken from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaResolution {
+    pub source: SchemaSource,
+    /// The sources tried first, which were not configured or, for a
+    /// registry, had no schema of the table.
+    pub skipped: Vec<SchemaSource>,
+}
+
+impl fmt::Display for SchemaResolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "table schema from the {}", self.source)?;
+        if !self.skipped.is_empty() {
+            let skipped: Vec<_> = self.skipped.iter().map(ToString::to_string).collect();
+            write!(f, " (skipped: {})", skipped.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`TableProvider`] over a dataset whose file schemas drifted.
+///
+/// It lists the files, unifies their schemas under the [`EvolutionOptions`], and
+/// scans them through a [`ListingTable`] with
+/// [`SchemaEvolutionAdapterFactory`] and [`EvolvingFormat`] wired in:
+///
+/// ```ignore
+/// let provider = SchemaEvolutionTableProvider::try_new(
+///     &ctx.state(),
+///     "s3://bucket/events/",
+///     Arc::new(ParquetFormat::default()),
+///     EvolutionOptions::default(),
+/// )
+/// .await?;
+/// ctx.register_table("events", Arc::new(provider))?;
+/// ```
+#[derive(Debug)]
+pub struct SchemaEvolutionTableProvider {
+    inner: ListingTable,
+    report: MergeReport,
+    resolution: SchemaResolution,
+}
+
+impl SchemaEvolutionTableProvider {
+    pub async fn try_new(
+        state: &dyn Session,
+        table_path: impl AsRef<str>,
+        format: Arc<dyn FileFormat>,
+        options: EvolutionOptions,
+    ) -> Result<Self> {
+        let table_url = ListingTableUrl::parse(table_path)?;
+        let field_mapping = options.field_mapping;
+
+        let mut adapter_factory = SchemaEvolutionAdapterFactory::new()
+            .with_policy(options.policy.clone())
+            .with_field_mapping(field_mapping.clone())
+            .with_missing_column_policy(options.missing_columns)
+            .with_memory_limits(options.memory_limits);
+        if let Some(manifest) = &options.manifest {
+            adapter_factory = adapter_factory.with_manifest(Arc::clone(manifest));
+        }
+        if let Some(generator) = &options.row_ids {
+            adapter_factory = adapter_factory.with_row_ids(Arc::clone(generator));
+        }
+        let format = EvolvingFormat::new(format).with_adapter_factory(adapter_factory.clone());
+        let mut listing_options = ListingOptions::new(Arc::new(format))
+            .with_table_partition_cols(options.table_partition_cols)
+            .with_collect_stat(options.collect_stat)
+            .with_target_partitions(state.config_options().execution.target_partitions);
+        if let Some(file_extension) = options.file_extension {
+            listing_options = listing_options.with_file_extension(file_extension);
+        }
+
+        let unifier = SchemaUnifier::new()
+            .with_policy(options.policy)
+            .with_field_mapping(field_mapping.clone())
+            .with_discovery(options.discovery)
+            .with_string_encoding(options.string_encoding);
+        let chain = match options.schema_sources.as_slice() {
+            [] => SchemaSource::DEFAULT_CHAIN,
+            sources => sources,
+        };
+        let mut skipped = Vec::new();
+        let mut resolved = None;
+        for source in chain {
+            resolved = match (source, &options.schema, &options.manifest) {
+                (SchemaSource::Explicit, Some(schema), _) => {
+                    Some((Arc::clone(schema), MergeReport::default()))
+                }
+                (SchemaSource::Registry, _, _) => match &options.registry {
+                    Some(registry) => registry
+                        .latest_schema(&table_url)
+                        .await?
+                        .map(|schema| (schema, MergeReport::default())),
+                    None => None,
+                },
+                (SchemaSource::Manifest, _, Some(manifest)) => {
+                    Some((manifest.schema(), MergeReport::default()))
+                }
+                (SchemaSource::Inference, _, _) => {
+                    let unified = unifier
+                        .unify_url(state, &table_url, &listing_options)
+                        .await?;
+                    Some((unified.schema, unified.report))
+                }
+                _ => None,
+            };
+            if resolved.is_some() {
+                break;
+            }
+            skipped.push(*source);
+        }
+        let Some((schema, mut report)) = resolved else {
+            let chain: Vec<_> = chain.iter().map(ToString::to_string).collect();
+            return config_err!(
+                "No source of the table schema is configured (tried: {})",
+                chain.join(", ")
+            );
+        };
+        let resolution = SchemaResolution {
+            source: chain[skipped.len()],
+            skipped,
+        };
+        log::info!("{}: {resolution}", table_url.as_str());
+        report.resolution = Some(resolution.clone());
+        field_mapping.validate(&schema)?;
+
+        let schema = match &options.row_ids {
+            Some(generator) if schema.field_with_name(ROW_ID_COLUMN).is_err() => {
+                let mut fields = schema.fields().to_vec();
+                fields.push(Arc::new(row_id_field(generator.as_ref())));
+                Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+            }
+            _ => schema,
+        };
+
+        let config = ListingTableConfig::new(table_url)
+            .with_listing_options(listing_options)
+            .with_schema(schema)
+            .with_expr_adapter_factory(Arc::new(adapter_factory));
+
+        Ok(Self {
+            inner: ListingTable::try_new(config)?,
+            report,
+            resolution,
+        })
+    }
+
+    /// Where the table schema was taken from and, when it was inferred, how
+    /// each file's schema deviated from it when the provider was created.
+    pub fn report(&self) -> &MergeReport {
+        &self.report
+    }
+
+    /// Where the table schema was taken from.
+    pub fn schema_resolution(&self) -> &SchemaResolution {
+        &self.resolution
+    }
+
+    pub fn listing_table(&self) -> &ListingTable {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl TableProvider for SchemaEvolutionTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    fn constraints(&self) -> Option<&Constraints> {
+        self.inner.constraints()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.inner.table_type()
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.inner.scan(state, projection, filters, limit).await
+    }
+
+    async fn scan_with_args<'a>(
+        &self,
+        state: &dyn Session,
+        args: ScanArgs<'a>,
+    ) -> Result<ScanResult> {
+        self.inner.scan_with_args(state, args).await
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        self.inner.supports_filters_pushdown(filters)
+    }
+
+    fn statistics(&self) -> Option<Statistics> {
+        self.inner.statistics()
+    }
+
+    async fn insert_into(
+        &self,
+        state: &dyn Session,
+        input: Arc<dyn ExecutionPlan>,
+        insert_op: InsertOp,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.inner.insert_into(state, input, insert_op).await
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use arrow::datatypes::{Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::common::{Result, ScalarValue, config_err};
+use datafusion::datasource::listing::ListingTableUrl;
+
+use crate::canonical::{CanonicalizeOptions, canonicalize_type};
+use crate::discovery::DiscoveredSchemas;
+use crate::fingerprint::SchemaFingerprint;
+use crate::manifest::{EvolutionManifest, MigrationStep, SchemaVersion, VersionRule};
+
+/// A service holding the authoritative schema of tables, e.g. a catalog,
+/// consulted as [`SchemaSource::Registry`](crate::provider::SchemaSource::Registry).
+#[async_trait]
+pub trait SchemaRegistry: fmt::Debug + Send + Sync {
+    /// The latest schema registered for the table at `table_url`, if any.
+    async fn latest_schema(&self, table_url: &ListingTableUrl) -> Result<Option<SchemaRef>>;
+}
+
+/// Freeze a one-time inference pass into an [`EvolutionManifest`], so that a
+/// directory whose files drifted can be governed by declared versions from
+/// then on.
+///
+/// Files are grouped by the [`SchemaFingerprint`] of their schema, and each
+/// group becomes a version listing its files by path. Versions are numbered
+/// from 1 in the order their oldest file was modified, files without a
+/// modification time first, and the newest is the table schema. The steps
+/// between versions are derived from the column names: a column only the
+/// newer version has is added with a null default, one it lacks is dropped,
+/// and one it stores with another type is cast. Renames cannot be told apart
+/// from a drop and an add; edit the manifest to declare them.
+///
+/// ```ignore
+/// let discovered = SchemaDiscovery::new()
+///     .discover(&ctx.state(), &table_url, &listing_options)
+///     .await?;
+/// let manifest = bootstrap_from_inference(&discovered)?;
+/// std::fs::write("manifest.json", manifest.to_json()?)?;
+/// ```
+pub fn bootstrap_from_inference(discovered: &DiscoveredSchemas) -> Result<EvolutionManifest> {
+    if let Some(file) = discovered.skipped.first() {
+        return config_err!(
+            "Schema discovery skipped {} files, e.g. {}; bootstrapping needs every file's schema",
+            discovered.skipped.len(),
+            file.path
+        );
+    }
+    if discovered.files.is_empty() {
+        return config_err!("No files to bootstrap the manifest from");
+    }
+
+    struct Group {
+        schema: SchemaRef,
+        oldest: Option<SystemTime>,
+        paths: Vec<String>,
+    }
+    let mut groups: Vec<Group> = Vec::new();
+    let mut positions = HashMap::new();
+    for (file, schema) in &discovered.files {
+        // Footer metadata is not part of the version, so it does not tell
+        // versions apart either
+        let schema = Schema::new(schema.fields().clone());
+        let position = *positions
+            .entry(SchemaFingerprint::of(&schema))
+            .or_insert_with(|| {
+                groups.push(Group {
+                    schema: Arc::new(schema),
+                    oldest: file.last_modified,
+                    paths: Vec::new(),
+                });
+                groups.len() - 1
+            });
+        let group = &mut groups[position];
+        group.oldest = group.oldest.min(file.last_modified);
+        group
+            .paths
+            .push(file.path.trim_start_matches('/').to_string());
+    }
+    groups.sort_by(|left, right| left.oldest.cmp(&right.oldest));
+
+    let mut versions: Vec<SchemaVersion> = Vec::with_capacity(groups.len());
+    for group in groups {
+        let migration = match versions.last() {
+            Some(previous) => migration(&previous.schema, &group.schema)?,
+            None => Vec::new(),
+        };
+        versions.push(SchemaVersion {
+            version: versions.len() as u64 + 1,
+            schema: group.schema,
+            migration,
+            files: VersionRule::Paths(group.paths.into_iter().collect()),
+        });
+    }
+    EvolutionManifest::try_new(versions)
+}
+
+/// The steps turning the columns of `previous` into those of `next`.
+fn migration(previous: &Schema, next: &Schema) -> Result<Vec<MigrationStep>> {
+    // Encodings the versions are grouped across need no cast
+    let options = CanonicalizeOptions::default();
+    let mut steps = Vec::new();
+    for field in previous.fields() {
+        match next.field_with_name(field.name()) {
+            Ok(next_field)
+                if canonicalize_type(next_field.data_type(), &options)
+                    != canonicalize_type(field.data_type(), &options) =>
+            {
+                steps.push(MigrationStep::Cast {
+                    column: field.name().clone(),
+                    data_type: next_field.data_type().clone(),
+                });
+            }
+            Ok(_) => {}
+            Err(_) => steps.push(MigrationStep::Drop {
+                column: field.name().clone(),
+            }),
+        }
+    }
+    for field in next.fields() {
+        if previous.field_with_name(field.name()).is_err() {
+            steps.push(MigrationStep::Add {
+                column: field.name().clone(),
+                data_type: field.data_type().clone(),
+                default: ScalarValue::try_from(field.data_type())?,
+            });
+        }
+    }
+    Ok(steps)
+}
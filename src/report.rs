@@ -0,0 +1,191 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use arrow::datatypes::Schema;
+use arrow::temporal_conversions::timestamp_s_to_datetime;
+
+use crate::discovery::DiscoveredSchemas;
+use crate::fingerprint::SchemaFingerprint;
+use crate::merge::{Deviation, MergeReport};
+
+/// How a dataset's schema evolved over time, window by window.
+#[derive(Debug, Clone)]
+pub struct SchemaTrend {
+    pub window: Duration,
+    /// The windows files were written in, oldest first; windows without files
+    /// are left out.
+    pub windows: Vec<TrendWindow>,
+    /// How many schema versions changed each column, most changed first.
+    pub churn: Vec<(String, usize)>,
+    /// Files left out because the store has no modification time for them.
+    pub undated: usize,
+}
+
+/// The files written in one window of a [`SchemaTrend`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrendWindow {
+    pub start: SystemTime,
+    pub files: usize,
+    /// Schemas first written in this window.
+    pub new_versions: usize,
+    /// Files that do not have the table schema and are adapted when read.
+    pub adapted_files: usize,
+    /// Columns cast when reading the files, summed over the files.
+    pub casts: usize,
+}
+
+/// Summarize the schema history of the files read by a [`SchemaDiscovery`]
+/// over windows of `window`, aligned to the Unix epoch: how many new schema
+/// versions appeared, which columns they changed most, and how much adapting
+/// the files written in each window cost, from the deviations in `report`.
+///
+/// [`SchemaDiscovery`]: crate::discovery::SchemaDiscovery
+///
+/// ```ignore
+/// let discovered = SchemaDiscovery::new().discover(&state, &table_url, &options).await?;
+/// let unified = SchemaUnifier::new().unify_url(&state, &table_url, &options).await?;
+/// let quarterly = trend(&discovered, &unified.report, Duration::from_secs(91 * 86_400));
+/// println!("{quarterly}");
+/// ```
+pub fn trend(
+    discovered: &DiscoveredSchemas,
+    report: &MergeReport,
+    window: Duration,
+) -> SchemaTrend {
+    let window_secs = window.as_secs().max(1);
+    let deviations: HashMap<&str, &[Deviation]> = report
+        .files
+        .iter()
+        .map(|file| (file.path.as_str(), file.deviations.as_slice()))
+        .collect();
+    let mut files: Vec<_> = discovered
+        .files
+        .iter()
+        .filter_map(|(file, schema)| Some((file.last_modified?, file, schema)))
+        .collect();
+    files.sort_by(|left, right| {
+        left.0
+            .cmp(&right.0)
+            .then_with(|| left.1.path.cmp(&right.1.path))
+    });
+    let undated = discovered.files.len() - files.len();
+
+    let mut windows: Vec<TrendWindow> = Vec::new();
+    let mut churn: HashMap<String, usize> = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut latest: Option<&Schema> = None;
+    for (modified, file, schema) in files {
+        let secs = modified
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let start = UNIX_EPOCH + Duration::from_secs(secs / window_secs * window_secs);
+        if windows.last().is_none_or(|last| last.start != start) {
+            windows.push(TrendWindow {
+                start,
+                files: 0,
+                new_versions: 0,
+                adapted_files: 0,
+                casts: 0,
+            });
+        }
+        let current = windows.last_mut().unwrap();
+        current.files += 1;
+        if seen.insert(SchemaFingerprint::of(schema)) {
+            current.new_versions += 1;
+            if let Some(previous) = latest {
+                for column in changed_columns(previous, schema) {
+                    *churn.entry(column).or_default() += 1;
+                }
+            }
+            latest = Some(schema.as_ref());
+        }
+        let file_deviations = deviations
+            .get(file.path.as_str())
+            .copied()
+            .unwrap_or_default();
+        if !file_deviations.is_empty() {
+            current.adapted_files += 1;
+        }
+        current.casts += file_deviations
+            .iter()
+            .filter(|deviation| matches!(deviation, Deviation::TypeMismatch { .. }))
+            .count();
+    }
+
+    let mut churn: Vec<_> = churn.into_iter().collect();
+    churn.sort_by(|left, right| right.1.cmp(&left.1).then_with(|| left.0.cmp(&right.0)));
+    SchemaTrend {
+        window,
+        windows,
+        churn,
+        undated,
+    }
+}
+
+/// The columns one schema has and the other lacks or stores with another
+/// type.
+fn changed_columns(previous: &Schema, next: &Schema) -> Vec<String> {
+    let mut changed: Vec<String> = previous
+        .fields()
+        .iter()
+        .filter(|field| {
+            !next
+                .field_with_name(field.name())
+                .is_ok_and(|next| next.data_type() == field.data_type())
+        })
+        .map(|field| field.name().clone())
+        .collect();
+    changed.extend(
+        next.fields()
+            .iter()
+            .filter(|field| previous.field_with_name(field.name()).is_err())
+            .map(|field| field.name().clone()),
+    );
+    changed
+}
+
+impl fmt::Display for SchemaTrend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.window.as_secs().max(1);
+        if secs % 86_400 == 0 {
+            write!(f, "{}-day windows", secs / 86_400)?;
+        } else {
+            write!(f, "{secs}s windows")?;
+        }
+        if self.undated > 0 {
+            write!(
+                f,
+                " ({} files without a modification time left out)",
+                self.undated
+            )?;
+        }
+        writeln!(f)?;
+
+        for window in &self.windows {
+            let secs = window
+                .start
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            match timestamp_s_to_datetime(secs as i64) {
+                Some(start) => write!(f, "  {}", start.date())?,
+                None => write!(f, "  {secs}")?,
+            }
+            writeln!(
+                f,
+                ": {} files, {} new schema versions, {} adapted, {} casts",
+                window.files, window.new_versions, window.adapted_files, window.casts
+            )?;
+        }
+
+        if !self.churn.is_empty() {
+            writeln!(f, "\nmost changed columns:")?;
+            for (column, changes) in self.churn.iter().take(10) {
+                writeln!(f, "  {column}: {changes}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use arrow::array::AsArray;
+use arrow::datatypes::{SchemaRef, UInt64Type};
+use datafusion::common::{DataFusionError, GetExt, Result};
+use datafusion::datasource::file_format::parquet::ParquetFormatFactory;
+use datafusion::datasource::file_format::{FileFormat, FileFormatFactory, format_as_file_type};
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
+use datafusion::datasource::provider_as_source;
+use datafusion::execution::{SessionState, SessionStateBuilder};
+use datafusion::logical_expr::LogicalPlanBuilder;
+use datafusion::prelude::{DataFrame, SessionContext};
+use futures::TryStreamExt;
+use tokio_util::sync::CancellationToken;
+
+use crate::adapter::{ColumnPlan, SchemaEvolutionAdapterFactory};
+use crate::cancel::{PartialWrite, cancellable};
+use crate::format::{EvolvingFormat, FileContext};
+
+/// How [`rewrite_to_schema`] reads the old files and writes the new ones.
+#[derive(Clone)]
+pub struct RewriteOptions {
+    /// The format of the files to rewrite.
+    pub format: Arc<dyn FileFormat>,
+    /// The format to write; Parquet by default.
+    pub output: Arc<dyn FileFormatFactory>,
+    pub adapter_factory: SchemaEvolutionAdapterFactory,
+    /// Hive-style partition columns to write the rows under, e.g. `day=1/`.
+    pub partition_by: Vec<String>,
+    /// Plan the rewrite and check every file, but write nothing.
+    pub dry_run: bool,
+    /// Called after each file is rewritten, or checked in a dry run.
+    pub progress: Option<Arc<dyn Fn(&RewriteProgress) + Send + Sync>>,
+    /// Stops the rewrite when cancelled.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl RewriteOptions {
+    pub fn new(format: Arc<dyn FileFormat>) -> Self {
+        Self {
+            format,
+            output: Arc::new(ParquetFormatFactory::new()),
+            adapter_factory: SchemaEvolutionAdapterFactory::default(),
+            partition_by: Vec::new(),
+            dry_run: false,
+            progress: None,
+            cancellation: None,
+        }
+    }
+
+    pub fn with_output(mut self, output: Arc<dyn FileFormatFactory>) -> Self {
+        self.output = output;
+        self
+    }
+
+    pub fn with_adapter_factory(mut self, adapter_factory: SchemaEvolutionAdapterFactory) -> Self {
+        self.adapter_factory = adapter_factory;
+        self
+    }
+
+    pub fn with_partition_by(mut self, partition_by: Vec<String>) -> Self {
+        self.partition_by = partition_by;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_progress(
+        mut self,
+        progress: impl Fn(&RewriteProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Stop the rewrite once `token` is cancelled, deleting what the file
+    /// being written had written so far.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
+
+impl fmt::Debug for RewriteOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RewriteOptions")
+            .field("format", &self.format)
+            .field("output", &self.output)
+            .field("adapter_factory", &self.adapter_factory)
+            .field("partition_by", &self.partition_by)
+            .field("dry_run", &self.dry_run)
+            .field("progress", &self.progress.is_some())
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
+
+/// Reported to [`RewriteOptions::progress`].
+#[derive(Debug)]
+pub struct RewriteProgress<'a> {
+    pub file: &'a RewrittenFile,
+    /// Files rewritten so far, including this one.
+    pub done: usize,
+    pub total: usize,
+}
+
+/// One file of a [`rewrite_to_schema`].
+#[derive(Debug, Clone)]
+pub struct RewrittenFile {
+    /// The path of the old file, relative to the object store root.
+    pub source: String,
+    /// Where its rows are written: a file, or the directory partitions are
+    /// written under.
+    pub destination: String,
+    pub file_schema: SchemaRef,
+    /// Rows written; `None` in a dry run.
+    pub rows: Option<u64>,
+}
+
+/// The outcome of a [`rewrite_to_schema`].
+#[derive(Debug, Clone, Default)]
+pub struct RewriteSummary {
+    pub files: Vec<RewrittenFile>,
+}
+
+impl RewriteSummary {
+    pub fn rows(&self) -> u64 {
+        self.files.iter().filter_map(|file| file.rows).sum()
+    }
+}
+
+/// Stream every file under `src_url` through the schema evolution adapter and
+/// write it under `dst_url` in `target_schema`, keeping its relative path, so
+/// that the old files can be replaced by files that need no adaptation.
+///
+/// Every file is checked before anything is written: a column the policy
+/// cannot coerce fails the rewrite with the file named. Each file is read as
+/// one partition and written in the order it was read, so sorted files stay
+/// sorted. The old files are left in place.
+///
+/// Cancelling [`RewriteOptions::cancellation`], or dropping the returned
+/// future, stops the rewrite and deletes the partial output of the file being
+/// written; the files already rewritten are kept. A file that fails to be
+/// written is deleted the same way.
+///
+/// ```ignore
+/// let summary = rewrite_to_schema(
+///     &ctx,
+///     "s3://bucket/events/",
+///     "s3://bucket/events_v2/",
+///     unified.schema,
+///     RewriteOptions::new(Arc::new(ParquetFormat::default()))
+///         .with_progress(|p| println!("{}/{} {}", p.done, p.total, p.file.source)),
+/// )
+/// .await?;
+/// ```
+pub async fn rewrite_to_schema(
+    ctx: &SessionContext,
+    src_url: impl AsRef<str>,
+    dst_url: impl AsRef<str>,
+    target_schema: SchemaRef,
+    options: RewriteOptions,
+) -> Result<RewriteSummary> {
+    let src_url = ListingTableUrl::parse(src_url)?;
+    let dst_url = ListingTableUrl::parse(dst_url)?;
+
+    // One partition per file, so that the rows keep their order
+    let mut config = ctx.copied_config().with_target_partitions(1);
+    config.options_mut().execution.repartition_file_scans = false;
+    let state = SessionStateBuilder::new_from_existing(ctx.state())
+        .with_config(config)
+        .build();
+
+    let cancellation = options.cancellation.as_ref();
+    let store = state.runtime_env().object_store(&src_url)?;
+    let extension = options.format.get_ext();
+    let mut objects: Vec<_> = cancellable(cancellation, "The rewrite", async {
+        Ok(src_url
+            .list_all_files(&state, store.as_ref(), &extension)
+            .await?
+            .try_collect()
+            .await?)
+    })
+    .await?;
+    objects.sort_by(|left, right| left.location.cmp(&right.location));
+
+    let mut summary = RewriteSummary::default();
+    for object in &objects {
+        let file = FileContext::from(object);
+        let file_schema = cancellable(
+            cancellation,
+            "The rewrite",
+            options
+                .format
+                .infer_schema(&state, &store, std::slice::from_ref(object)),
+        )
+        .await?;
+        let adapter = options.adapter_factory.adapter(
+            Arc::clone(&target_schema),
+            Arc::clone(&file_schema),
+            Some(file.clone()),
+        );
+        let conflict = target_schema.fields().iter().find_map(|field| {
+            match adapter.column_plan(field.name()) {
+                Some(ColumnPlan::Incompatible(err)) => Some(err.clone().with_file(&file.path)),
+                _ => None,
+            }
+        });
+        if let Some(err) = conflict {
+            return Err(DataFusionError::External(Box::new(err)));
+        }
+
+        let relative = object
+            .location
+            .as_ref()
+            .strip_prefix(src_url.prefix().as_ref())
+            .unwrap_or(object.location.as_ref())
+            .trim_start_matches('/');
+        let destination = if options.partition_by.is_empty() {
+            let stem = relative.strip_suffix(&extension).unwrap_or(relative);
+            format!(
+                "{}/{stem}.{}",
+                dst_url.as_str().trim_end_matches('/'),
+                options.output.get_ext().trim_start_matches('.')
+            )
+        } else {
+            dst_url.as_str().to_string()
+        };
+        summary.files.push(RewrittenFile {
+            source: file.path,
+            destination,
+            file_schema,
+            rows: None,
+        });
+    }
+
+    let dst_store = state.runtime_env().object_store(&dst_url)?;
+    let total = summary.files.len();
+    for (done, (object, file)) in objects.iter().zip(&mut summary.files).enumerate() {
+        if !options.dry_run {
+            let url = ListingTableUrl::parse(format!(
+                "{}{}",
+                src_url.object_store().as_str(),
+                object.location
+            ))?;
+            let location = ListingTableUrl::parse(&file.destination)?.prefix().clone();
+            let write = if options.partition_by.is_empty() {
+                PartialWrite::file(Arc::clone(&dst_store), location)
+            } else {
+                PartialWrite::directory(Arc::clone(&dst_store), location).await?
+            };
+            let rows = cancellable(
+                cancellation,
+                "The rewrite",
+                rewrite_file(&state, url, file, &target_schema, &options),
+            )
+            .await;
+            match rows {
+                Ok(rows) => {
+                    write.finish();
+                    file.rows = Some(rows);
+                }
+                Err(err) => {
+                    if let Err(cleanup) = write.clean_up().await {
+                        log::warn!(
+                            "Could not clean up the partial rewrite of {}: {cleanup}",
+                            file.source
+                        );
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        if let Some(progress) = &options.progress {
+            progress(&RewriteProgress {
+                file,
+                done: done + 1,
+                total,
+            });
+        }
+    }
+    Ok(summary)
+}
+
+/// Copy the file at `url` to `file.destination` in `target_schema`, returning
+/// the rows written.
+async fn rewrite_file(
+    state: &SessionState,
+    url: ListingTableUrl,
+    file: &RewrittenFile,
+    target_schema: &SchemaRef,
+    options: &RewriteOptions,
+) -> Result<u64> {
+    let format = EvolvingFormat::new(Arc::clone(&options.format))
+        .with_adapter_factory(options.adapter_factory.clone());
+    let config = ListingTableConfig::new(url)
+        .with_listing_options(ListingOptions::new(Arc::new(format)))
+        .with_schema(Arc::clone(target_schema))
+        .with_expr_adapter_factory(Arc::new(options.adapter_factory.clone()));
+    let table = ListingTable::try_new(config)?;
+
+    let scan =
+        LogicalPlanBuilder::scan("rewrite", provider_as_source(Arc::new(table)), None)?.build()?;
+    let plan = LogicalPlanBuilder::copy_to(
+        scan,
+        file.destination.clone(),
+        format_as_file_type(Arc::clone(&options.output)),
+        HashMap::new(),
+        options.partition_by.clone(),
+    )?
+    .build()?;
+    let batches = DataFrame::new(state.clone(), plan).collect().await?;
+    Ok(batches
+        .iter()
+        .filter(|batch| batch.num_rows() > 0)
+        .map(|batch| batch.column(0).as_primitive::<UInt64Type>().value(0))
+        .sum())
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, FixedSizeBinaryBuilder};
+use arrow::datatypes::{DataType, Field};
+use datafusion::common::Result;
+use datafusion::object_store::ObjectMeta;
+
+use crate::discovery::object_version;
+use crate::fingerprint::Fnv1a;
+
+/// The name of the synthetic row-id column.
+pub const ROW_ID_COLUMN: &str = "_row_id";
+
+/// Generates the values of the [`ROW_ID_COLUMN`] for files that lack a primary
+/// key.
+///
+/// Ids must only depend on the file and the position of the row in it, so that
+/// re-reading an unmodified file yields the same ids.
+pub trait RowIdGenerator: fmt::Debug + Send + Sync {
+    /// The type of the ids.
+    fn data_type(&self) -> DataType;
+
+    /// The ids of the `len` rows of `file` starting at row `offset`.
+    fn generate(&self, file: &ObjectMeta, offset: u64, len: usize) -> Result<ArrayRef>;
+}
+
+/// Row ids of 16 bytes: a fingerprint of the file's path and version, then the
+/// row's ordinal in the file, both big-endian so that ids sort by file and
+/// then by row.
+///
+/// The version is the file's entity tag, or its size and modification time
+/// when the store has none, so rewriting a file changes the ids of its rows.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileOrdinalRowIds;
+
+impl FileOrdinalRowIds {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// The fingerprint of `file` the ids of its rows start with.
+    pub fn file_fingerprint(file: &ObjectMeta) -> u64 {
+        let mut hasher = Fnv1a::new();
+        hasher.write_str(file.location.as_ref());
+        hasher.write_str(&object_version(file));
+        hasher.finish()
+    }
+}
+
+impl RowIdGenerator for FileOrdinalRowIds {
+    fn data_type(&self) -> DataType {
+        DataType::FixedSizeBinary(16)
+    }
+
+    fn generate(&self, file: &ObjectMeta, offset: u64, len: usize) -> Result<ArrayRef> {
+        let fingerprint = Self::file_fingerprint(file).to_be_bytes();
+        let mut ids = FixedSizeBinaryBuilder::with_capacity(len, 16);
+        let mut id = [0; 16];
+        id[..8].copy_from_slice(&fingerprint);
+        for ordinal in offset..offset + len as u64 {
+            id[8..].copy_from_slice(&ordinal.to_be_bytes());
+            ids.append_value(id)?;
+        }
+        Ok(Arc::new(ids.finish()))
+    }
+}
+
+/// The table field of the row ids `generator` produces.
+pub fn row_id_field(generator: &dyn RowIdGenerator) -> Field {
+    Field::new(ROW_ID_COLUMN, generator.data_type(), false)
+}
@@ -0,0 +1,143 @@
+use std::any::Any;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use arrow::array::ArrayRef;
+use arrow::compute::kernels::zip::zip;
+use arrow::compute::{and, is_not_null, is_null};
+use arrow::datatypes::{DataType, FieldRef, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{Result, ScalarValue, exec_err};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+
+/// Replace the values a lenient cast turned into null, those non-null in
+/// `input` but null in `cast`, with `sentinel`.
+pub(crate) fn fill_uncoercible(
+    input: &ArrayRef,
+    cast: &ArrayRef,
+    sentinel: &ScalarValue,
+) -> Result<ArrayRef> {
+    if cast.null_count() == input.null_count() {
+        return Ok(Arc::clone(cast));
+    }
+    let failed = and(&is_not_null(input)?, &is_null(cast)?)?;
+    let sentinel = sentinel.cast_to(cast.data_type())?.to_scalar()?;
+    Ok(zip(&failed, &sentinel, cast)?)
+}
+
+/// Evaluates a lenient cast and replaces the values it could not convert with
+/// a sentinel, as [`UncoercibleValue::Sentinel`](crate::UncoercibleValue)
+/// says. Statistics pruning does not look through it, since the file's
+/// statistics do not account for the sentinel.
+#[derive(Debug, Clone, Eq)]
+pub struct SentinelExpr {
+    input: Arc<dyn PhysicalExpr>,
+    cast: Arc<dyn PhysicalExpr>,
+    sentinel: ScalarValue,
+}
+
+impl SentinelExpr {
+    /// `cast` is the lenient cast of `input`.
+    pub fn new(
+        input: Arc<dyn PhysicalExpr>,
+        cast: Arc<dyn PhysicalExpr>,
+        sentinel: ScalarValue,
+    ) -> Self {
+        Self {
+            input,
+            cast,
+            sentinel,
+        }
+    }
+
+    pub fn input(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.input
+    }
+
+    pub fn cast(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.cast
+    }
+
+    pub fn sentinel(&self) -> &ScalarValue {
+        &self.sentinel
+    }
+}
+
+impl PartialEq for SentinelExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.input.eq(&other.input) && self.cast.eq(&other.cast) && self.sentinel == other.sentinel
+    }
+}
+
+impl Hash for SentinelExpr {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.input.hash(state);
+        self.cast.hash(state);
+        self.sentinel.hash(state);
+    }
+}
+
+impl fmt::Display for SentinelExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SENTINEL({}, {})", self.cast, self.sentinel)
+    }
+}
+
+impl PhysicalExpr for SentinelExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        self.cast.data_type(input_schema)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        self.input.nullable(input_schema)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_rows = batch.num_rows();
+        let input = self.input.evaluate(batch)?;
+        let cast = self.cast.evaluate(batch)?;
+        if let (ColumnarValue::Scalar(input), ColumnarValue::Scalar(cast)) = (&input, &cast) {
+            return Ok(ColumnarValue::Scalar(
+                if !input.is_null() && cast.is_null() {
+                    self.sentinel.cast_to(&cast.data_type())?
+                } else {
+                    cast.clone()
+                },
+            ));
+        }
+        Ok(ColumnarValue::Array(fill_uncoercible(
+            &input.into_array(num_rows)?,
+            &cast.into_array(num_rows)?,
+            &self.sentinel,
+        )?))
+    }
+
+    fn return_field(&self, input_schema: &Schema) -> Result<FieldRef> {
+        let field = self.cast.return_field(input_schema)?;
+        let nullable = self.input.nullable(input_schema)?;
+        Ok(Arc::new(field.as_ref().clone().with_nullable(nullable)))
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn PhysicalExpr>> {
+        vec![&self.input, &self.cast]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let [input, cast] = <[_; 2]>::try_from(children)
+            .or_else(|_| exec_err!("SentinelExpr expects two children"))?;
+        Ok(Arc::new(Self::new(input, cast, self.sentinel.clone())))
+    }
+
+    fn fmt_sql(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
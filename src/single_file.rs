@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use datafusion::common::{Result, config_err};
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
+use datafusion::datasource::provider_as_source;
+use datafusion::execution::SessionStateBuilder;
+use datafusion::logical_expr::LogicalPlanBuilder;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::prelude::{DataFrame, SessionContext};
+use vortex::VortexSessionDefault;
+use vortex::session::VortexSession;
+use vortex_datafusion::VortexFormat;
+
+use crate::adapter::SchemaEvolutionAdapterFactory;
+use crate::format::EvolvingFormat;
+
+/// Read the file at `path`, a local path or object store URL, as batches in
+/// `target_schema`, with the default [`SchemaEvolutionAdapterFactory`].
+///
+/// The format is told from the file's extension, `.parquet` or `.vortex`.
+/// The batches come in the file's row order. Useful in pipelines of one's own
+/// that need no table:
+///
+/// ```ignore
+/// let mut stream = adapt_file_to_schema(&ctx, "s3://bucket/events/old.parquet", schema).await?;
+/// while let Some(batch) = stream.try_next().await? {
+///     sink.write(&batch)?;
+/// }
+/// ```
+pub async fn adapt_file_to_schema(
+    ctx: &SessionContext,
+    path: impl AsRef<str>,
+    target_schema: SchemaRef,
+) -> Result<SendableRecordBatchStream> {
+    adapt_file_with(
+        ctx,
+        path,
+        target_schema,
+        SchemaEvolutionAdapterFactory::new(),
+    )
+    .await
+}
+
+/// [`adapt_file_to_schema`] with the policy, renames and defaults of
+/// `adapter_factory`.
+pub async fn adapt_file_with(
+    ctx: &SessionContext,
+    path: impl AsRef<str>,
+    target_schema: SchemaRef,
+    adapter_factory: SchemaEvolutionAdapterFactory,
+) -> Result<SendableRecordBatchStream> {
+    let path = path.as_ref();
+    let format = format_of(path)?;
+    let url = ListingTableUrl::parse(path)?;
+
+    // One partition, so that the rows keep their order
+    let mut config = ctx.copied_config().with_target_partitions(1);
+    config.options_mut().execution.repartition_file_scans = false;
+    let state = SessionStateBuilder::new_from_existing(ctx.state())
+        .with_config(config)
+        .build();
+
+    let format = EvolvingFormat::new(format).with_adapter_factory(adapter_factory.clone());
+    let config = ListingTableConfig::new(url)
+        .with_listing_options(ListingOptions::new(Arc::new(format)))
+        .with_schema(target_schema)
+        .with_expr_adapter_factory(Arc::new(adapter_factory));
+    let table = ListingTable::try_new(config)?;
+    let plan =
+        LogicalPlanBuilder::scan("file", provider_as_source(Arc::new(table)), None)?.build()?;
+    DataFrame::new(state, plan).execute_stream().await
+}
+
+/// The format of a file, from its extension.
+fn format_of(path: &str) -> Result<Arc<dyn FileFormat>> {
+    let extension = path.rsplit_once('.').map(|(_, extension)| extension);
+    match extension {
+        Some("parquet") => Ok(Arc::new(ParquetFormat::default())),
+        Some("vortex") => Ok(Arc::new(VortexFormat::new(VortexSession::default()))),
+        _ => config_err!("Cannot tell the format of {path}: expected a .parquet or .vortex file"),
+    }
+}
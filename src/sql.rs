@@ -0,0 +1,230 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::ControlFlow;
+
+use datafusion::common::{Result, TableReference};
+use datafusion::prelude::{DataFrame, SessionContext};
+use datafusion::sql::parser::Statement as DFStatement;
+use datafusion::sql::sqlparser::ast::{
+    Expr, Ident, Query, SelectItem, SetExpr, Statement, TableFactor, Visit, VisitMut, Visitor,
+    VisitorMut, visit_expressions_mut,
+};
+
+use crate::mapping::FieldMapping;
+
+/// Historical column names that queries written before a rename still use,
+/// per table, and the names the tables expose now.
+///
+/// ```ignore
+/// let aliases = ColumnAliases::new().with_table("events", &mapping);
+/// // `product_code` was renamed to `code`
+/// let df = aliases
+///     .sql(&ctx, "SELECT product_code FROM events WHERE product_code = 'A1'")
+///     .await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ColumnAliases {
+    /// Per table name, the current name of each historical one.
+    tables: HashMap<String, HashMap<String, String>>,
+}
+
+impl ColumnAliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept the historical names of the renames in `mapping` for `table`,
+    /// named without catalog or schema. Chained renames resolve to the last
+    /// name; scopes are ignored, as a query reads every file.
+    pub fn with_table(mut self, table: impl Into<String>, mapping: &FieldMapping) -> Self {
+        let aliases = self.tables.entry(table.into()).or_default();
+        for rename in mapping.renames() {
+            let mut current = &rename.to;
+            let mut visited = HashSet::new();
+            while visited.insert(current.as_str()) {
+                match mapping.renames().iter().find(|next| next.from == *current) {
+                    Some(next) => current = &next.to,
+                    None => break,
+                }
+            }
+            aliases.insert(rename.from.clone(), current.clone());
+        }
+        self
+    }
+
+    /// Plan `sql` like [`SessionContext::sql`], after replacing the historical
+    /// column names of the tables it reads with their current names.
+    ///
+    /// Unknown columns fail SQL planning, before any analyzer rule could map
+    /// them, so the parsed statement is rewritten instead. A bare column is
+    /// only replaced when no table of the statement has a column of that name,
+    /// and a column selected under a historical name keeps it as its output
+    /// name, so dashboards see the columns they saw before.
+    pub async fn sql(&self, ctx: &SessionContext, sql: &str) -> Result<DataFrame> {
+        let state = ctx.state();
+        let dialect = state.config().options().sql_parser.dialect.clone();
+        let mut statement = state.sql_to_statement(sql, &dialect)?;
+        if let DFStatement::Statement(statement) = &mut statement {
+            self.rewrite(ctx, statement).await?;
+        }
+        let plan = state.statement_to_plan(statement).await?;
+        ctx.execute_logical_plan(plan).await
+    }
+
+    async fn rewrite(&self, ctx: &SessionContext, statement: &mut Statement) -> Result<()> {
+        let mut relations = Relations::default();
+        let _ = Visit::visit(&*statement, &mut relations);
+
+        // The table each qualifier refers to, and the columns of each table
+        let mut qualifiers = HashMap::new();
+        let mut columns: HashMap<String, HashSet<String>> = HashMap::new();
+        for (reference, alias) in relations.tables {
+            let table = reference.table().to_string();
+            if let Ok(provider) = ctx.table_provider(reference).await {
+                columns.entry(table.clone()).or_default().extend(
+                    provider
+                        .schema()
+                        .fields()
+                        .iter()
+                        .map(|field| field.name().clone()),
+                );
+            }
+            qualifiers.insert(alias.unwrap_or_else(|| table.clone()), table);
+        }
+
+        let mut renamer = Renamer::default();
+        let mut ambiguous = HashSet::new();
+        for (qualifier, table) in &qualifiers {
+            let Some(aliases) = self.tables.get(table) else {
+                continue;
+            };
+            let own = columns.get(table);
+            let aliases: HashMap<_, _> = aliases
+                .iter()
+                .filter(|(from, _)| own.is_none_or(|own| !own.contains(*from)))
+                .map(|(from, to)| (from.as_str(), to.as_str()))
+                .collect();
+            for (&from, &to) in &aliases {
+                if columns.values().any(|columns| columns.contains(from)) {
+                    continue;
+                }
+                if renamer
+                    .bare
+                    .insert(from, to)
+                    .is_some_and(|other| other != to)
+                {
+                    ambiguous.insert(from);
+                }
+            }
+            renamer.qualified.insert(qualifier.clone(), aliases);
+        }
+        renamer.bare.retain(|from, _| !ambiguous.contains(from));
+        if renamer.qualified.is_empty() {
+            return Ok(());
+        }
+
+        let _ = VisitMut::visit(statement, &mut renamer);
+        let _ = visit_expressions_mut(statement, |expr| {
+            if let Some((renamed, _)) = renamer.renamed(expr) {
+                *expr = renamed;
+            }
+            ControlFlow::<()>::Continue(())
+        });
+        Ok(())
+    }
+}
+
+/// The tables a statement reads, with their aliases.
+#[derive(Default)]
+struct Relations {
+    tables: Vec<(TableReference, Option<String>)>,
+}
+
+impl Visitor for Relations {
+    type Break = ();
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<()> {
+        if let TableFactor::Table { name, alias, .. } = table_factor {
+            self.tables.push((
+                TableReference::from(name.to_string()),
+                alias.as_ref().map(|alias| normalize(&alias.name)),
+            ));
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Replaces historical column names in expressions, and names selected
+/// columns after what the query called them.
+#[derive(Default)]
+struct Renamer<'a> {
+    /// Per table name or alias, the current name of each historical one.
+    qualified: HashMap<String, HashMap<&'a str, &'a str>>,
+    /// Historical names that may be used unqualified.
+    bare: HashMap<&'a str, &'a str>,
+}
+
+impl Renamer<'_> {
+    /// `expr` with its column renamed, and the name it was written with.
+    fn renamed(&self, expr: &Expr) -> Option<(Expr, Ident)> {
+        match expr {
+            Expr::Identifier(ident) => {
+                let current = self.bare.get(normalize(ident).as_str())?;
+                Some((Expr::Identifier(quoted(current)), ident.clone()))
+            }
+            Expr::CompoundIdentifier(parts) => {
+                let [.., qualifier, column] = parts.as_slice() else {
+                    return None;
+                };
+                let current = self
+                    .qualified
+                    .get(&normalize(qualifier))?
+                    .get(normalize(column).as_str())?;
+                let mut parts = parts.clone();
+                *parts.last_mut()? = quoted(current);
+                Some((Expr::CompoundIdentifier(parts), column.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    fn alias_projection(&self, body: &mut SetExpr) {
+        match body {
+            SetExpr::Select(select) => {
+                for item in &mut select.projection {
+                    if let SelectItem::UnnamedExpr(expr) = item
+                        && let Some((expr, alias)) = self.renamed(expr)
+                    {
+                        *item = SelectItem::ExprWithAlias { expr, alias };
+                    }
+                }
+            }
+            SetExpr::SetOperation { left, right, .. } => {
+                self.alias_projection(left);
+                self.alias_projection(right);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl VisitorMut for Renamer<'_> {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &mut Query) -> ControlFlow<()> {
+        self.alias_projection(&mut query.body);
+        ControlFlow::Continue(())
+    }
+}
+
+/// The column name an identifier refers to: unquoted names are lowercase.
+fn normalize(ident: &Ident) -> String {
+    match ident.quote_style {
+        Some(_) => ident.value.clone(),
+        None => ident.value.to_lowercase(),
+    }
+}
+
+/// An identifier referring to exactly `name`.
+fn quoted(name: &str) -> Ident {
+    Ident::with_quote('"', name)
+}
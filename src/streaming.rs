@@ -0,0 +1,254 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow::datatypes::SchemaRef;
+use datafusion::catalog::TableProvider;
+use datafusion::catalog::streaming::StreamingTable;
+use datafusion::common::Result;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
+use datafusion::execution::{
+    SendableRecordBatchStream, SessionState, SessionStateBuilder, TaskContext,
+};
+use datafusion::object_store::ObjectMeta;
+use datafusion::physical_plan::execute_stream;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::PartitionStream;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+
+use crate::adapter::SchemaEvolutionAdapterFactory;
+use crate::discovery::object_version;
+use crate::format::EvolvingFormat;
+use crate::notify::{FileEvent, FileNotifications, deduplicated};
+
+/// An unbounded source over a directory that keeps growing: files already
+/// there are read first, then the directory is listed again every poll
+/// interval and new files are read as they appear, each through the schema
+/// evolution adapter.
+///
+/// The table schema is fixed when the stream is created, e.g. by unifying the
+/// files present then; columns that later files add and the schema lacks are
+/// not read. Files are read in the order of modification time, each version
+/// once: a file overwritten in place is read again in whole, so its rows are
+/// streamed a second time.
+///
+/// With [`Self::with_notifications`], new files are read as their
+/// notifications arrive instead, and the directory is only listed once, when
+/// the stream starts.
+///
+/// ```ignore
+/// let table = EvolvingStream::new(table_url, Arc::new(ParquetFormat::default()), schema)
+///     .with_adapter_factory(adapter_factory)
+///     .with_poll_interval(Duration::from_secs(10))
+///     .into_table()?;
+/// ctx.register_table("events", Arc::new(table))?;
+/// let mut batches = ctx.sql("SELECT * FROM events WHERE level = 'error'").await?
+///     .execute_stream()
+///     .await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct EvolvingStream {
+    table_url: ListingTableUrl,
+    format: Arc<dyn FileFormat>,
+    schema: SchemaRef,
+    adapter_factory: SchemaEvolutionAdapterFactory,
+    poll_interval: Duration,
+    notifications: Option<Arc<dyn FileNotifications>>,
+}
+
+impl EvolvingStream {
+    pub fn new(table_url: ListingTableUrl, format: Arc<dyn FileFormat>, schema: SchemaRef) -> Self {
+        Self {
+            table_url,
+            format,
+            schema,
+            adapter_factory: SchemaEvolutionAdapterFactory::default(),
+            poll_interval: Duration::from_secs(30),
+            notifications: None,
+        }
+    }
+
+    pub fn with_adapter_factory(mut self, adapter_factory: SchemaEvolutionAdapterFactory) -> Self {
+        self.adapter_factory = adapter_factory;
+        self
+    }
+
+    /// How long to wait before listing the directory again after reading
+    /// every file found; 30 seconds by default.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Read the files `notifications` reports as created instead of polling;
+    /// repeated and out-of-order events are dropped. The stream ends when the
+    /// notifications do.
+    pub fn with_notifications(mut self, notifications: Arc<dyn FileNotifications>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// An unbounded table of this one stream.
+    pub fn into_table(self) -> Result<StreamingTable> {
+        Ok(
+            StreamingTable::try_new(Arc::clone(&self.schema), vec![Arc::new(self)])?
+                .with_infinite_table(true),
+        )
+    }
+
+    /// Read `object` in the table schema.
+    async fn read_file(
+        &self,
+        state: &SessionState,
+        object: &ObjectMeta,
+    ) -> Result<SendableRecordBatchStream> {
+        let url = ListingTableUrl::parse(format!(
+            "{}{}",
+            self.table_url.object_store().as_str(),
+            object.location
+        ))?;
+        let format = EvolvingFormat::new(Arc::clone(&self.format))
+            .with_adapter_factory(self.adapter_factory.clone());
+        let config = ListingTableConfig::new(url)
+            .with_listing_options(ListingOptions::new(Arc::new(format)))
+            .with_schema(Arc::clone(&self.schema))
+            .with_expr_adapter_factory(Arc::new(self.adapter_factory.clone()));
+        let plan = ListingTable::try_new(config)?
+            .scan(state, None, &[], None)
+            .await?;
+        execute_stream(plan, state.task_ctx())
+    }
+}
+
+impl PartitionStream for EvolvingStream {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let state = SessionStateBuilder::new()
+            .with_config(ctx.session_config().clone())
+            .with_runtime_env(ctx.runtime_env())
+            .with_default_features()
+            .build();
+        // Subscribed before listing, so that no file is missed in between
+        let events = self
+            .notifications
+            .as_ref()
+            .map(|notifications| deduplicated(notifications.subscribe()));
+        let poller = Poller {
+            stream: self.clone(),
+            state: state.clone(),
+            events,
+            seen: HashSet::new(),
+            pending: VecDeque::new(),
+            polled: false,
+        };
+        let files = futures::stream::unfold(poller, |mut poller| async move {
+            let next = poller.next().await.transpose()?;
+            Some((next, poller))
+        });
+        let stream = self.clone();
+        let batches = files
+            .and_then(move |object| {
+                let stream = stream.clone();
+                let state = state.clone();
+                async move { stream.read_file(&state, &object).await }
+            })
+            .try_flatten();
+        Box::pin(RecordBatchStreamAdapter::new(
+            Arc::clone(&self.schema),
+            batches.boxed(),
+        ))
+    }
+}
+
+/// Lists the directory of an [`EvolvingStream`], or follows its
+/// notifications, for files it has not read.
+struct Poller {
+    stream: EvolvingStream,
+    state: SessionState,
+    events: Option<BoxStream<'static, Result<FileEvent>>>,
+    /// The paths and versions of the files read.
+    seen: HashSet<(String, String)>,
+    pending: VecDeque<ObjectMeta>,
+    polled: bool,
+}
+
+impl Poller {
+    /// The next file to read, waiting for one to appear; `None` once the
+    /// notifications end.
+    async fn next(&mut self) -> Result<Option<ObjectMeta>> {
+        loop {
+            if let Some(object) = self.pending.pop_front() {
+                return Ok(Some(object));
+            }
+            if !self.polled {
+                self.polled = true;
+                self.poll().await?;
+                continue;
+            }
+            let Some(events) = &mut self.events else {
+                tokio::time::sleep(self.stream.poll_interval).await;
+                self.poll().await?;
+                continue;
+            };
+            match events.next().await.transpose()? {
+                Some(FileEvent::Created(object)) if self.is_new(&object) => {
+                    self.seen.insert(seen_key(&object));
+                    self.pending.push_back(object);
+                }
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Whether `object` is a file of the stream's table, in a version it has
+    /// not read.
+    fn is_new(&self, object: &ObjectMeta) -> bool {
+        let table_url = &self.stream.table_url;
+        object.size > 0
+            && table_url.contains(&object.location, false)
+            && object
+                .location
+                .as_ref()
+                .ends_with(&self.stream.format.get_ext())
+            && !self.seen.contains(&seen_key(object))
+    }
+
+    async fn poll(&mut self) -> Result<()> {
+        let store = self
+            .state
+            .runtime_env()
+            .object_store(&self.stream.table_url)?;
+        let mut objects: Vec<_> = self
+            .stream
+            .table_url
+            .list_all_files(&self.state, store.as_ref(), &self.stream.format.get_ext())
+            .await?
+            // Empty files cannot affect the stream but fail to parse
+            .try_filter(|object| futures::future::ready(object.size > 0))
+            .try_collect()
+            .await?;
+        objects.retain(|object| !self.seen.contains(&seen_key(object)));
+        objects.sort_by(|left, right| {
+            left.last_modified
+                .cmp(&right.last_modified)
+                .then_with(|| left.location.cmp(&right.location))
+        });
+        for object in objects {
+            self.seen.insert(seen_key(&object));
+            self.pending.push_back(object);
+        }
+        Ok(())
+    }
+}
+
+fn seen_key(object: &ObjectMeta) -> (String, String) {
+    (object.location.to_string(), object_version(object))
+}
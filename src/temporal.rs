@@ -0,0 +1,303 @@
+use std::any::Any;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, AsArray};
+use arrow::compute::{CastOptions, cast_with_options};
+use arrow::datatypes::{DataType, FieldRef, Int64Type, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{Result, ScalarValue, exec_err};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_expr::PhysicalExpr;
+
+/// How timestamps stored without a timezone are read into a column with one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NaiveTimestamps {
+    /// The values are instants in UTC; only the timezone is attached.
+    #[default]
+    Utc,
+    /// The values are wall-clock times in the column's timezone and are
+    /// converted to UTC, as Arrow's `cast` does.
+    Local,
+}
+
+/// Whether `data_type` is a date or a timestamp.
+pub(crate) fn is_temporal(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(..)
+    )
+}
+
+/// Whether every value of `from` is the same instant as some `to` value.
+///
+/// Timestamps widen to finer units, but not to nanoseconds, which only reach
+/// the years 1677 to 2262; timezones may be attached or changed, which keeps
+/// the instant, but not removed. Dates widen to timestamps at midnight UTC.
+pub(crate) fn is_temporal_widening(from: &DataType, to: &DataType) -> bool {
+    match (from, to) {
+        (DataType::Timestamp(from_unit, from_tz), DataType::Timestamp(to_unit, to_tz)) => {
+            (from_unit == to_unit || (is_finer(to_unit, from_unit) && !is_nanos(to_unit)))
+                && (to_tz.is_some() || from_tz.is_none())
+        }
+        (DataType::Date32 | DataType::Date64, DataType::Timestamp(unit, _)) => !is_nanos(unit),
+        _ => false,
+    }
+}
+
+/// A type both temporal types widen to: the finer unit, and the timezone of
+/// either side, the left one if both have one. Dates take the timestamp's
+/// type.
+pub(crate) fn temporal_supertype(left: &DataType, right: &DataType) -> Option<DataType> {
+    let candidate = match (left, right) {
+        (DataType::Timestamp(left_unit, left_tz), DataType::Timestamp(right_unit, right_tz)) => {
+            let unit = if is_finer(right_unit, left_unit) {
+                *right_unit
+            } else {
+                *left_unit
+            };
+            DataType::Timestamp(unit, left_tz.clone().or_else(|| right_tz.clone()))
+        }
+        (DataType::Date32 | DataType::Date64, DataType::Timestamp(..)) => right.clone(),
+        (DataType::Timestamp(..), DataType::Date32 | DataType::Date64) => left.clone(),
+        _ => return None,
+    };
+    (is_temporal_widening(left, &candidate) && is_temporal_widening(right, &candidate))
+        .then_some(candidate)
+}
+
+/// Whether [`cast_temporal`] gives other values than Arrow's `cast`, whose
+/// results statistics pruning assumes when it looks through a cast.
+pub(crate) fn differs_from_arrow(from: &DataType, to: &DataType, naive: NaiveTimestamps) -> bool {
+    let from_tz = match from {
+        DataType::Timestamp(_, tz) => tz.as_deref(),
+        _ => None,
+    };
+    match to {
+        DataType::Timestamp(to_unit, Some(to_tz)) => {
+            (from_tz.is_none() && naive == NaiveTimestamps::Utc && !is_utc(to_tz))
+                || matches!(from, DataType::Timestamp(from_unit, _) if is_finer(from_unit, to_unit))
+        }
+        DataType::Timestamp(to_unit, None) => {
+            matches!(from, DataType::Timestamp(from_unit, _) if is_finer(from_unit, to_unit))
+        }
+        _ => false,
+    }
+}
+
+/// Cast a date or timestamp array to a timestamp type.
+///
+/// Units are rescaled, rounding towards the past when coarsening, so that a
+/// value before 1970 stays in the interval it was in. Timezones follow
+/// `naive` when `array` has none, and otherwise only change the type: the
+/// instants are the same. Other casts are Arrow's.
+pub(crate) fn cast_temporal(
+    array: &ArrayRef,
+    to: &DataType,
+    naive: NaiveTimestamps,
+    options: &CastOptions,
+) -> Result<ArrayRef> {
+    let DataType::Timestamp(to_unit, to_tz) = to else {
+        return Ok(cast_with_options(array, to, options)?);
+    };
+    let (from_unit, from_tz) = match array.data_type() {
+        DataType::Timestamp(unit, tz) => (*unit, tz.clone()),
+        // Midnight UTC
+        _ => {
+            let array = cast_with_options(array, &DataType::Timestamp(*to_unit, None), options)?;
+            return cast_temporal(&array, to, naive, options);
+        }
+    };
+
+    let array = if is_finer(&from_unit, to_unit) {
+        let factor = unit_nanos(&from_unit) / unit_nanos(to_unit);
+        let values = cast_with_options(array, &DataType::Int64, options)?;
+        let values: ArrayRef = Arc::new(
+            values
+                .as_primitive::<Int64Type>()
+                .unary::<_, Int64Type>(|value| value.div_euclid(factor)),
+        );
+        cast_with_options(
+            &values,
+            &DataType::Timestamp(*to_unit, from_tz.clone()),
+            options,
+        )?
+    } else {
+        cast_with_options(
+            array,
+            &DataType::Timestamp(*to_unit, from_tz.clone()),
+            options,
+        )?
+    };
+
+    match (&from_tz, to_tz, naive) {
+        // Arrow reads naive values as wall-clock times in the new timezone;
+        // as wall-clock times in UTC they are the instants themselves
+        (None, Some(_), NaiveTimestamps::Utc) => {
+            let utc = cast_with_options(
+                &array,
+                &DataType::Timestamp(*to_unit, Some("UTC".into())),
+                options,
+            )?;
+            Ok(cast_with_options(&utc, to, options)?)
+        }
+        _ => Ok(cast_with_options(&array, to, options)?),
+    }
+}
+
+/// [`cast_temporal`] of one value, e.g. a min/max statistic.
+pub(crate) fn cast_temporal_scalar(
+    value: &ScalarValue,
+    to: &DataType,
+    naive: NaiveTimestamps,
+    options: &CastOptions,
+) -> Result<ScalarValue> {
+    let array = cast_temporal(&value.to_array()?, to, naive, options)?;
+    ScalarValue::try_from_array(&array, 0)
+}
+
+fn unit_nanos(unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1_000_000_000,
+        TimeUnit::Millisecond => 1_000_000,
+        TimeUnit::Microsecond => 1_000,
+        TimeUnit::Nanosecond => 1,
+    }
+}
+
+/// Whether `left` is a finer unit than `right`.
+fn is_finer(left: &TimeUnit, right: &TimeUnit) -> bool {
+    unit_nanos(left) < unit_nanos(right)
+}
+
+fn is_nanos(unit: &TimeUnit) -> bool {
+    *unit == TimeUnit::Nanosecond
+}
+
+fn is_utc(tz: &str) -> bool {
+    matches!(tz, "UTC" | "utc" | "Z" | "+00:00" | "Etc/UTC")
+}
+
+/// Casts its child with [`cast_temporal`]. Used by the adapter for the
+/// temporal casts whose values differ from Arrow's `cast`; statistics pruning
+/// does not look through it.
+#[derive(Debug, Clone, Eq)]
+pub struct TemporalCastExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    target_field: FieldRef,
+    naive: NaiveTimestamps,
+    cast_options: CastOptions<'static>,
+}
+
+impl TemporalCastExpr {
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        target_field: FieldRef,
+        naive: NaiveTimestamps,
+        cast_options: CastOptions<'static>,
+    ) -> Self {
+        Self {
+            expr,
+            target_field,
+            naive,
+            cast_options,
+        }
+    }
+
+    pub fn expr(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.expr
+    }
+
+    pub fn target_field(&self) -> &FieldRef {
+        &self.target_field
+    }
+}
+
+impl PartialEq for TemporalCastExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.expr.eq(&other.expr)
+            && self.target_field == other.target_field
+            && self.naive == other.naive
+            && self.cast_options == other.cast_options
+    }
+}
+
+impl Hash for TemporalCastExpr {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.expr.hash(state);
+        self.target_field.hash(state);
+        self.naive.hash(state);
+        self.cast_options.hash(state);
+    }
+}
+
+impl fmt::Display for TemporalCastExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CAST_TEMPORAL({} AS {})",
+            self.expr,
+            self.target_field.data_type()
+        )
+    }
+}
+
+impl PhysicalExpr for TemporalCastExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.target_field.data_type().clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(self.target_field.is_nullable())
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let target = self.target_field.data_type();
+        match self.expr.evaluate(batch)? {
+            ColumnarValue::Array(array) => Ok(ColumnarValue::Array(cast_temporal(
+                &array,
+                target,
+                self.naive,
+                &self.cast_options,
+            )?)),
+            ColumnarValue::Scalar(scalar) => Ok(ColumnarValue::Scalar(cast_temporal_scalar(
+                &scalar,
+                target,
+                self.naive,
+                &self.cast_options,
+            )?)),
+        }
+    }
+
+    fn return_field(&self, _input_schema: &Schema) -> Result<FieldRef> {
+        Ok(Arc::clone(&self.target_field))
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn PhysicalExpr>> {
+        vec![&self.expr]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let Some(child) = children.pop() else {
+            return exec_err!("TemporalCastExpr expects one child");
+        };
+        Ok(Arc::new(Self::new(
+            child,
+            Arc::clone(&self.target_field),
+            self.naive,
+            self.cast_options.clone(),
+        )))
+    }
+
+    fn fmt_sql(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use datafusion::catalog::Session;
+use datafusion::common::Result;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+
+use crate::adapter::{ColumnPlan, SchemaEvolutionAdapterFactory};
+use crate::discovery::SchemaDiscovery;
+use crate::policy::CoercionError;
+
+/// Checks that the files of a table can be read with a pinned schema, e.g.
+/// one given with [`EvolutionOptions::with_schema`](crate::EvolutionOptions::with_schema).
+///
+/// Verifying only the partitions a query touches reads only their footers:
+///
+/// ```ignore
+/// let verification = SchemaVerifier::new()
+///     .with_discovery(SchemaDiscovery::new().with_paths(glob::Pattern::new("dt=2024-06-*")?))
+///     .verify(&ctx.state(), &table_url, &listing_options, &pinned)
+///     .await?;
+/// if !verification.is_compatible() {
+///     return Err(...);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemaVerifier {
+    discovery: SchemaDiscovery,
+    adapter_factory: SchemaEvolutionAdapterFactory,
+}
+
+impl SchemaVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Which files to read, and how; every file by default.
+    pub fn with_discovery(mut self, discovery: SchemaDiscovery) -> Self {
+        self.discovery = discovery;
+        self
+    }
+
+    /// The adapter the files are read with.
+    pub fn with_adapter_factory(mut self, adapter_factory: SchemaEvolutionAdapterFactory) -> Self {
+        self.adapter_factory = adapter_factory;
+        self
+    }
+
+    /// Read the schemas of the files under `table_url` that the discovery
+    /// selects, and check that the adapter can read each one as `schema`.
+    pub async fn verify(
+        &self,
+        state: &dyn Session,
+        table_url: &ListingTableUrl,
+        options: &ListingOptions,
+        schema: &SchemaRef,
+    ) -> Result<Verification> {
+        let discovered = self.discovery.discover(state, table_url, options).await?;
+        let mut conflicts = Vec::new();
+        for (file, file_schema) in &discovered.files {
+            let adapter = self.adapter_factory.adapter(
+                Arc::clone(schema),
+                Arc::clone(file_schema),
+                Some(file.clone()),
+            );
+            conflicts.extend(schema.fields().iter().filter_map(|field| {
+                match adapter.column_plan(field.name()) {
+                    Some(ColumnPlan::Incompatible(err)) => Some(err.clone().with_file(&file.path)),
+                    _ => None,
+                }
+            }));
+        }
+        Ok(Verification {
+            files: discovered.files.len(),
+            unread: discovered.skipped.len(),
+            conflicts,
+        })
+    }
+}
+
+/// The outcome of [`SchemaVerifier::verify`].
+#[derive(Debug, Clone, Default)]
+pub struct Verification {
+    /// The files checked.
+    pub files: usize,
+    /// The files the discovery sampled out after others agreed on a schema.
+    pub unread: usize,
+    /// The columns of checked files that cannot be read as the schema.
+    pub conflicts: Vec<CoercionError>,
+}
+
+impl Verification {
+    pub fn is_compatible(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} files checked", self.files)?;
+        if self.unread > 0 {
+            write!(f, ", {} assumed to agree", self.unread)?;
+        }
+        writeln!(f, ", {} conflicts", self.conflicts.len())?;
+        for err in &self.conflicts {
+            writeln!(f, "  {err}")?;
+        }
+        Ok(())
+    }
+}
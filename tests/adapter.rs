@@ -0,0 +1,136 @@
+//! The adapter must plan each file once, and rewrite expressions over the
+//! table schema into expressions over the file's own columns.
+
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, AsArray, Int32Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema, SchemaRef};
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_expr::expressions::col;
+use datafusion::physical_expr_adapter::PhysicalExprAdapter;
+use schema_evolution::adapter::{ColumnPlan, FilePlan, SchemaEvolutionAdapterFactory};
+use schema_evolution::policy::{Coercion, CoercionPolicy};
+
+fn table_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("score", DataType::Int64, true),
+    ]))
+}
+
+/// `id` narrower than the table's, columns in another order, no `score`.
+fn file() -> RecordBatch {
+    RecordBatch::try_from_iter(vec![
+        (
+            "name",
+            Arc::new(StringArray::from(vec!["a", "b"])) as ArrayRef,
+        ),
+        ("id", Arc::new(Int32Array::from(vec![1, 2]))),
+    ])
+    .unwrap()
+}
+
+fn evaluate(expr: Arc<dyn PhysicalExpr>, batch: &RecordBatch) -> ArrayRef {
+    expr.evaluate(batch)
+        .unwrap()
+        .into_array(batch.num_rows())
+        .unwrap()
+}
+
+#[test]
+fn plans_files_by_the_work_they_need() {
+    let factory = SchemaEvolutionAdapterFactory::new();
+    let table = table_schema();
+    assert_eq!(
+        factory
+            .adapter(Arc::clone(&table), Arc::clone(&table), None)
+            .file_plan(),
+        FilePlan::Identity
+    );
+
+    let reordered = Arc::new(Schema::new(vec![
+        Field::new("score", DataType::Int64, true),
+        Field::new("extra", DataType::Boolean, true),
+        Field::new("id", DataType::Int64, true),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    assert_eq!(
+        factory
+            .adapter(Arc::clone(&table), reordered, None)
+            .file_plan(),
+        FilePlan::Reorder
+    );
+
+    let file = file();
+    let adapter = factory.adapter(Arc::clone(&table), file.schema(), None);
+    assert_eq!(adapter.file_plan(), FilePlan::Adapt);
+    assert_eq!(
+        adapter.column_plan("name"),
+        Some(&ColumnPlan::Passthrough {
+            index: 0,
+            name: "name".to_string()
+        })
+    );
+    assert!(matches!(
+        adapter.column_plan("id"),
+        Some(ColumnPlan::Cast {
+            index: 1,
+            coercion: Coercion::Widen,
+            ..
+        })
+    ));
+    assert!(matches!(
+        adapter.column_plan("score"),
+        Some(ColumnPlan::Missing { .. })
+    ));
+    assert_eq!(adapter.column_plan("nope"), None);
+}
+
+#[test]
+fn rewrites_reordered_widened_and_missing_columns() {
+    let table = table_schema();
+    let file = file();
+    let adapter =
+        SchemaEvolutionAdapterFactory::new().adapter(Arc::clone(&table), file.schema(), None);
+
+    let id = evaluate(adapter.rewrite(col("id", &table).unwrap()).unwrap(), &file);
+    assert_eq!(id.as_primitive::<Int64Type>().values(), &[1, 2]);
+    let name = evaluate(
+        adapter.rewrite(col("name", &table).unwrap()).unwrap(),
+        &file,
+    );
+    assert_eq!(name.as_string::<i32>().value(1), "b");
+    let score = evaluate(
+        adapter.rewrite(col("score", &table).unwrap()).unwrap(),
+        &file,
+    );
+    assert_eq!(score.data_type(), &DataType::Int64);
+    assert_eq!(score.null_count(), 2);
+}
+
+#[test]
+fn conflicts_fail_only_the_expressions_that_use_them() {
+    let table = table_schema();
+    let file = RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int32Array::from(vec![1])) as ArrayRef),
+        ("name", Arc::new(Int32Array::from(vec![2]))),
+    ])
+    .unwrap();
+    let adapter = SchemaEvolutionAdapterFactory::new()
+        .with_policy(CoercionPolicy::widening())
+        .adapter(Arc::clone(&table), file.schema(), None);
+
+    assert!(matches!(
+        adapter.column_plan("name"),
+        Some(ColumnPlan::Incompatible(_))
+    ));
+    let err = adapter.rewrite(col("name", &table).unwrap()).unwrap_err();
+    assert!(
+        err.to_string().contains("Cannot coerce column 'name'"),
+        "{err}"
+    );
+
+    let id = evaluate(adapter.rewrite(col("id", &table).unwrap()).unwrap(), &file);
+    assert_eq!(id.as_primitive::<Int64Type>().values(), &[1]);
+}
@@ -0,0 +1,184 @@
+//! Aggregates over widened and parsed columns must equal those over the files
+//! cast to the table schema up front.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Decimal128Array, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::util::display::array_value_to_string;
+use arrow::util::pretty::pretty_format_batches;
+use datafusion::datasource::MemTable;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use parquet::arrow::ArrowWriter;
+use schema_evolution::{EvolutionOptions, SchemaEvolutionTableProvider};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, batch: &RecordBatch) {
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// `qty` widens from Int32, with values whose sum overflows Int32; `price` is
+/// UTF8 in the old file, some of it not numeric; `rate` is rescaled from
+/// Decimal(10, 2) to Decimal(12, 4).
+fn files() -> Vec<RecordBatch> {
+    let old = RecordBatch::try_from_iter(vec![
+        (
+            "grp",
+            Arc::new(StringArray::from(vec!["a", "a", "b", "b"])) as ArrayRef,
+        ),
+        (
+            "qty",
+            Arc::new(Int32Array::from(vec![i32::MAX, i32::MAX, -7, i32::MIN])),
+        ),
+        (
+            "price",
+            Arc::new(StringArray::from(vec![
+                Some("10"),
+                Some("x"),
+                None,
+                Some("-3"),
+            ])),
+        ),
+        (
+            "rate",
+            Arc::new(
+                Decimal128Array::from(vec![125, 99_999_999, -1, 0])
+                    .with_precision_and_scale(10, 2)
+                    .unwrap(),
+            ),
+        ),
+    ])
+    .unwrap();
+    let new = RecordBatch::try_from_iter(vec![
+        (
+            "grp",
+            Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+        ),
+        (
+            "qty",
+            Arc::new(Int64Array::from(vec![i64::from(i32::MAX) * 4, 1, 0])),
+        ),
+        ("price", Arc::new(Int64Array::from(vec![5, 1_000, -3]))),
+        (
+            "rate",
+            Arc::new(
+                Decimal128Array::from(vec![12_345, -99_999_999_999, 1])
+                    .with_precision_and_scale(12, 4)
+                    .unwrap(),
+            ),
+        ),
+    ])
+    .unwrap();
+    vec![old, new]
+}
+
+fn table_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("grp", DataType::Utf8, true),
+        Field::new("qty", DataType::Int64, true),
+        Field::new("price", DataType::Int64, true),
+        Field::new("rate", DataType::Decimal128(12, 4), true),
+    ]))
+}
+
+async fn context() -> (TempDir, SessionContext) {
+    let dir = tempfile::tempdir().unwrap();
+    for (index, batch) in files().iter().enumerate() {
+        write_parquet(&dir.path().join(format!("{index}.parquet")), batch);
+    }
+    let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(2));
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        dir.path().to_str().unwrap(),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new().with_schema(table_schema()),
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+    (dir, ctx)
+}
+
+/// The files cast to the table schema with Arrow's safe casts, before any
+/// query sees them.
+fn reference() -> SessionContext {
+    let schema = table_schema();
+    let batches = files()
+        .iter()
+        .map(|batch| {
+            let columns = schema
+                .fields()
+                .iter()
+                .map(|field| {
+                    let column = batch.column_by_name(field.name()).unwrap();
+                    cast(column, field.data_type()).unwrap()
+                })
+                .collect();
+            RecordBatch::try_new(Arc::clone(&schema), columns).unwrap()
+        })
+        .collect();
+    let ctx = SessionContext::new();
+    let table = MemTable::try_new(schema, vec![batches]).unwrap();
+    ctx.register_table("t", Arc::new(table)).unwrap();
+    ctx
+}
+
+async fn query(ctx: &SessionContext, sql: &str) -> String {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    pretty_format_batches(&batches).unwrap().to_string()
+}
+
+/// The single value `sql` returns.
+async fn value(ctx: &SessionContext, sql: &str) -> String {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    array_value_to_string(batches[0].column(0), 0).unwrap()
+}
+
+const QUERIES: &[&str] = &[
+    "SELECT sum(qty), avg(qty), min(qty), max(qty), count(qty) FROM t",
+    "SELECT grp, sum(qty), avg(qty), min(qty), max(qty) FROM t GROUP BY grp ORDER BY grp",
+    "SELECT sum(price), avg(price), min(price), max(price), count(price) FROM t",
+    "SELECT grp, sum(price), min(price) FROM t GROUP BY grp ORDER BY grp",
+    "SELECT sum(rate), avg(rate), min(rate), max(rate) FROM t",
+    "SELECT grp, sum(rate), max(rate) FROM t GROUP BY grp ORDER BY grp",
+    "SELECT count(DISTINCT price), sum(DISTINCT qty) FROM t",
+    "SELECT sum(qty) FILTER (WHERE price IS NULL), max(rate) FILTER (WHERE qty < 0) FROM t",
+];
+
+#[tokio::test]
+async fn aggregates_match_precast_files() {
+    let (_dir, ctx) = context().await;
+    let reference = reference();
+    for sql in QUERIES {
+        assert_eq!(
+            query(&ctx, sql).await,
+            query(&reference, sql).await,
+            "{sql}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn widened_and_rescaled_sums_are_exact() {
+    let (_dir, ctx) = context().await;
+    // 6 * i32::MAX, summed as Int64 although the old file alone overflows Int32
+    assert_eq!(
+        value(&ctx, "SELECT sum(qty) FROM t WHERE grp = 'a'").await,
+        "12884901882"
+    );
+    assert_eq!(
+        value(&ctx, "SELECT sum(qty) FROM t WHERE grp = 'b'").await,
+        "-2147483654"
+    );
+    // Decimal(10, 2) values rescaled to Decimal(12, 4) keep every digit
+    assert_eq!(
+        value(&ctx, "SELECT sum(rate) FROM t").await,
+        "-8999997.5353"
+    );
+}
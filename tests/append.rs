@@ -0,0 +1,118 @@
+//! Writers introducing schemas must take the lock in turn, and only ever
+//! release their own.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use datafusion::error::DataFusionError;
+use datafusion::object_store::memory::InMemory;
+use datafusion::object_store::path::Path;
+use datafusion::object_store::{ObjectStore, PutPayload};
+use schema_evolution::append::{AppendConflict, AppendProtocol, SCHEMA_LOCK, fingerprinted_name};
+use schema_evolution::fingerprint::SchemaFingerprint;
+
+fn schema(columns: &[&str]) -> Schema {
+    Schema::new(
+        columns
+            .iter()
+            .map(|name| Field::new(*name, DataType::Int64, true))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn protocol(store: &Arc<dyn ObjectStore>, writer: &str) -> AppendProtocol {
+    AppendProtocol::new(Arc::clone(store), Path::from("events"), writer)
+}
+
+async fn lock_writer(store: &Arc<dyn ObjectStore>) -> Option<String> {
+    let lock = store
+        .get(&Path::from("events").child(SCHEMA_LOCK))
+        .await
+        .ok()?;
+    let lock: serde_json::Value = serde_json::from_slice(&lock.bytes().await.unwrap()).unwrap();
+    Some(lock["writer"].as_str().unwrap().to_string())
+}
+
+#[tokio::test]
+async fn different_schemas_conflict() {
+    let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    let first = protocol(&store, "a").begin(&schema(&["id"])).await.unwrap();
+    assert!(first.holds_lock());
+
+    let err = protocol(&store, "b")
+        .begin(&schema(&["id", "score"]))
+        .await
+        .unwrap_err();
+    let DataFusionError::External(conflict) = &err else {
+        panic!("{err}");
+    };
+    let conflict = conflict.downcast_ref::<AppendConflict>().unwrap();
+    assert_eq!(conflict.writer, "a");
+    assert_eq!(conflict.fingerprint, first.fingerprint().to_string());
+
+    first.commit().await.unwrap();
+    assert_eq!(lock_writer(&store).await, None);
+    let second = protocol(&store, "b")
+        .begin(&schema(&["id", "score"]))
+        .await
+        .unwrap();
+    assert!(second.holds_lock());
+}
+
+#[tokio::test]
+async fn expired_lock_is_taken_over_and_kept_by_its_new_holder() {
+    let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    let first = protocol(&store, "a")
+        .with_lease(Duration::ZERO)
+        .begin(&schema(&["id"]))
+        .await
+        .unwrap();
+    assert!(first.holds_lock());
+
+    let second = protocol(&store, "b")
+        .begin(&schema(&["id", "score"]))
+        .await
+        .unwrap();
+    assert!(second.holds_lock());
+    assert_eq!(lock_writer(&store).await.as_deref(), Some("b"));
+
+    // The expired holder must not release its successor's lock
+    first.commit().await.unwrap();
+    assert_eq!(lock_writer(&store).await.as_deref(), Some("b"));
+    second.commit().await.unwrap();
+    assert_eq!(lock_writer(&store).await, None);
+}
+
+#[tokio::test]
+async fn same_schema_from_two_writers_does_not_conflict() {
+    let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    let first = protocol(&store, "a").begin(&schema(&["id"])).await.unwrap();
+    let second = protocol(&store, "b").begin(&schema(&["id"])).await.unwrap();
+    assert!(first.holds_lock());
+    assert!(!second.holds_lock());
+    assert_eq!(second.fingerprint(), first.fingerprint());
+
+    // Committing without the lock leaves it to its holder
+    second.commit().await.unwrap();
+    assert_eq!(lock_writer(&store).await.as_deref(), Some("a"));
+    first.commit().await.unwrap();
+    assert_eq!(lock_writer(&store).await, None);
+}
+
+#[tokio::test]
+async fn known_schema_needs_no_lock() {
+    let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    let schema = schema(&["id"]);
+    let name = format!(
+        "events/{}",
+        fingerprinted_name("part-0001", SchemaFingerprint::of(&schema), "parquet")
+    );
+    store
+        .put(&Path::from(name), PutPayload::from_static(b"data"))
+        .await
+        .unwrap();
+    let session = protocol(&store, "a").begin(&schema).await.unwrap();
+    assert!(!session.holds_lock());
+    assert_eq!(lock_writer(&store).await, None);
+}
@@ -0,0 +1,132 @@
+//! Cancelled discoveries and rewrites must stop promptly and leave no partial
+//! output behind.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use datafusion::prelude::SessionContext;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::discovery::SchemaDiscovery;
+use schema_evolution::{CancellationToken, RewriteOptions, rewrite_to_schema};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, batch: &RecordBatch) {
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// Three files, the first storing `id` as Int32.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("src");
+    std::fs::create_dir_all(&src).unwrap();
+    write_parquet(
+        &src.join("a.parquet"),
+        &RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+        )])
+        .unwrap(),
+    );
+    for (name, id) in [("b.parquet", 3), ("c.parquet", 4)] {
+        write_parquet(
+            &src.join(name),
+            &RecordBatch::try_from_iter(vec![(
+                "id",
+                Arc::new(Int64Array::from(vec![id])) as ArrayRef,
+            )])
+            .unwrap(),
+        );
+    }
+    dir
+}
+
+fn target_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]))
+}
+
+fn url(path: &Path) -> String {
+    format!("{}/", path.to_str().unwrap())
+}
+
+/// The files under `dir`, relative to it.
+fn files(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<String> = entries
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    files
+}
+
+#[tokio::test]
+async fn cancelled_discovery_fails() {
+    let dir = dataset();
+    let ctx = SessionContext::new();
+    let token = CancellationToken::new();
+    token.cancel();
+    let err = SchemaDiscovery::new()
+        .with_cancellation(token)
+        .discover(
+            &ctx.state(),
+            &ListingTableUrl::parse(url(&dir.path().join("src"))).unwrap(),
+            &ListingOptions::new(Arc::new(ParquetFormat::default())),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("cancelled"), "{err}");
+}
+
+#[tokio::test]
+async fn cancelled_rewrite_keeps_finished_files_only() {
+    let dir = dataset();
+    let dst = dir.path().join("dst");
+    let ctx = SessionContext::new();
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    let err = rewrite_to_schema(
+        &ctx,
+        url(&dir.path().join("src")),
+        url(&dst),
+        target_schema(),
+        RewriteOptions::new(Arc::new(ParquetFormat::default()))
+            .with_cancellation(token)
+            .with_progress(move |progress| {
+                if progress.done == 1 {
+                    cancel.cancel();
+                }
+            }),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("cancelled"), "{err}");
+    assert_eq!(files(&dst), ["a.parquet"]);
+}
+
+#[tokio::test]
+async fn rewrite_cancelled_up_front_writes_nothing() {
+    let dir = dataset();
+    let dst = dir.path().join("dst");
+    let ctx = SessionContext::new();
+    let token = CancellationToken::new();
+    token.cancel();
+    let err = rewrite_to_schema(
+        &ctx,
+        url(&dir.path().join("src")),
+        url(&dst),
+        target_schema(),
+        RewriteOptions::new(Arc::new(ParquetFormat::default())).with_cancellation(token),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("cancelled"), "{err}");
+    assert!(files(&dst).is_empty());
+}
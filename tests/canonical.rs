@@ -0,0 +1,129 @@
+//! Canonical schemas must make physically different encodings of the same
+//! data equal, drop only volatile metadata, and be what the unifier and the
+//! adapter compare schemas by.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use schema_evolution::adapter::{FilePlan, SchemaEvolutionAdapterFactory};
+use schema_evolution::canonical::{
+    CanonicalizeOptions, canonicalize, canonicalize_type, canonicalize_with, equivalent,
+    sorted_metadata,
+};
+use schema_evolution::merge::SchemaUnifier;
+
+fn metadata(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// A schema as a Spark job writes it: large strings, a dictionary, a list
+/// with `element` items inside a struct, and writer metadata.
+fn written() -> Schema {
+    let tags = DataType::LargeList(Arc::new(Field::new("element", DataType::Utf8View, true)));
+    let address = DataType::Struct(Fields::from(vec![
+        Field::new("city", DataType::LargeUtf8, true),
+        Field::new("tags", tags, true),
+    ]));
+    Schema::new_with_metadata(
+        vec![
+            Field::new("id", DataType::Int64, false)
+                .with_metadata(metadata(&[("pandas", "{}"), ("comment", "primary key")])),
+            Field::new(
+                "region",
+                DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::LargeUtf8)),
+                true,
+            ),
+            Field::new("address", address, true),
+        ],
+        metadata(&[("org.apache.spark.version", "3.5.0"), ("owner", "ingest")]),
+    )
+}
+
+#[test]
+fn canonical_schemas_normalize_types_recursively() {
+    let tags = DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)));
+    let address = DataType::Struct(Fields::from(vec![
+        Field::new("city", DataType::Utf8, true),
+        Field::new("tags", tags, true),
+    ]));
+    let expected = Schema::new_with_metadata(
+        vec![
+            Field::new("id", DataType::Int64, false)
+                .with_metadata(metadata(&[("comment", "primary key")])),
+            Field::new("region", DataType::Utf8, true),
+            Field::new("address", address, true),
+        ],
+        metadata(&[("owner", "ingest")]),
+    );
+    assert_eq!(canonicalize(&written()), expected);
+    // Canonicalizing is idempotent
+    assert_eq!(canonicalize(&expected), expected);
+}
+
+#[test]
+fn metadata_only_keeps_the_types_as_written() {
+    let canonical = canonicalize_with(&written(), &CanonicalizeOptions::metadata_only());
+    for (canonical, written) in canonical.fields().iter().zip(written().fields()) {
+        assert_eq!(canonical.data_type(), written.data_type());
+    }
+    assert_eq!(
+        sorted_metadata(canonical.metadata())
+            .into_iter()
+            .collect::<Vec<_>>(),
+        [("owner", "ingest")]
+    );
+
+    // Extra volatile keys are dropped from fields too
+    let options = CanonicalizeOptions::metadata_only().with_volatile_metadata_key("comment");
+    let canonical = canonicalize_with(&written(), &options);
+    assert!(canonical.field(0).metadata().is_empty());
+}
+
+#[test]
+fn dictionaries_keep_their_keys_unless_unwrapped() {
+    let dictionary = DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::LargeUtf8));
+    let options = CanonicalizeOptions {
+        unwrap_dictionaries: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        canonicalize_type(&dictionary, &options),
+        DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8))
+    );
+    assert!(!equivalent(
+        &Schema::new(vec![Field::new("a", DataType::Int32, true)]),
+        &Schema::new(vec![Field::new("a", DataType::Int64, true)]),
+        &CanonicalizeOptions::default()
+    ));
+}
+
+#[test]
+fn unified_fields_drop_volatile_metadata() {
+    let file: SchemaRef = Arc::new(written());
+    let unified = SchemaUnifier::new().unify(vec![("a.parquet".to_string(), file)]);
+    assert_eq!(
+        unified.schema.field(0).metadata(),
+        &metadata(&[("comment", "primary key")])
+    );
+}
+
+#[test]
+fn identity_ignores_volatile_metadata() {
+    let table: SchemaRef = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+    let file = |entries: &[(&str, &str)]| -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, true).with_metadata(metadata(entries)),
+        ]))
+    };
+    let plan = |file_schema| {
+        SchemaEvolutionAdapterFactory::new()
+            .adapter(Arc::clone(&table), file_schema, None)
+            .file_plan()
+    };
+    assert_eq!(plan(file(&[("pandas", "{}")])), FilePlan::Identity);
+    assert_eq!(plan(file(&[("comment", "key")])), FilePlan::Reorder);
+}
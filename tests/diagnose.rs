@@ -0,0 +1,92 @@
+//! A failing query must be explained down to the file and column it fails on.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::prelude::SessionContext;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::EvolutionOptions;
+use schema_evolution::diagnose::{FailureExplanation, FailureStage};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// `score` is required by the table but missing from the old file.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a_old.parquet"),
+        vec![("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef)],
+    );
+    write_parquet(
+        &dir.path().join("b_new.parquet"),
+        vec![
+            ("id", Arc::new(Int64Array::from(vec![3])) as ArrayRef),
+            ("score", Arc::new(Int64Array::from(vec![10]))),
+        ],
+    );
+    dir
+}
+
+async fn explain(dir: &TempDir, sql: &str) -> FailureExplanation {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("score", DataType::Int64, false),
+    ]));
+    FailureExplanation::explain(
+        &SessionContext::new(),
+        &ListingTableUrl::parse(format!("{}/", dir.path().to_str().unwrap())).unwrap(),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new().with_schema(schema),
+        "t",
+        sql,
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn names_the_file_and_column() {
+    let dir = dataset();
+    let explanation = explain(&dir, "SELECT sum(score) FROM t").await;
+    let failure = explanation.failure.as_ref().unwrap();
+    assert_eq!(failure.stage, FailureStage::Execution);
+
+    assert_eq!(explanation.files.len(), 1, "{explanation}");
+    let file = &explanation.files[0];
+    assert!(file.path.ends_with("a_old.parquet"), "{explanation}");
+    let columns: Vec<_> = file
+        .columns
+        .iter()
+        .map(|column| (column.column.as_str(), column.step.as_str()))
+        .collect();
+    assert_eq!(columns, [("score", "filled in as missing from the file")]);
+    assert!(
+        explanation.to_string().contains("column 'score'"),
+        "{explanation}"
+    );
+}
+
+#[tokio::test]
+async fn reports_planning_failures_and_successes() {
+    let dir = dataset();
+    let explanation = explain(&dir, "SELECT nope FROM t").await;
+    assert_eq!(
+        explanation.failure.as_ref().unwrap().stage,
+        FailureStage::Planning
+    );
+    assert!(explanation.files.is_empty());
+
+    let explanation = explain(&dir, "SELECT id FROM t").await;
+    assert!(explanation.failure.is_none(), "{explanation}");
+}
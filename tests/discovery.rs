@@ -0,0 +1,149 @@
+//! Discovery must read the newest files until enough agree, serve unchanged
+//! files from its cache and only read the paths it is restricted to.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array, RecordBatch};
+use arrow::datatypes::DataType;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use datafusion::prelude::SessionContext;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::discovery::{DiscoveredSchemas, SchemaCache, SchemaDiscovery};
+use tempfile::TempDir;
+
+/// Write `id` as `column` into `path`, modified `age` seconds ago.
+fn write_parquet(path: &Path, column: ArrayRef, age: u64) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let batch = RecordBatch::try_from_iter(vec![("id", column)]).unwrap();
+    let file = std::fs::File::create(path).unwrap();
+    let mut writer = ArrowWriter::try_new(file.try_clone().unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+    file.set_modified(SystemTime::now() - Duration::from_secs(age))
+        .unwrap();
+}
+
+fn int32() -> ArrayRef {
+    Arc::new(Int32Array::from(vec![1, 2]))
+}
+
+fn int64() -> ArrayRef {
+    Arc::new(Int64Array::from(vec![3]))
+}
+
+async fn discover(discovery: &SchemaDiscovery, dir: &TempDir) -> DiscoveredSchemas {
+    discovery
+        .discover(
+            &SessionContext::new().state(),
+            &ListingTableUrl::parse(format!("{}/", dir.path().to_str().unwrap())).unwrap(),
+            &ListingOptions::new(Arc::new(ParquetFormat::default())),
+        )
+        .await
+        .unwrap()
+}
+
+fn names<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut names: Vec<_> = paths
+        .into_iter()
+        .map(|path| path.rsplit('/').next().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn agreement_reads_the_newest_files_only() {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(&dir.path().join("a.parquet"), int32(), 400);
+    write_parquet(&dir.path().join("b.parquet"), int64(), 300);
+    write_parquet(&dir.path().join("c.parquet"), int64(), 200);
+    write_parquet(&dir.path().join("d.parquet"), int64(), 100);
+
+    let discovered = discover(&SchemaDiscovery::new().with_agreement(2), &dir).await;
+    let read = names(discovered.files.iter().map(|(file, _)| file.path.as_str()));
+    assert_eq!(read, ["c.parquet", "d.parquet"]);
+    let skipped = names(discovered.skipped.iter().map(|file| file.path.as_str()));
+    assert_eq!(skipped, ["a.parquet", "b.parquet"]);
+
+    // A newer change of schema breaks the run, so the files keep being read
+    write_parquet(&dir.path().join("e.parquet"), int32(), 0);
+    let discovered = discover(&SchemaDiscovery::new().with_agreement(2), &dir).await;
+    let read = names(discovered.files.iter().map(|(file, _)| file.path.as_str()));
+    assert_eq!(read, ["c.parquet", "d.parquet", "e.parquet"]);
+
+    let discovered = discover(&SchemaDiscovery::new(), &dir).await;
+    assert_eq!(discovered.files.len(), 5);
+    assert!(discovered.skipped.is_empty());
+}
+
+#[tokio::test]
+async fn cache_reads_changed_files_again() {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(&dir.path().join("a.parquet"), int32(), 200);
+    write_parquet(&dir.path().join("b.parquet"), int64(), 100);
+    let cache = SchemaCache::new();
+    let discovery = SchemaDiscovery::new().with_cache(cache.clone());
+
+    discover(&discovery, &dir).await;
+    assert_eq!(cache.len(), 2);
+    discover(&discovery, &dir).await;
+    assert_eq!(cache.len(), 2);
+
+    // The rewritten file has another version, so it is read again
+    write_parquet(&dir.path().join("a.parquet"), int64(), 0);
+    let discovered = discover(&discovery, &dir).await;
+    assert_eq!(cache.len(), 3);
+    for (file, schema) in &discovered.files {
+        assert_eq!(
+            schema.field(0).data_type(),
+            &DataType::Int64,
+            "{}",
+            file.path
+        );
+    }
+
+    // Both versions of the rewritten file are forgotten
+    let (rewritten, _) = discovered
+        .files
+        .iter()
+        .find(|(file, _)| file.path.ends_with("a.parquet"))
+        .unwrap();
+    cache.remove(&rewritten.path);
+    assert_eq!(cache.len(), 1);
+}
+
+#[tokio::test]
+async fn paths_restrict_the_files_read() {
+    let dir = tempfile::tempdir().unwrap();
+    for (partition, column) in [
+        ("dt=2024-05-31", int32()),
+        ("dt=2024-06-01", int64()),
+        ("dt=2024-06-02", int64()),
+    ] {
+        write_parquet(
+            &dir.path().join(partition).join("part-0.parquet"),
+            column,
+            0,
+        );
+    }
+
+    let discovery = SchemaDiscovery::new().with_paths(glob::Pattern::new("dt=2024-06-*").unwrap());
+    let discovered = discover(&discovery, &dir).await;
+    let mut partitions: Vec<_> = discovered
+        .files
+        .iter()
+        .map(|(file, schema)| {
+            assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+            file.path.rsplit('/').nth(1).unwrap()
+        })
+        .collect();
+    partitions.sort();
+    assert_eq!(partitions, ["dt=2024-06-01", "dt=2024-06-02"]);
+    assert!(discovered.skipped.is_empty());
+
+    let discovery = discovery.with_paths(glob::Pattern::new("dt=2024-05-31/*").unwrap());
+    assert_eq!(discover(&discovery, &dir).await.files.len(), 3);
+}
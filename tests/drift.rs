@@ -0,0 +1,196 @@
+//! Drift reports must render every drifted column and the strictest mode that
+//! reconciles the files. Statistics drift must alert only when a column's null
+//! rate or range moves past its threshold between generations, and never on
+//! columns without the statistics to compare.
+
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::common::stats::Precision;
+use datafusion::common::{ColumnStatistics, ScalarValue, Statistics};
+use schema_evolution::drift::{
+    DriftReport, StatisticsAlert, StatisticsDrift, StatisticsThresholds,
+};
+use schema_evolution::format::FileContext;
+
+type FileStatistics = (FileContext, SchemaRef, Statistics);
+
+/// Generation 0 stores `v` alone, generation 1 adds `note`.
+fn schema(generation: usize) -> SchemaRef {
+    let mut fields = vec![Field::new("v", DataType::Int64, true)];
+    if generation == 1 {
+        fields.push(Field::new("note", DataType::Utf8, true));
+    }
+    Arc::new(Schema::new(fields))
+}
+
+/// A file of `generation` with `rows` rows, `nulls` of them null in `v`, and
+/// the min and max of `v` if known.
+fn file(
+    path: &str,
+    generation: usize,
+    rows: usize,
+    nulls: usize,
+    range: Option<(i64, i64)>,
+) -> FileStatistics {
+    let schema = schema(generation);
+    let mut column = ColumnStatistics::new_unknown().with_null_count(Precision::Exact(nulls));
+    if let Some((min, max)) = range {
+        column = column
+            .with_min_value(Precision::Exact(ScalarValue::Int64(Some(min))))
+            .with_max_value(Precision::Exact(ScalarValue::Int64(Some(max))));
+    }
+    let mut statistics = Statistics::new_unknown(&schema).with_num_rows(Precision::Exact(rows));
+    statistics.column_statistics[0] = column;
+    let modified = UNIX_EPOCH + Duration::from_secs(generation as u64 * 100);
+    (
+        FileContext::new(path).with_last_modified(modified),
+        schema,
+        statistics,
+    )
+}
+
+fn drift(files: Vec<FileStatistics>, thresholds: StatisticsThresholds) -> StatisticsDrift {
+    StatisticsDrift::new(files, thresholds)
+}
+
+#[test]
+fn null_rate_alerts_past_the_threshold() {
+    let files = || {
+        vec![
+            file("t/a.parquet", 0, 10, 0, Some((0, 10))),
+            file("t/b.parquet", 1, 10, 2, Some((0, 10))),
+        ]
+    };
+    let statistics = drift(files(), StatisticsThresholds::new());
+    assert_eq!(
+        statistics.alerts,
+        [StatisticsAlert::NullRate {
+            column: "v".to_string(),
+            generation: 1,
+            before: 0.0,
+            after: 0.2,
+        }]
+    );
+
+    let statistics = drift(files(), StatisticsThresholds::new().with_null_rate(0.25));
+    assert!(statistics.alerts.is_empty(), "{:?}", statistics.alerts);
+
+    // A change of exactly the threshold is allowed
+    let files = vec![
+        file("t/a.parquet", 0, 10, 0, Some((0, 10))),
+        file("t/b.parquet", 1, 10, 1, Some((0, 10))),
+    ];
+    assert!(drift(files, StatisticsThresholds::new()).alerts.is_empty());
+}
+
+#[test]
+fn range_shift_alerts_past_the_threshold() {
+    let files = |after| {
+        vec![
+            file("t/a.parquet", 0, 10, 0, Some((0, 100))),
+            file("t/b.parquet", 1, 10, 0, Some(after)),
+        ]
+    };
+    // Both bounds moved by half the earlier range
+    assert!(
+        drift(files((50, 150)), StatisticsThresholds::new())
+            .alerts
+            .is_empty()
+    );
+
+    // The max moved by one and a half ranges, e.g. a unit change
+    let statistics = drift(files((0, 250)), StatisticsThresholds::new());
+    assert_eq!(
+        statistics.alerts,
+        [StatisticsAlert::RangeShift {
+            column: "v".to_string(),
+            generation: 1,
+            before: (0.0, 100.0),
+            after: (0.0, 250.0),
+        }]
+    );
+    let statistics = drift(
+        files((0, 250)),
+        StatisticsThresholds::new().with_range_shift(2.0),
+    );
+    assert!(statistics.alerts.is_empty(), "{:?}", statistics.alerts);
+}
+
+#[test]
+fn statistics_are_combined_over_the_files_of_a_generation() {
+    let files = vec![
+        file("t/b.parquet", 0, 10, 0, Some((20, 30))),
+        file("t/a.parquet", 0, 10, 1, Some((0, 10))),
+        // Empty files have no range to widen it with
+        file("t/c.parquet", 0, 0, 0, None),
+    ];
+    let statistics = drift(files, StatisticsThresholds::new());
+    let [generation] = statistics.generations.as_slice() else {
+        panic!("{:?}", statistics.generations);
+    };
+    assert_eq!(
+        generation.files,
+        ["t/a.parquet", "t/b.parquet", "t/c.parquet"]
+    );
+    assert_eq!(generation.columns[0].null_rate, Some(0.05));
+    assert_eq!(generation.columns[0].range, Some((0.0, 30.0)));
+}
+
+#[test]
+fn columns_without_min_max_are_not_compared() {
+    // The later generation's file has no min/max for `v`, and `note` is not
+    // numeric
+    let files = vec![
+        file("t/a.parquet", 0, 10, 0, Some((0, 100))),
+        file("t/b.parquet", 1, 10, 0, None),
+        file("t/c.parquet", 1, 10, 0, Some((1_000, 2_000))),
+    ];
+    let statistics = drift(files, StatisticsThresholds::new());
+    assert!(statistics.alerts.is_empty(), "{:?}", statistics.alerts);
+    let columns = &statistics.generations[1].columns;
+    assert_eq!(columns[0].range, None);
+    assert_eq!(columns[1].name, "note");
+    assert_eq!(columns[1].range, None);
+    // Without null counts there is no null rate either
+    assert_eq!(columns[1].null_rate, None);
+}
+
+#[test]
+fn reports_render_drifted_columns_and_the_suggested_schema() {
+    let files: Vec<(String, SchemaRef)> = vec![
+        (
+            "b.parquet".to_string(),
+            Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)])),
+        ),
+        (
+            "a.parquet".to_string(),
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int32, false),
+                Field::new("name", DataType::Utf8, true),
+            ])),
+        ),
+    ];
+    let expected = "\
+2 files, 2 columns, 2 drifted
+
+column 'id'
+  Int32 not null in 1 file: a.parquet
+  Int64 not null in 1 file: b.parquet
+
+column 'name'
+  Utf8 nullable in 1 file: a.parquet
+  missing from 1 file: b.parquet
+
+strict: 1 files fail
+  Cannot coerce column 'id' from Int32 to Int64 under the strict policy (file: a.parquet)
+widening: every file is compatible
+lenient: every file is compatible
+
+suggested schema (widening):
+  id: Int64 not null
+  name: Utf8
+";
+    assert_eq!(DriftReport::new(files).to_string(), expected);
+}
@@ -0,0 +1,130 @@
+//! A string column stored plain, dictionary-encoded and as views must be read
+//! in the encoding the unifier is configured with, with the same values.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, AsArray, DictionaryArray, RecordBatch, StringArray, StringViewArray};
+use arrow::datatypes::{DataType, Int32Type};
+use datafusion::catalog::TableProvider;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::SessionContext;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::encoding::StringEncoding;
+use schema_evolution::{EvolutionOptions, SchemaEvolutionTableProvider};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, name: ArrayRef) {
+    let batch = RecordBatch::try_from_iter([("name", name)]).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// One file per encoding; the writer keeps the Arrow type in the footer.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a_plain.parquet"),
+        Arc::new(StringArray::from(vec!["a", "b"])),
+    );
+    write_parquet(
+        &dir.path().join("b_dictionary.parquet"),
+        Arc::new(DictionaryArray::<Int32Type>::from_iter(["c", "c", "d"])),
+    );
+    write_parquet(
+        &dir.path().join("c_view.parquet"),
+        Arc::new(StringViewArray::from(vec!["e"])),
+    );
+    dir
+}
+
+/// The table type of `name` and its values, read in order.
+async fn read(dir: &TempDir, encoding: StringEncoding) -> (DataType, Vec<String>) {
+    let ctx = SessionContext::new();
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        format!("{}/", dir.path().to_str().unwrap()),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new().with_string_encoding(encoding),
+    )
+    .await
+    .unwrap();
+    let table_type = provider.schema().field(0).data_type().clone();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+
+    let batches = ctx
+        .sql("SELECT name FROM t ORDER BY name")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let mut values = Vec::new();
+    for batch in batches {
+        assert_eq!(batch.column(0).data_type(), &table_type);
+        let column = arrow::compute::cast(batch.column(0), &DataType::Utf8).unwrap();
+        values.extend(
+            column
+                .as_string::<i32>()
+                .iter()
+                .map(|value| value.unwrap().to_string()),
+        );
+    }
+    (table_type, values)
+}
+
+#[tokio::test]
+async fn reads_every_encoding_into_the_configured_one() {
+    let dir = dataset();
+    let expected = [
+        (StringEncoding::Plain, DataType::Utf8),
+        (StringEncoding::View, DataType::Utf8View),
+        (
+            StringEncoding::Dictionary,
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+        ),
+    ];
+    for (encoding, table_type) in expected {
+        let (read_type, values) = read(&dir, encoding).await;
+        assert_eq!(read_type, table_type, "{encoding:?}");
+        assert_eq!(values, ["a", "b", "c", "c", "d", "e"], "{encoding:?}");
+    }
+}
+
+#[test]
+fn only_mixed_string_columns_change() {
+    let dictionary = DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8));
+
+    // Stored the same way everywhere, the unified type is kept
+    let same = [&DataType::Utf8, &DataType::Utf8];
+    assert_eq!(
+        StringEncoding::View.apply(&DataType::Utf8, &same),
+        DataType::Utf8
+    );
+
+    // Without a dictionary in any file, there is nothing to keep encoded
+    let plain_and_view = [&DataType::Utf8, &DataType::Utf8View];
+    assert_eq!(
+        StringEncoding::Dictionary.apply(&DataType::Utf8, &plain_and_view),
+        DataType::Utf8
+    );
+    let plain_and_dictionary = [&DataType::Utf8, &dictionary];
+    assert_eq!(
+        StringEncoding::Dictionary.apply(&DataType::Utf8, &plain_and_dictionary),
+        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+    );
+
+    // Bytes become binary views, and other types are left alone
+    let binary = [&DataType::Binary, &DataType::LargeBinary];
+    assert_eq!(
+        StringEncoding::View.apply(&DataType::Binary, &binary),
+        DataType::BinaryView
+    );
+    let numbers = [&DataType::Int32, &DataType::Int64];
+    assert_eq!(
+        StringEncoding::View.apply(&DataType::Int64, &numbers),
+        DataType::Int64
+    );
+}
@@ -0,0 +1,99 @@
+//! A table exported as an Arrow C stream must be imported with the unified
+//! schema and every row, whether it is pulled inside the runtime or not.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, AsArray, Int32Array, Int64Array, RecordBatch, RecordBatchReader, StringArray,
+};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema};
+use arrow::ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::{DataFrame, SessionContext};
+use parquet::arrow::ArrowWriter;
+use schema_evolution::ffi::export_c_stream;
+use schema_evolution::{EvolutionOptions, SchemaEvolutionTableProvider};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// `id` widened from `Int32`, and `name` added later.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a.parquet"),
+        vec![("id", Arc::new(Int32Array::from(vec![1, 2])))],
+    );
+    write_parquet(
+        &dir.path().join("b.parquet"),
+        vec![
+            ("id", Arc::new(Int64Array::from(vec![3]))),
+            ("name", Arc::new(StringArray::from(vec!["c"]))),
+        ],
+    );
+    dir
+}
+
+async fn table(dir: &TempDir) -> DataFrame {
+    let ctx = SessionContext::new();
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        format!("{}/", dir.path().to_str().unwrap()),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::default(),
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+    ctx.sql("SELECT id, name FROM t ORDER BY id").await.unwrap()
+}
+
+/// Imports the stream and checks what the consumer sees.
+fn check_import(stream: FFI_ArrowArrayStream) {
+    let reader = ArrowArrayStreamReader::try_new(stream).unwrap();
+    let schema = Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("name", DataType::Utf8, true),
+    ]);
+    assert_eq!(reader.schema().as_ref(), &schema);
+
+    let batches: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+    let batch = arrow::compute::concat_batches(&Arc::new(schema), &batches).unwrap();
+    let ids = batch
+        .column(0)
+        .as_primitive::<Int64Type>()
+        .values()
+        .to_vec();
+    assert_eq!(ids, [1, 2, 3]);
+    let names: Vec<_> = batch.column(1).as_string::<i32>().iter().collect();
+    assert_eq!(names, [None, None, Some("c")]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pulled_from_another_thread() {
+    let dir = dataset();
+    let stream = export_c_stream(table(&dir).await).await.unwrap();
+    std::thread::spawn(move || check_import(stream))
+        .join()
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pulled_from_within_the_runtime() {
+    let dir = dataset();
+    let stream = export_c_stream(table(&dir).await).await.unwrap();
+    check_import(stream);
+
+    // And from one of its workers
+    let stream = export_c_stream(table(&dir).await).await.unwrap();
+    tokio::spawn(async move { check_import(stream) })
+        .await
+        .unwrap();
+}
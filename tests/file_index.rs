@@ -0,0 +1,122 @@
+//! File indexes must read back what was written, one chunk at a time, and
+//! refuse files that are not indexes of a known layout.
+
+use std::sync::Arc;
+
+use datafusion::object_store::memory::InMemory;
+use datafusion::object_store::path::Path;
+use datafusion::object_store::{ObjectStore, PutPayload};
+use schema_evolution::file_index::ManifestFileIndex;
+
+fn entries() -> Vec<(String, u64)> {
+    (0..10)
+        .map(|day| {
+            (
+                format!("events/dt=2024-06-{day:02}/part-0.parquet"),
+                day / 5 + 1,
+            )
+        })
+        .collect()
+}
+
+async fn written() -> (Arc<dyn ObjectStore>, Path) {
+    let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    let location = Path::from("events.index");
+    // Shuffled, with a leading slash the index drops
+    let mut files = entries();
+    files.reverse();
+    files[0].0.insert(0, '/');
+    ManifestFileIndex::write(store.as_ref(), &location, files, 3)
+        .await
+        .unwrap();
+    (store, location)
+}
+
+#[tokio::test]
+async fn roundtrips_one_chunk_at_a_time() {
+    let (store, location) = written().await;
+    let index = ManifestFileIndex::open(store, location).await.unwrap();
+    assert_eq!(index.num_chunks(), 4);
+    assert_eq!(index.loaded_chunks(), 0);
+
+    index
+        .load(["events/dt=2024-06-04/part-0.parquet"])
+        .await
+        .unwrap();
+    assert_eq!(index.loaded_chunks(), 1);
+    assert_eq!(
+        index
+            .version_of("/events/dt=2024-06-04/part-0.parquet")
+            .unwrap(),
+        Some(1)
+    );
+    // Inside the chunk's range, but not indexed
+    assert_eq!(
+        index
+            .version_of("events/dt=2024-06-04/part-1.parquet")
+            .unwrap(),
+        None
+    );
+    // Outside every chunk's range
+    assert_eq!(index.version_of("other/part-0.parquet").unwrap(), None);
+
+    let err = index
+        .version_of("events/dt=2024-06-09/part-0.parquet")
+        .unwrap_err();
+    assert!(err.to_string().contains("not loaded"), "{err}");
+
+    assert_eq!(index.prefetch("events/").await.unwrap(), 4);
+    for (path, version) in entries() {
+        assert_eq!(index.version_of(&path).unwrap(), Some(version), "{path}");
+    }
+}
+
+#[tokio::test]
+async fn prefetch_reads_the_chunks_of_a_prefix() {
+    let (store, location) = written().await;
+    let index = ManifestFileIndex::open(store, location).await.unwrap();
+    // Days 06 to 08 are the third chunk
+    assert_eq!(index.prefetch("events/dt=2024-06-07").await.unwrap(), 1);
+    assert_eq!(index.loaded_chunks(), 1);
+    assert_eq!(
+        index
+            .version_of("events/dt=2024-06-07/part-0.parquet")
+            .unwrap(),
+        Some(2)
+    );
+}
+
+#[tokio::test]
+async fn rejects_duplicate_paths() {
+    let store = InMemory::new();
+    let files = vec![("a.parquet".to_string(), 1), ("/a.parquet".to_string(), 2)];
+    let err = ManifestFileIndex::write(&store, &Path::from("index"), files, 10)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("listed twice"), "{err}");
+}
+
+#[tokio::test]
+async fn rejects_other_files() {
+    let (store, location) = written().await;
+    let bytes = store.get(&location).await.unwrap().bytes().await.unwrap();
+
+    let mut bad_magic = bytes.to_vec();
+    let len = bad_magic.len();
+    bad_magic[len - 8..len - 2].copy_from_slice(b"PAR1\0\0");
+    let mut bad_version = bytes.to_vec();
+    bad_version[len - 2..].copy_from_slice(b"99");
+    let cases = [
+        ("short", b"SEVIDX01".to_vec(), "not a manifest file index"),
+        ("magic", bad_magic, "not a manifest file index"),
+        ("version", bad_version, "of version 99"),
+    ];
+    for (name, bytes, expected) in cases {
+        let location = Path::from(name);
+        store.put(&location, PutPayload::from(bytes)).await.unwrap();
+        let err = ManifestFileIndex::open(Arc::clone(&store), location)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(expected), "{name}: {err}");
+    }
+}
@@ -0,0 +1,92 @@
+//! Fingerprints are persisted next to data: a schema must hash to the same
+//! value in every release, whatever other defaults change.
+
+use std::collections::HashMap;
+
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use schema_evolution::fingerprint::{FingerprintAlgorithm, SchemaFingerprint};
+
+fn schema(name_type: DataType, metadata: &[(&str, &str)]) -> Schema {
+    Schema::new_with_metadata(
+        vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", name_type, true),
+            Field::new(
+                "at",
+                DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+                true,
+            ),
+        ],
+        metadata
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    )
+}
+
+#[test]
+fn v1_hashes_are_frozen() {
+    let cases = [
+        (
+            schema(DataType::Utf8, &[("owner", "ingest")]),
+            "v1:f60249dc6c093d74",
+        ),
+        // Another string encoding and volatile metadata hash the same
+        (
+            schema(DataType::Utf8View, &[("owner", "ingest"), ("pandas", "{}")]),
+            "v1:f60249dc6c093d74",
+        ),
+        (
+            Schema::new_with_metadata(Vec::<Field>::new(), HashMap::new()),
+            "v1:a8c7f832281a39c5",
+        ),
+    ];
+    for (schema, expected) in cases {
+        let fingerprint = SchemaFingerprint::with_algorithm(&schema, FingerprintAlgorithm::V1);
+        assert_eq!(fingerprint.to_string(), expected, "{schema:?}");
+        assert_eq!(SchemaFingerprint::of(&schema), fingerprint);
+    }
+    assert_ne!(
+        SchemaFingerprint::of(&schema(DataType::Utf8, &[("owner", "other")])),
+        SchemaFingerprint::of(&schema(DataType::Utf8, &[("owner", "ingest")])),
+    );
+}
+
+#[test]
+fn text_form_roundtrips() {
+    let fingerprint = SchemaFingerprint::of(&schema(DataType::Utf8, &[]));
+    let text = fingerprint.to_string();
+    assert_eq!(text.len(), "v1:".len() + 16);
+    assert_eq!(text.parse::<SchemaFingerprint>(), Ok(fingerprint));
+
+    let parsed: SchemaFingerprint = "v1:00000000000000ff".parse().unwrap();
+    assert_eq!(parsed.algorithm(), FingerprintAlgorithm::V1);
+    assert_eq!(parsed.hash(), 0xff);
+    assert_eq!(parsed.to_string(), "v1:00000000000000ff");
+
+    for invalid in ["", "v1", "v1:", "1:ff", "v9:ff", "vx:ff", "v1:xyz"] {
+        let err = invalid.parse::<SchemaFingerprint>().unwrap_err();
+        assert!(
+            err.to_string().contains("invalid schema fingerprint"),
+            "{invalid}"
+        );
+    }
+}
+
+#[test]
+fn upgrade_verifies_the_schema() {
+    let stored = schema(DataType::Utf8, &[]);
+    let fingerprint = SchemaFingerprint::with_algorithm(&stored, FingerprintAlgorithm::V1);
+    assert!(fingerprint.is_current());
+    assert!(fingerprint.matches(&stored));
+    assert_eq!(fingerprint.upgrade(&stored), Some(fingerprint));
+    // An equivalent encoding still verifies
+    assert_eq!(
+        fingerprint.upgrade(&schema(DataType::LargeUtf8, &[])),
+        Some(fingerprint)
+    );
+
+    let changed = schema(DataType::Int64, &[]);
+    assert!(!fingerprint.matches(&changed));
+    assert_eq!(fingerprint.upgrade(&changed), None);
+}
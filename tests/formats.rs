@@ -0,0 +1,311 @@
+//! The same evolved dataset must read the same through every supported format.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, AsArray, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema};
+use arrow::util::pretty::pretty_format_batches;
+use datafusion::common::ScalarValue;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use futures::TryStreamExt;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::single_file::adapt_file_with;
+use schema_evolution::{
+    CoercionPolicy, EvolutionOptions, FieldMapping, MissingColumnPolicy,
+    SchemaEvolutionTableProvider, UncoercibleValue,
+};
+use tempfile::TempDir;
+use vortex::VortexSessionDefault;
+use vortex::array::arrow::FromArrowArray;
+use vortex::file::WriteOptionsSessionExt;
+use vortex::session::VortexSession;
+use vortex_datafusion::VortexFormat;
+
+#[derive(Debug, Clone, Copy)]
+enum Backend {
+    Parquet,
+    Vortex,
+}
+
+impl Backend {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Parquet => "parquet",
+            Self::Vortex => "vortex",
+        }
+    }
+
+    fn format(self) -> Arc<dyn FileFormat> {
+        match self {
+            Self::Parquet => Arc::new(ParquetFormat::default()),
+            Self::Vortex => Arc::new(VortexFormat::new(VortexSession::default())),
+        }
+    }
+
+    async fn write(self, path: &Path, columns: Vec<(&str, ArrayRef)>) {
+        let batch = RecordBatch::try_from_iter(columns).unwrap();
+        match self {
+            Self::Parquet => {
+                let file = std::fs::File::create(path).unwrap();
+                let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+                writer.write(&batch).unwrap();
+                writer.close().unwrap();
+            }
+            Self::Vortex => {
+                let mut file = tokio::fs::File::create(path).await.unwrap();
+                let array = vortex::array::ArrayRef::from_arrow(batch, false).unwrap();
+                VortexSession::default()
+                    .write_options()
+                    .write(&mut file, array.to_array_stream())
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// `id` widens from Int32; `code` is UTF8 in the old file and Int64 in the new
+/// one; `qty` was called `quantity`; `region` was added with the new file.
+async fn dataset(backend: Backend) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let ext = backend.extension();
+    backend
+        .write(
+            &dir.path().join(format!("a_old.{ext}")),
+            vec![
+                ("id", Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef),
+                (
+                    "code",
+                    Arc::new(StringArray::from(vec!["A100", "B200", "300"])),
+                ),
+                ("quantity", Arc::new(Int64Array::from(vec![1, 2, 3]))),
+            ],
+        )
+        .await;
+    backend
+        .write(
+            &dir.path().join(format!("b_new.{ext}")),
+            vec![
+                ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+                ("code", Arc::new(Int64Array::from(vec![400, 500]))),
+                ("qty", Arc::new(Int64Array::from(vec![4, 5]))),
+                ("region", Arc::new(StringArray::from(vec!["us", "us"]))),
+            ],
+        )
+        .await;
+    dir
+}
+
+async fn context(backend: Backend, dir: &TempDir) -> SessionContext {
+    context_with(backend, dir, EvolutionOptions::new()).await
+}
+
+async fn context_with(
+    backend: Backend,
+    dir: &TempDir,
+    options: EvolutionOptions,
+) -> SessionContext {
+    let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(1));
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        dir.path().to_str().unwrap(),
+        backend.format(),
+        options
+            .with_field_mapping(FieldMapping::new().with_rename("quantity", "qty"))
+            .with_missing_columns(MissingColumnPolicy::new().with_literal("region", "eu")),
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+    ctx
+}
+
+async fn query(ctx: &SessionContext, sql: &str) -> String {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    pretty_format_batches(&batches).unwrap().to_string()
+}
+
+const CASES: &[(&str, &str)] = &[
+    (
+        "SELECT id, code, qty, region FROM t ORDER BY id",
+        "\
++----+------+-----+--------+
+| id | code | qty | region |
++----+------+-----+--------+
+| 1  | A100 | 1   | eu     |
+| 2  | B200 | 2   | eu     |
+| 3  | 300  | 3   | eu     |
+| 4  | 400  | 4   | us     |
+| 5  | 500  | 5   | us     |
++----+------+-----+--------+",
+    ),
+    (
+        "SELECT id FROM t WHERE code = '300' OR code = '400' ORDER BY id",
+        "\
++----+
+| id |
++----+
+| 3  |
+| 4  |
++----+",
+    ),
+    (
+        "SELECT region, sum(qty) AS qty FROM t GROUP BY region ORDER BY region",
+        "\
++--------+-----+
+| region | qty |
++--------+-----+
+| eu     | 6   |
+| us     | 9   |
++--------+-----+",
+    ),
+    (
+        "SELECT arrow_typeof(id) AS id, arrow_typeof(code) AS code FROM t LIMIT 1",
+        "\
++-------+------+
+| id    | code |
++-------+------+
+| Int64 | Utf8 |
++-------+------+",
+    ),
+];
+
+async fn assert_cases(backend: Backend) {
+    let dir = dataset(backend).await;
+    let ctx = context(backend, &dir).await;
+    for (sql, expected) in CASES {
+        assert_eq!(query(&ctx, sql).await, *expected, "{sql} ({backend:?})");
+    }
+}
+
+/// With `code` pinned to Int64, the old file's `A100` and `B200` cannot be
+/// converted: `(sql, rows as nulls, rows with the sentinel -1)`.
+const UNCOERCIBLE_CASES: &[(&str, &str, &str)] = &[
+    ("SELECT id FROM t WHERE code IS NULL ORDER BY id", "1 2", ""),
+    ("SELECT id FROM t WHERE code = -1 ORDER BY id", "", "1 2"),
+    (
+        "SELECT id FROM t WHERE code <> 300 ORDER BY id",
+        "4 5",
+        "1 2 4 5",
+    ),
+    (
+        "SELECT a.id FROM t a JOIN t b ON a.code = b.code ORDER BY a.id, b.id",
+        "3 4 5",
+        "1 1 2 2 3 4 5",
+    ),
+];
+
+async fn assert_uncoercible(backend: Backend) {
+    let dir = dataset(backend).await;
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("code", DataType::Int64, true),
+        Field::new("qty", DataType::Int64, true),
+        Field::new("region", DataType::Utf8, true),
+    ]));
+    for (value, sentinel) in [
+        (UncoercibleValue::Null, false),
+        (
+            UncoercibleValue::Sentinel(ScalarValue::Int64(Some(-1))),
+            true,
+        ),
+    ] {
+        let options = EvolutionOptions::new()
+            .with_schema(Arc::clone(&schema))
+            .with_policy(CoercionPolicy::lenient().with_column_uncoercible("code", value));
+        let ctx = context_with(backend, &dir, options).await;
+        for (sql, nulls, sentinels) in UNCOERCIBLE_CASES {
+            let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+            let ids: Vec<String> = batches
+                .iter()
+                .flat_map(|batch| {
+                    let ids = batch.column(0).as_primitive::<Int64Type>();
+                    ids.values()
+                        .iter()
+                        .map(|id| id.to_string())
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            let expected = if sentinel { sentinels } else { nulls };
+            assert_eq!(
+                ids.join(" "),
+                *expected,
+                "{sql} ({backend:?}, sentinel: {sentinel})"
+            );
+        }
+    }
+}
+
+/// The old file alone, read without a table.
+async fn assert_single_file(backend: Backend) {
+    let dir = dataset(backend).await;
+    let path = dir.path().join(format!("a_old.{}", backend.extension()));
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("code", DataType::Utf8, true),
+        Field::new("qty", DataType::Int64, true),
+        Field::new("region", DataType::Utf8, true),
+    ]));
+    let adapter_factory = SchemaEvolutionAdapterFactory::new()
+        .with_field_mapping(FieldMapping::new().with_rename("quantity", "qty"))
+        .with_missing_column_policy(MissingColumnPolicy::new().with_literal("region", "eu"));
+    let stream = adapt_file_with(
+        &SessionContext::new(),
+        path.to_str().unwrap(),
+        Arc::clone(&schema),
+        adapter_factory,
+    )
+    .await
+    .unwrap();
+    let batches: Vec<RecordBatch> = stream.try_collect().await.unwrap();
+    for batch in &batches {
+        assert_eq!(batch.schema().fields(), schema.fields(), "{backend:?}");
+    }
+    let expected = "\
++----+------+-----+--------+
+| id | code | qty | region |
++----+------+-----+--------+
+| 1  | A100 | 1   | eu     |
+| 2  | B200 | 2   | eu     |
+| 3  | 300  | 3   | eu     |
++----+------+-----+--------+";
+    assert_eq!(
+        pretty_format_batches(&batches).unwrap().to_string(),
+        expected,
+        "{backend:?}"
+    );
+}
+
+#[tokio::test]
+async fn parquet() {
+    assert_cases(Backend::Parquet).await;
+}
+
+#[tokio::test]
+async fn vortex() {
+    assert_cases(Backend::Vortex).await;
+}
+
+#[tokio::test]
+async fn parquet_uncoercible() {
+    assert_uncoercible(Backend::Parquet).await;
+}
+
+#[tokio::test]
+async fn vortex_uncoercible() {
+    assert_uncoercible(Backend::Vortex).await;
+}
+
+#[tokio::test]
+async fn parquet_single_file() {
+    assert_single_file(Backend::Parquet).await;
+}
+
+#[tokio::test]
+async fn vortex_single_file() {
+    assert_single_file(Backend::Vortex).await;
+}
@@ -0,0 +1,140 @@
+//! Joining tables whose keys unified to different types must compare the keys
+//! in their common type, whichever join the planner picks.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::util::pretty::pretty_format_batches;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::execution::SessionStateBuilder;
+use datafusion::physical_plan::displayable;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use parquet::arrow::ArrowWriter;
+use schema_evolution::join::JoinKeyCoercion;
+use schema_evolution::{EvolutionOptions, SchemaEvolutionTableProvider};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// `orders.code` is UTF8 and Int64 in its files and unifies to UTF8;
+/// `products.code` is Int32 and Int64 and unifies to Int64.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let orders = dir.path().join("orders");
+    let products = dir.path().join("products");
+    std::fs::create_dir_all(&orders).unwrap();
+    std::fs::create_dir_all(&products).unwrap();
+    write_parquet(
+        &orders.join("a_old.parquet"),
+        vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+            (
+                "code",
+                Arc::new(StringArray::from(vec!["400", "0400", "A1"])),
+            ),
+        ],
+    );
+    write_parquet(
+        &orders.join("b_new.parquet"),
+        vec![
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            ("code", Arc::new(Int64Array::from(vec![5, 40]))),
+        ],
+    );
+    write_parquet(
+        &products.join("a_old.parquet"),
+        vec![
+            ("code", Arc::new(Int32Array::from(vec![400, 5])) as ArrayRef),
+            ("name", Arc::new(StringArray::from(vec!["x", "y"]))),
+        ],
+    );
+    write_parquet(
+        &products.join("b_new.parquet"),
+        vec![
+            ("code", Arc::new(Int64Array::from(vec![40, 7])) as ArrayRef),
+            ("name", Arc::new(StringArray::from(vec!["z", "w"]))),
+        ],
+    );
+    dir
+}
+
+async fn context(dir: &TempDir, prefer_hash_join: bool) -> SessionContext {
+    let mut config = SessionConfig::new().with_target_partitions(2);
+    config.options_mut().optimizer.prefer_hash_join = prefer_hash_join;
+    let state = SessionStateBuilder::new()
+        .with_config(config)
+        .with_default_features()
+        .with_analyzer_rules(JoinKeyCoercion::new().with_default_rules())
+        .build();
+    let ctx = SessionContext::new_with_state(state);
+    for table in ["orders", "products"] {
+        let provider = SchemaEvolutionTableProvider::try_new(
+            &ctx.state(),
+            dir.path().join(table).to_str().unwrap(),
+            Arc::new(ParquetFormat::default()),
+            EvolutionOptions::new(),
+        )
+        .await
+        .unwrap();
+        ctx.register_table(table, Arc::new(provider)).unwrap();
+    }
+    ctx
+}
+
+const JOIN: &str =
+    "SELECT o.id, p.name FROM orders o JOIN products p ON o.code = p.code ORDER BY o.id";
+
+/// Keys compared as strings: `'0400'` does not match 400, and `'A1'` matches
+/// nothing instead of failing the cast to Int64.
+const EXPECTED: &str = "\
++----+------+
+| id | name |
++----+------+
+| 1  | x    |
+| 4  | y    |
+| 5  | z    |
++----+------+";
+
+async fn assert_join(prefer_hash_join: bool, operator: &str) {
+    let dir = dataset();
+    let ctx = context(&dir, prefer_hash_join).await;
+    let df = ctx.sql(JOIN).await.unwrap();
+    let plan = df.clone().create_physical_plan().await.unwrap();
+    let plan = displayable(plan.as_ref()).indent(true).to_string();
+    assert!(plan.contains(operator), "{plan}");
+
+    let batches = df.collect().await.unwrap();
+    assert_eq!(
+        pretty_format_batches(&batches).unwrap().to_string(),
+        EXPECTED
+    );
+}
+
+#[tokio::test]
+async fn hash_join() {
+    assert_join(true, "HashJoinExec").await;
+}
+
+#[tokio::test]
+async fn sort_merge_join() {
+    assert_join(false, "SortMergeJoin").await;
+}
+
+#[tokio::test]
+async fn reversed_keys() {
+    let dir = dataset();
+    let ctx = context(&dir, true).await;
+    let sql = "SELECT o.id, p.name FROM orders o JOIN products p ON p.code = o.code ORDER BY o.id";
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    assert_eq!(
+        pretty_format_batches(&batches).unwrap().to_string(),
+        EXPECTED
+    );
+}
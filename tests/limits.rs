@@ -0,0 +1,176 @@
+//! Files needing many string-parse casts must read the same values with their
+//! casts evaluated in reduced batches, and be counted in the scan's metrics.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::util::pretty::pretty_format_batches;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::source::DataSourceExec;
+use datafusion::physical_expr::expressions::col;
+use datafusion::physical_expr_adapter::PhysicalExprAdapter;
+use datafusion::physical_plan::{ExecutionPlan, collect};
+use datafusion::prelude::{SessionConfig, SessionContext};
+use parquet::arrow::ArrowWriter;
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::limits::MemoryLimits;
+use schema_evolution::{EvolutionOptions, SchemaEvolutionTableProvider};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, batch: &RecordBatch) {
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// The old file stores `a` and `b` as strings, some not numeric; the new one
+/// as integers.
+fn old_file() -> RecordBatch {
+    let a: Vec<Option<String>> = (0..10).map(|i| (i != 4).then(|| i.to_string())).collect();
+    let b: Vec<String> = (0..10)
+        .map(|i| {
+            if i % 3 == 0 {
+                "x".into()
+            } else {
+                (i * 10).to_string()
+            }
+        })
+        .collect();
+    RecordBatch::try_from_iter(vec![
+        (
+            "id",
+            Arc::new(Int64Array::from_iter_values(0..10)) as ArrayRef,
+        ),
+        ("a", Arc::new(StringArray::from(a))),
+        ("b", Arc::new(StringArray::from(b))),
+    ])
+    .unwrap()
+}
+
+fn new_file() -> RecordBatch {
+    RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int64Array::from(vec![10, 11])) as ArrayRef),
+        ("a", Arc::new(Int64Array::from(vec![100, 110]))),
+        ("b", Arc::new(Int64Array::from(vec![1_000, 1_100]))),
+    ])
+    .unwrap()
+}
+
+fn table_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Int64, true),
+    ]))
+}
+
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(&dir.path().join("a_old.parquet"), &old_file());
+    write_parquet(&dir.path().join("b_new.parquet"), &new_file());
+    dir
+}
+
+async fn context(dir: &TempDir, limits: MemoryLimits) -> SessionContext {
+    let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(1));
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        dir.path().to_str().unwrap(),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new()
+            .with_schema(table_schema())
+            .with_memory_limits(limits),
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+    ctx
+}
+
+/// The query's results and the scan's `files_with_reduced_cast_batches`.
+async fn scan(ctx: &SessionContext, sql: &str) -> (String, usize) {
+    let plan = ctx
+        .sql(sql)
+        .await
+        .unwrap()
+        .create_physical_plan()
+        .await
+        .unwrap();
+    let batches = collect(Arc::clone(&plan), ctx.task_ctx()).await.unwrap();
+    let mut reduced = 0;
+    let mut plans = vec![plan];
+    while let Some(plan) = plans.pop() {
+        if plan.as_any().is::<DataSourceExec>()
+            && let Some(metrics) = plan.metrics()
+        {
+            reduced += metrics
+                .sum_by_name("files_with_reduced_cast_batches")
+                .map_or(0, |value| value.as_usize());
+        }
+        plans.extend(plan.children().into_iter().cloned());
+    }
+    (
+        pretty_format_batches(&batches).unwrap().to_string(),
+        reduced,
+    )
+}
+
+const QUERIES: &[&str] = &[
+    "SELECT * FROM t ORDER BY id",
+    "SELECT id, a + b AS total FROM t WHERE b > 20 ORDER BY id",
+    "SELECT count(a), sum(b) FROM t WHERE a IS NOT NULL",
+];
+
+#[tokio::test]
+async fn reduced_batches_read_the_same_values() {
+    let dir = dataset();
+    let reference = context(&dir, MemoryLimits::new()).await;
+    let limited = context(&dir, MemoryLimits::new().with_parse_batch_rows(3)).await;
+    for sql in QUERIES {
+        let (expected, none) = scan(&reference, sql).await;
+        let (actual, reduced) = scan(&limited, sql).await;
+        assert_eq!(actual, expected, "{sql}");
+        assert_eq!(none, 0, "{sql}");
+        // Only the old file parses strings
+        assert_eq!(reduced, 1, "{sql}");
+    }
+}
+
+#[tokio::test]
+async fn files_with_few_parse_casts_keep_their_batches() {
+    let dir = dataset();
+    let ctx = context(
+        &dir,
+        MemoryLimits::new()
+            .with_parse_batch_rows(3)
+            .with_min_parse_casts(3),
+    )
+    .await;
+    let (_, reduced) = scan(&ctx, "SELECT * FROM t").await;
+    assert_eq!(reduced, 0);
+}
+
+#[test]
+fn parse_casts_are_evaluated_in_slices() {
+    let file = old_file();
+    let factory = SchemaEvolutionAdapterFactory::new()
+        .with_memory_limits(MemoryLimits::new().with_parse_batch_rows(4));
+    let adapter = factory.adapter(table_schema(), file.schema(), None);
+    assert_eq!(adapter.parse_batch_rows(), Some(4));
+
+    let a = adapter.rewrite(col("a", &table_schema()).unwrap()).unwrap();
+    assert!(a.to_string().starts_with("REDUCED_BATCH("), "{a}");
+    let id = adapter
+        .rewrite(col("id", &table_schema()).unwrap())
+        .unwrap();
+    assert!(!id.to_string().contains("REDUCED_BATCH"), "{id}");
+
+    let values = a.evaluate(&file).unwrap().into_array(10).unwrap();
+    let expected: ArrayRef = Arc::new(Int64Array::from(
+        (0..10).map(|i| (i != 4).then_some(i)).collect::<Vec<_>>(),
+    ));
+    assert_eq!(&values, &expected);
+}
@@ -0,0 +1,264 @@
+//! Manifests must be validated when loaded, roundtrip through JSON and plan
+//! every file's migration to the last version.
+
+use std::collections::HashMap;
+
+use arrow::datatypes::Schema;
+use datafusion::common::ScalarValue;
+use schema_evolution::format::FileContext;
+use schema_evolution::manifest::{EvolutionManifest, MigrationPlan};
+
+#[test]
+fn default_added_before_a_scale_is_scaled() {
+    let manifest = EvolutionManifest::from_json(
+        r#"{"versions": [
+          {"version": 1, "files": {"path": "v1/*"}, "columns": [{"name": "id", "type": "Int64"}]},
+          {"version": 2, "files": {"path": "v2/*"},
+           "columns": [{"name": "id", "type": "Int64"}, {"name": "latency", "type": "Int64"},
+                       {"name": "ratio", "type": "Float64"}],
+           "migration": [{"op": "add", "column": "latency", "type": "Int64", "default": 1500},
+                         {"op": "add", "column": "ratio", "type": "Float64", "default": 0.5}]},
+          {"version": 3, "files": {"path": "v3/*"},
+           "columns": [{"name": "id", "type": "Int64"}, {"name": "latency", "type": "Int64"},
+                       {"name": "ratio", "type": "Float64"}],
+           "migration": [{"op": "scale", "column": "latency", "factor": 0.001},
+                         {"op": "scale", "column": "ratio", "factor": 100.0}]}
+        ]}"#,
+    )
+    .unwrap();
+
+    // Files of version 1 lack both columns: their defaults are in the units of
+    // version 2 and are scaled like its values
+    let plan = manifest
+        .plan(Some(&FileContext::new("v1/a.parquet")), &Schema::empty())
+        .unwrap();
+    assert!(plan.scales.is_empty());
+    assert_eq!(
+        plan.defaults,
+        [
+            ("latency".to_string(), ScalarValue::Int64(Some(1))),
+            ("ratio".to_string(), ScalarValue::Float64(Some(50.0))),
+        ]
+    );
+
+    let plan = manifest
+        .plan(Some(&FileContext::new("v2/a.parquet")), &Schema::empty())
+        .unwrap();
+    assert!(plan.defaults.is_empty());
+    assert_eq!(
+        plan.scales,
+        [("latency".to_string(), 0.001), ("ratio".to_string(), 100.0)]
+    );
+}
+
+#[test]
+fn scaling_a_string_default_fails() {
+    let manifest = EvolutionManifest::from_json(
+        r#"{"versions": [
+          {"version": 1, "files": {"path": "v1/*"}, "columns": [{"name": "id", "type": "Int64"}]},
+          {"version": 2, "files": {"path": "v2/*"},
+           "columns": [{"name": "id", "type": "Int64"}, {"name": "code", "type": "Utf8"}],
+           "migration": [{"op": "add", "column": "code", "type": "Utf8", "default": "x"}]},
+          {"version": 3, "files": {"path": "v3/*"},
+           "columns": [{"name": "id", "type": "Int64"}, {"name": "code", "type": "Utf8"}],
+           "migration": [{"op": "scale", "column": "code", "factor": 2.0}]}
+        ]}"#,
+    )
+    .unwrap();
+    let err = manifest
+        .plan(Some(&FileContext::new("v1/a.parquet")), &Schema::empty())
+        .unwrap_err();
+    assert!(err.to_string().contains("Cannot scale 'code'"), "{err}");
+}
+
+/// The manifest of the type's documentation.
+const MANIFEST: &str = r#"{"versions": [
+  {"version": 1, "files": {"path": "*/v1/*"},
+   "columns": [{"name": "uid", "type": "Int32"}, {"name": "status", "type": "Int32"},
+               {"name": "latency", "type": "Int64"}]},
+  {"version": 2, "files": {"metadata": {"key": "schema_version", "value": "2"}},
+   "columns": [{"name": "user_id", "type": "Int64"}, {"name": "latency", "type": "Int64"},
+               {"name": "region", "type": "Utf8"}],
+   "migration": [
+     {"op": "rename", "from": "uid", "to": "user_id"},
+     {"op": "cast", "column": "user_id", "type": "Int64"},
+     {"op": "drop", "column": "status"},
+     {"op": "scale", "column": "latency", "factor": 0.001},
+     {"op": "add", "column": "region", "type": "Utf8", "default": "eu"}]},
+  {"version": 3, "files": {"path": "*/v3/*"},
+   "columns": [{"name": "user_id", "type": "Int64"}, {"name": "latency", "type": "Int64"},
+               {"name": "region", "type": "Utf8"}, {"name": "status", "type": "Utf8"}],
+   "migration": [{"op": "add", "column": "status", "type": "Utf8", "default": null}]}
+]}"#;
+
+#[test]
+fn plans_every_step_after_the_file_version() {
+    let manifest = EvolutionManifest::from_json(MANIFEST).unwrap();
+    assert_eq!(manifest.schema().fields().len(), 4);
+
+    let plan = manifest
+        .plan(
+            Some(&FileContext::new("events/v1/a.parquet")),
+            &Schema::empty(),
+        )
+        .unwrap();
+    assert_eq!(plan.version, 1);
+    assert_eq!(plan.renames, [("uid".to_string(), "user_id".to_string())]);
+    assert_eq!(plan.casts, ["user_id"]);
+    assert_eq!(plan.scales, [("latency".to_string(), 0.001)]);
+    // The stored `status` is dropped, though version 3 adds a column so named
+    assert!(plan.dropped.contains("status"));
+    assert_eq!(
+        plan.defaults,
+        [
+            (
+                "region".to_string(),
+                ScalarValue::Utf8(Some("eu".to_string()))
+            ),
+            ("status".to_string(), ScalarValue::Utf8(None)),
+        ]
+    );
+
+    // Version 2 is told by the footer's metadata rather than the path
+    let footer = Schema::empty().with_metadata(HashMap::from([(
+        "schema_version".to_string(),
+        "2".to_string(),
+    )]));
+    let plan = manifest
+        .plan(Some(&FileContext::new("events/a.parquet")), &footer)
+        .unwrap();
+    assert_eq!(plan.version, 2);
+    assert!(plan.renames.is_empty() && plan.casts.is_empty() && plan.scales.is_empty());
+    assert!(plan.dropped.is_empty());
+    assert_eq!(
+        plan.defaults,
+        [("status".to_string(), ScalarValue::Utf8(None))]
+    );
+
+    let plan = manifest
+        .plan(
+            Some(&FileContext::new("events/v3/a.parquet")),
+            &Schema::empty(),
+        )
+        .unwrap();
+    assert_eq!(plan.version, 3);
+    assert_eq!(
+        plan,
+        MigrationPlan {
+            version: 3,
+            ..Default::default()
+        }
+    );
+
+    let err = manifest
+        .plan(
+            Some(&FileContext::new("events/a.parquet")),
+            &Schema::empty(),
+        )
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("File events/a.parquet matches no version"),
+        "{err}"
+    );
+}
+
+#[test]
+fn json_roundtrips() {
+    let manifest = EvolutionManifest::from_json(MANIFEST).unwrap();
+    let json = manifest.to_json().unwrap();
+    let reread = EvolutionManifest::from_json(&json).unwrap();
+    assert_eq!(reread.to_json().unwrap(), json);
+    for (version, reread) in manifest.versions().iter().zip(reread.versions()) {
+        assert_eq!(version.version, reread.version);
+        assert_eq!(version.schema, reread.schema);
+        assert_eq!(version.migration, reread.migration);
+    }
+}
+
+#[test]
+fn rejects_migrations_that_do_not_yield_the_version() {
+    let manifest = |second: &str| {
+        EvolutionManifest::from_json(&format!(
+            r#"{{"versions": [
+              {{"version": 1, "files": {{"path": "v1/*"}},
+               "columns": [{{"name": "id", "type": "Int64"}}, {{"name": "ms", "type": "Int64"}}]}},
+              {second}
+            ]}}"#
+        ))
+        .unwrap_err()
+        .to_string()
+    };
+    let cases = [
+        (
+            r#"{"version": 1, "files": {"path": "v2/*"},
+               "columns": [{"name": "id", "type": "Int64"}, {"name": "ms", "type": "Int64"}]}"#,
+            "Version 1 follows version 1; versions must increase",
+        ),
+        (
+            r#"{"version": 2, "files": {"path": "v2/*"},
+               "columns": [{"name": "key", "type": "Int64"}, {"name": "ms", "type": "Int64"}],
+               "migration": [{"op": "rename", "from": "uid", "to": "key"}]}"#,
+            "Version 2 renames 'uid', which version 1 does not have",
+        ),
+        (
+            r#"{"version": 2, "files": {"path": "v2/*"},
+               "columns": [{"name": "id", "type": "Int64"}],
+               "migration": [{"op": "drop", "column": "status"}]}"#,
+            "Version 2 drops 'status', which it does not have",
+        ),
+        (
+            r#"{"version": 2, "files": {"path": "v2/*"},
+               "columns": [{"name": "id", "type": "Int64"}, {"name": "ms", "type": "Int64"}],
+               "migration": [{"op": "scale", "column": "ms", "factor": 0.0}]}"#,
+            "Version 2 scales 'ms' by 0; factors must be positive",
+        ),
+        (
+            r#"{"version": 2, "files": {"path": "v2/*"},
+               "columns": [{"name": "id", "type": "Int64"}, {"name": "ms", "type": "Int64"}],
+               "migration": [{"op": "cast", "column": "seconds", "type": "Float64"}]}"#,
+            "Version 2 casts 'seconds', which it does not have",
+        ),
+        (
+            r#"{"version": 2, "files": {"path": "v2/*"},
+               "columns": [{"name": "id", "type": "Int64"}, {"name": "ms", "type": "Int64"}],
+               "migration": [{"op": "cast", "column": "ms", "type": "Float64"}]}"#,
+            "Version 2 casts 'ms' to Float64, but declares it as Int64",
+        ),
+        (
+            r#"{"version": 2, "files": {"path": "v2/*"},
+               "columns": [{"name": "id", "type": "Int64"}]}"#,
+            r#"The migration to version 2 yields columns ["id", "ms"], but it declares ["id"]"#,
+        ),
+    ];
+    for (second, expected) in cases {
+        let err = manifest(second);
+        assert!(err.contains(expected), "{err}");
+    }
+
+    let err = EvolutionManifest::from_json(r#"{"versions": []}"#).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("A manifest needs at least one version"),
+        "{err}"
+    );
+}
+
+#[test]
+fn rejects_casts_arrow_cannot_do() {
+    let err = EvolutionManifest::from_json(
+        r#"{"versions": [
+          {"version": 1, "files": {"path": "v1/*"},
+           "columns": [{"name": "payload", "type": "Binary"}]},
+          {"version": 2, "files": {"path": "v2/*"},
+           "columns": [{"name": "payload", "type": "Int64"}],
+           "migration": [{"op": "cast", "column": "payload", "type": "Int64"}]}
+        ]}"#,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Version 2 casts 'payload' from Binary to Int64, which arrow cannot do"),
+        "{err}"
+    );
+}
@@ -0,0 +1,96 @@
+//! Renamed columns must be found under any of their historical names, in the
+//! files each rename is scoped to.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use schema_evolution::format::FileContext;
+use schema_evolution::mapping::{FieldMapping, MappingScope};
+
+fn schema(names: &[&str]) -> SchemaRef {
+    Arc::new(Schema::new(
+        names
+            .iter()
+            .map(|name| Field::new(*name, DataType::Int64, true))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+fn names(schema: &Schema) -> Vec<&str> {
+    schema
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .collect()
+}
+
+#[test]
+fn chained_renames_resolve_to_any_historical_name() {
+    let mapping = FieldMapping::new()
+        .with_rename("uid", "user")
+        .with_rename("user", "user_id");
+
+    assert_eq!(
+        mapping.resolve("user_id", &schema(&["user_id"]), None),
+        Some("user_id")
+    );
+    assert_eq!(
+        mapping.resolve("user_id", &schema(&["user"]), None),
+        Some("user")
+    );
+    assert_eq!(
+        mapping.resolve("user_id", &schema(&["uid"]), None),
+        Some("uid")
+    );
+    // The most recent name the file has wins
+    assert_eq!(
+        mapping.resolve("user_id", &schema(&["uid", "user"]), None),
+        Some("user")
+    );
+    assert_eq!(mapping.resolve("user_id", &schema(&["other"]), None), None);
+
+    let applied = mapping.apply(&schema(&["uid", "other"]), None);
+    assert_eq!(names(&applied), ["user_id", "other"]);
+    // Files without historical names are returned as they are
+    let current = schema(&["user_id"]);
+    assert!(Arc::ptr_eq(&mapping.apply(&current, None), &current));
+}
+
+#[test]
+fn cyclic_renames_terminate() {
+    let mapping = FieldMapping::new()
+        .with_rename("a", "b")
+        .with_rename("b", "a");
+    assert_eq!(mapping.resolve("a", &schema(&["c"]), None), None);
+    assert_eq!(mapping.resolve("a", &schema(&["b"]), None), Some("b"));
+    let applied = mapping.apply(&schema(&["c"]), None);
+    assert_eq!(names(&applied), ["c"]);
+}
+
+#[test]
+fn scoped_renames_apply_to_their_files_only() {
+    let cutoff = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mapping = FieldMapping::new()
+        .with_scoped_rename("uid", "user_id", MappingScope::path_prefix("/events/2023/"))
+        .with_scoped_rename("usr", "user_id", MappingScope::modified_before(cutoff));
+    let file = schema(&["uid", "usr"]);
+
+    let old_path = FileContext::new("events/2023/a.parquet");
+    let new_path = FileContext::new("events/2024/a.parquet");
+    assert_eq!(
+        mapping.resolve("user_id", &file, Some(&old_path)),
+        Some("uid")
+    );
+    assert_eq!(mapping.resolve("user_id", &file, Some(&new_path)), None);
+    // Without the file, no scoped rename applies
+    assert_eq!(mapping.resolve("user_id", &file, None), None);
+
+    let old = new_path
+        .clone()
+        .with_last_modified(cutoff - Duration::from_secs(1));
+    let new = new_path.with_last_modified(cutoff);
+    assert_eq!(mapping.resolve("user_id", &file, Some(&old)), Some("usr"));
+    assert_eq!(mapping.resolve("user_id", &file, Some(&new)), None);
+    assert_eq!(names(&mapping.apply(&file, Some(&old))), ["uid", "user_id"]);
+}
@@ -0,0 +1,159 @@
+//! Unified schemas must take the narrowest common type of every column under
+//! the policy, and report how each file deviates from them.
+
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use schema_evolution::merge::{Deviation, SchemaUnifier, common_type};
+use schema_evolution::policy::{Coercion, CoercionMode, CoercionPolicy};
+
+fn schema(fields: Vec<(&str, DataType, bool)>) -> SchemaRef {
+    Arc::new(Schema::new(
+        fields
+            .into_iter()
+            .map(|(name, data_type, nullable)| Field::new(name, data_type, nullable))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[test]
+fn common_type_widens_numbers() {
+    use DataType::*;
+
+    let cases = [
+        (Int32, Int64, CoercionMode::Widening, Some(Int64)),
+        (Int8, UInt8, CoercionMode::Widening, Some(Int16)),
+        (
+            UInt64,
+            Int64,
+            CoercionMode::Widening,
+            Some(Decimal128(20, 0)),
+        ),
+        (
+            Decimal128(10, 2),
+            Decimal128(12, 0),
+            CoercionMode::Widening,
+            Some(Decimal128(14, 2)),
+        ),
+        (Int32, Float32, CoercionMode::Widening, Some(Float64)),
+        (Int64, Float64, CoercionMode::Widening, None),
+        (Int64, Float64, CoercionMode::Lenient, Some(Float64)),
+        (Int32, Int64, CoercionMode::Strict, None),
+        (Utf8, Utf8View, CoercionMode::Strict, Some(Utf8)),
+        (Int64, Boolean, CoercionMode::Lenient, Some(Utf8)),
+    ];
+    for (left, right, mode, expected) in cases {
+        assert_eq!(
+            common_type(&left, &right, mode),
+            expected,
+            "{left} and {right} under {mode}"
+        );
+        assert_eq!(
+            common_type(&right, &left, mode),
+            expected,
+            "{right} and {left} under {mode}"
+        );
+    }
+}
+
+#[test]
+fn floats_and_decimals_meet_at_float64_lossily() {
+    use DataType::*;
+
+    for (decimal, float) in [(Decimal128(10, 2), Float64), (Decimal256(40, 5), Float32)] {
+        for (left, right) in [(&decimal, &float), (&float, &decimal)] {
+            assert_eq!(
+                common_type(left, right, CoercionMode::Lenient),
+                Some(Float64)
+            );
+            assert_eq!(common_type(left, right, CoercionMode::Widening), None);
+            assert_eq!(common_type(left, right, CoercionMode::Strict), None);
+        }
+    }
+
+    let files = vec![
+        (
+            "a.parquet".to_string(),
+            schema(vec![("price", Decimal128(10, 2), false)]),
+        ),
+        (
+            "b.parquet".to_string(),
+            schema(vec![("price", Float64, false)]),
+        ),
+    ];
+    let unified = SchemaUnifier::new().unify(files.clone());
+    let price = unified.schema.field_with_name("price").unwrap();
+    assert_eq!(price.data_type(), &Float64);
+    // Values may not survive the cast, so the column becomes nullable
+    assert!(price.is_nullable());
+    assert_eq!(
+        unified.report.files[0].deviations,
+        [Deviation::TypeMismatch {
+            column: "price".to_string(),
+            file_type: Decimal128(10, 2),
+            table_type: Float64,
+            coercion: Coercion::Lenient,
+        }]
+    );
+
+    let unified = SchemaUnifier::new()
+        .with_policy(CoercionPolicy::strict())
+        .unify(files);
+    assert!(!unified.report.is_compatible());
+    let conflict = unified.report.conflicts().next().unwrap();
+    assert_eq!(conflict.file.as_deref(), Some("b.parquet"));
+    assert_eq!(conflict.column, "price");
+}
+
+#[test]
+fn report_names_missing_and_widened_columns() {
+    let unified = SchemaUnifier::new()
+        .with_policy(CoercionPolicy::widening())
+        .unify(vec![
+            (
+                "b.parquet".to_string(),
+                schema(vec![
+                    ("id", DataType::Int64, false),
+                    ("score", DataType::Float64, false),
+                ]),
+            ),
+            (
+                "a.parquet".to_string(),
+                schema(vec![("id", DataType::Int32, false)]),
+            ),
+        ]);
+
+    // Files are visited in path order, and a missing column becomes nullable
+    assert_eq!(
+        unified.schema,
+        schema(vec![
+            ("id", DataType::Int64, false),
+            ("score", DataType::Float64, true),
+        ])
+    );
+    assert!(unified.report.is_compatible());
+    let deviating: Vec<_> = unified
+        .report
+        .deviating_files()
+        .map(|file| file.path.as_str())
+        .collect();
+    assert_eq!(deviating, ["a.parquet"]);
+    assert_eq!(
+        unified.report.files[0].deviations,
+        [
+            Deviation::TypeMismatch {
+                column: "id".to_string(),
+                file_type: DataType::Int32,
+                table_type: DataType::Int64,
+                coercion: Coercion::Widen,
+            },
+            Deviation::MissingColumn {
+                column: "score".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        unified.report.to_string(),
+        "a.parquet\n  column 'id' is Int32, coerced to Int64 (Widen)\n  missing column 'score'\n"
+    );
+}
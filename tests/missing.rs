@@ -0,0 +1,140 @@
+//! Columns older files lack must read as their configured defaults.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, Int64Array, RecordBatch, RecordBatchOptions, StringArray,
+};
+use arrow::datatypes::{
+    DataType, Field, Int64Type, Schema, SchemaRef, TimeUnit, TimestampMicrosecondType,
+};
+use datafusion::functions::expr_fn::concat;
+use datafusion::logical_expr::{col as df_col, lit};
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_expr::expressions::col;
+use datafusion::physical_expr_adapter::PhysicalExprAdapter;
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::format::FileContext;
+use schema_evolution::missing::{ColumnDefault, MissingColumnPolicy, NullArrayExpr};
+
+fn table_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("first", DataType::Utf8, true),
+        Field::new("region", DataType::Utf8, true),
+        Field::new("label", DataType::Utf8, true),
+        Field::new("priority", DataType::Int64, false),
+        Field::new(
+            "created_at",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            true,
+        ),
+    ]))
+}
+
+fn file() -> RecordBatch {
+    RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+        ("first", Arc::new(StringArray::from(vec!["ada", "bo"]))),
+    ])
+    .unwrap()
+}
+
+fn read(
+    policy: MissingColumnPolicy,
+    file_context: Option<FileContext>,
+    column: &str,
+) -> datafusion::common::Result<ArrayRef> {
+    let table = table_schema();
+    let batch = file();
+    let adapter = SchemaEvolutionAdapterFactory::new()
+        .with_missing_column_policy(policy)
+        .adapter(Arc::clone(&table), batch.schema(), file_context);
+    adapter
+        .rewrite(col(column, &table)?)?
+        .evaluate(&batch)?
+        .into_array(batch.num_rows())
+}
+
+#[test]
+fn literals_and_expressions_fill_missing_columns() {
+    let policy = MissingColumnPolicy::new()
+        .with_literal("region", "eu")
+        .with_literal("priority", 3_i32)
+        .with_expr("label", concat(vec![df_col("first"), lit("!")]));
+
+    let region = read(policy.clone(), None, "region").unwrap();
+    assert_eq!(region.as_string::<i32>().value(1), "eu");
+    // Literals are cast to the column's type
+    let priority = read(policy.clone(), None, "priority").unwrap();
+    assert_eq!(priority.as_primitive::<Int64Type>().values(), &[3, 3]);
+    let label = read(policy, None, "label").unwrap();
+    let label = label.as_string::<i32>();
+    assert_eq!((label.value(0), label.value(1)), ("ada!", "bo!"));
+}
+
+#[test]
+fn unconfigured_columns_are_null_unless_required() {
+    let region = read(MissingColumnPolicy::new(), None, "region").unwrap();
+    assert_eq!(region.null_count(), 2);
+    let err = read(MissingColumnPolicy::new(), None, "priority").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Non-nullable column 'priority' is missing"),
+        "{err}"
+    );
+}
+
+#[test]
+fn file_modification_time_needs_the_file() {
+    let policy = MissingColumnPolicy::new().with_column("created_at", ColumnDefault::FileModified);
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let file = FileContext::new("a.parquet").with_last_modified(modified);
+
+    let created_at = read(policy.clone(), Some(file), "created_at").unwrap();
+    assert_eq!(
+        created_at
+            .as_primitive::<TimestampMicrosecondType>()
+            .value(0),
+        1_700_000_000_000_000
+    );
+    let err = read(policy, None, "created_at").unwrap_err();
+    assert!(err.to_string().contains("EvolvingFormat"), "{err}");
+}
+
+/// A batch of `len` rows without columns.
+fn rows(len: usize) -> RecordBatch {
+    RecordBatch::try_new_with_options(
+        Arc::new(Schema::empty()),
+        vec![],
+        &RecordBatchOptions::new().with_row_count(Some(len)),
+    )
+    .unwrap()
+}
+
+#[test]
+fn null_arrays_are_sliced_from_the_longest_so_far() {
+    let expr = NullArrayExpr::new(Arc::new(Field::new("region", DataType::Int64, true)));
+    let evaluate = |len| {
+        let array = expr.evaluate(&rows(len)).unwrap().into_array(len).unwrap();
+        assert_eq!(array.len(), len);
+        assert_eq!(array.data_type(), &DataType::Int64);
+        assert_eq!(array.logical_null_count(), len);
+        array
+    };
+    let values = |array: &ArrayRef| array.to_data().buffers()[0].as_ptr();
+
+    let short = evaluate(3);
+    // Growing, a longer array replaces the cached one
+    let long = evaluate(10);
+    assert_ne!(values(&short), values(&long));
+    // Shrinking and growing back up to it, the batches share its buffer
+    for len in [4, 0, 10, 7] {
+        assert_eq!(values(&evaluate(len)), values(&long), "{len} rows");
+    }
+    // Past it, the cache grows again
+    let longer = evaluate(12);
+    assert_ne!(values(&longer), values(&long));
+    assert_eq!(values(&evaluate(11)), values(&longer));
+}
@@ -0,0 +1,179 @@
+//! Accessing one field of an evolved struct column must only read that field
+//! of each file's struct, under its own type.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, Int32Array, Int64Array, RecordBatch, StringArray, StructArray,
+};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema, SchemaRef};
+use arrow::util::pretty::pretty_format_batches;
+use datafusion::config::ConfigOptions;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::functions::core::get_field;
+use datafusion::physical_expr::expressions::{col, lit};
+use datafusion::physical_expr::{PhysicalExpr, ScalarFunctionExpr};
+use datafusion::physical_expr_adapter::PhysicalExprAdapter;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use parquet::arrow::ArrowWriter;
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::{EvolutionOptions, FieldMapping, SchemaEvolutionTableProvider};
+
+fn write_parquet(path: &Path, batch: &RecordBatch) {
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// `meta.user_id` widens from Int32; the old struct also has a wide `blob`
+/// field the new one dropped, and the new one adds `tag`.
+fn old_file() -> RecordBatch {
+    let meta = StructArray::from(vec![
+        (
+            Arc::new(Field::new("user_id", DataType::Int32, true)),
+            Arc::new(Int32Array::from(vec![7, 8])) as ArrayRef,
+        ),
+        (
+            Arc::new(Field::new("blob", DataType::Utf8, true)),
+            Arc::new(StringArray::from(vec!["x".repeat(4096), "y".repeat(4096)])) as ArrayRef,
+        ),
+    ]);
+    RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+        ("meta", Arc::new(meta)),
+    ])
+    .unwrap()
+}
+
+fn new_file() -> RecordBatch {
+    let meta = StructArray::from(vec![
+        (
+            Arc::new(Field::new("user_id", DataType::Int64, true)),
+            Arc::new(Int64Array::from(vec![9])) as ArrayRef,
+        ),
+        (
+            Arc::new(Field::new("tag", DataType::Utf8, true)),
+            Arc::new(StringArray::from(vec!["new"])) as ArrayRef,
+        ),
+    ]);
+    RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int64Array::from(vec![3])) as ArrayRef),
+        ("meta", Arc::new(meta)),
+    ])
+    .unwrap()
+}
+
+fn table_schema() -> SchemaRef {
+    let meta = DataType::Struct(
+        vec![
+            Field::new("user_id", DataType::Int64, true),
+            Field::new("blob", DataType::Utf8, true),
+            Field::new("tag", DataType::Utf8, true),
+        ]
+        .into(),
+    );
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("meta", meta, true),
+    ]))
+}
+
+fn field_access(name: &str, schema: &Schema) -> Arc<dyn PhysicalExpr> {
+    Arc::new(
+        ScalarFunctionExpr::try_new(
+            get_field(),
+            vec![col("meta", schema).unwrap(), lit(name)],
+            schema,
+            Arc::new(ConfigOptions::default()),
+        )
+        .unwrap(),
+    )
+}
+
+#[test]
+fn field_access_reads_the_stored_field() {
+    let file = old_file();
+    let adapter = SchemaEvolutionAdapterFactory::new().adapter(table_schema(), file.schema(), None);
+
+    let user_id = adapter
+        .rewrite(field_access("user_id", &table_schema()))
+        .unwrap();
+    // The old struct as a whole, with its `blob`, is not cast
+    assert!(!user_id.to_string().contains("blob"), "{user_id}");
+    let values = user_id.evaluate(&file).unwrap().into_array(2).unwrap();
+    assert_eq!(values.as_primitive::<Int64Type>().values(), &[7, 8]);
+
+    let tag = adapter
+        .rewrite(field_access("tag", &table_schema()))
+        .unwrap();
+    let values = tag.evaluate(&file).unwrap().into_array(2).unwrap();
+    assert_eq!(values.null_count(), 2);
+}
+
+#[tokio::test]
+async fn field_access_through_the_table() {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(&dir.path().join("a_old.parquet"), &old_file());
+    write_parquet(&dir.path().join("b_new.parquet"), &new_file());
+    let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(1));
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        dir.path().to_str().unwrap(),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new(),
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+
+    let batches = ctx
+        .sql(
+            "SELECT id, meta['user_id'] AS user_id, meta['tag'] AS tag FROM t \
+             WHERE meta['user_id'] > 7 ORDER BY id",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let expected = "\
++----+---------+-----+
+| id | user_id | tag |
++----+---------+-----+
+| 2  | 8       |     |
+| 3  | 9       | new |
++----+---------+-----+";
+    assert_eq!(
+        pretty_format_batches(&batches).unwrap().to_string(),
+        expected
+    );
+}
+
+#[tokio::test]
+async fn nested_renames_are_rejected() {
+    let mapping = FieldMapping::new()
+        .with_rename("id", "id")
+        .with_rename("meta.blob_v1", "meta.blob");
+    let err = mapping.validate(&table_schema()).unwrap_err();
+    assert!(err.to_string().contains("nested in 'meta'"), "{err}");
+    // A top-level column whose name has a dot is renamed as usual
+    let dotted = Schema::new(vec![Field::new("meta.blob", DataType::Utf8, true)]);
+    mapping.validate(&dotted).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(&dir.path().join("a_old.parquet"), &old_file());
+    let ctx = SessionContext::new();
+    let err = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        dir.path().to_str().unwrap(),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new()
+            .with_schema(table_schema())
+            .with_field_mapping(mapping),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("meta.blob"), "{err}");
+}
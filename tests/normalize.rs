@@ -0,0 +1,143 @@
+//! String normalization must apply its steps in order, keep the encoding of
+//! the array it normalizes and be applied by the adapter to the columns the
+//! policy names.
+
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, DictionaryArray, RecordBatch, StringArray, StringViewArray,
+};
+use arrow::datatypes::{DataType, Field, Int32Type, Schema};
+use datafusion::physical_expr::expressions::col;
+use datafusion::physical_expr_adapter::PhysicalExprAdapter;
+use schema_evolution::adapter::{FilePlan, SchemaEvolutionAdapterFactory};
+use schema_evolution::normalize::{CaseFold, StringNormalization, UnicodeForm};
+use schema_evolution::policy::CoercionPolicy;
+
+fn strings(array: &ArrayRef) -> Vec<Option<String>> {
+    let array = arrow::compute::cast(array, &DataType::Utf8).unwrap();
+    array
+        .as_string::<i32>()
+        .iter()
+        .map(|value| value.map(str::to_string))
+        .collect()
+}
+
+#[test]
+fn normalizes_values_step_by_step() {
+    let nfc = StringNormalization::new().with_unicode(UnicodeForm::Nfc);
+    assert_eq!(nfc.normalize("Cafe\u{301}"), "Café");
+    // Canonical composition keeps compatibility variants
+    assert_eq!(nfc.normalize("\u{fb01}le"), "\u{fb01}le");
+    let nfkc = StringNormalization::new().with_unicode(UnicodeForm::Nfkc);
+    assert_eq!(nfkc.normalize("\u{fb01}le \u{ff11}"), "file 1");
+
+    let folded = StringNormalization::new()
+        .with_trim()
+        .with_case(CaseFold::Upper);
+    assert_eq!(folded.normalize("  de "), "DE");
+    assert_eq!(
+        StringNormalization::new()
+            .with_case(CaseFold::Lower)
+            .normalize("ÉTÉ"),
+        "été"
+    );
+    assert_eq!(
+        StringNormalization::new()
+            .with_unicode(UnicodeForm::Nfc)
+            .with_case(CaseFold::Upper)
+            .with_trim()
+            .normalize(" cafe\u{301}\t"),
+        "CAFÉ"
+    );
+
+    assert!(StringNormalization::new().is_identity());
+    assert_eq!(StringNormalization::new().to_string(), "none");
+    assert_eq!(
+        StringNormalization::new()
+            .with_trim()
+            .with_unicode(UnicodeForm::Nfkc)
+            .with_case(CaseFold::Lower)
+            .to_string(),
+        "nfkc, lower, trim"
+    );
+}
+
+#[test]
+fn normalizes_arrays_in_their_encoding() {
+    let normalization = StringNormalization::new()
+        .with_trim()
+        .with_case(CaseFold::Lower);
+    let expected = vec![Some("de".to_string()), None, Some("fr".to_string())];
+
+    let utf8: ArrayRef = Arc::new(StringArray::from(vec![Some(" DE"), None, Some("Fr ")]));
+    let normalized = normalization.normalize_array(&utf8).unwrap();
+    assert_eq!(normalized.data_type(), &DataType::Utf8);
+    assert_eq!(strings(&normalized), expected);
+
+    let view: ArrayRef = Arc::new(StringViewArray::from(vec![Some(" DE"), None, Some("Fr ")]));
+    let normalized = normalization.normalize_array(&view).unwrap();
+    assert_eq!(normalized.data_type(), &DataType::Utf8View);
+    assert_eq!(strings(&normalized), expected);
+
+    let dictionary: ArrayRef = Arc::new(
+        vec![Some(" DE"), None, Some("Fr ")]
+            .into_iter()
+            .collect::<DictionaryArray<Int32Type>>(),
+    );
+    let normalized = normalization.normalize_array(&dictionary).unwrap();
+    assert_eq!(normalized.data_type(), dictionary.data_type());
+    assert_eq!(strings(&normalized), expected);
+
+    // Other arrays are left as they are
+    let numbers: ArrayRef = Arc::new(arrow::array::Int32Array::from(vec![1]));
+    assert!(Arc::ptr_eq(
+        &normalization.normalize_array(&numbers).unwrap(),
+        &numbers
+    ));
+}
+
+#[test]
+fn adapter_normalizes_the_columns_the_policy_names() {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("country", DataType::Utf8, true),
+        Field::new("city", DataType::Utf8, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        Arc::clone(&schema),
+        vec![
+            Arc::new(StringArray::from(vec![" de", "FR"])),
+            Arc::new(StringArray::from(vec![" Berlin", "Paris"])),
+        ],
+    )
+    .unwrap();
+    let factory = SchemaEvolutionAdapterFactory::new().with_policy(
+        CoercionPolicy::lenient().with_normalization(
+            "country",
+            StringNormalization::new()
+                .with_trim()
+                .with_case(CaseFold::Upper),
+        ),
+    );
+    let adapter = factory.adapter(Arc::clone(&schema), Arc::clone(&schema), None);
+    // Even a file in the table schema is adapted, to normalize it
+    assert_eq!(adapter.file_plan(), FilePlan::Adapt);
+
+    let evaluate = |name: &str| {
+        let expr = adapter.rewrite(col(name, &schema).unwrap()).unwrap();
+        let array = expr
+            .evaluate(&batch)
+            .unwrap()
+            .into_array(batch.num_rows())
+            .unwrap();
+        strings(&array)
+    };
+    assert_eq!(
+        evaluate("country"),
+        [Some("DE".to_string()), Some("FR".to_string())]
+    );
+    assert_eq!(
+        evaluate("city"),
+        [Some(" Berlin".to_string()), Some("Paris".to_string())]
+    );
+}
@@ -0,0 +1,102 @@
+//! Notifications delivered at least once and out of order must reach the
+//! subscriber once per change of a file, newest last.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use datafusion::common::DataFusionError;
+use datafusion::object_store::ObjectMeta;
+use datafusion::object_store::path::Path;
+use futures::{StreamExt, TryStreamExt};
+use schema_evolution::notify::{FileEvent, FileEventDeduplicator, deduplicated};
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn created(path: &str, secs: u64, e_tag: &str) -> FileEvent {
+    FileEvent::Created(ObjectMeta {
+        location: Path::from(path),
+        last_modified: at(secs).into(),
+        size: 100,
+        e_tag: Some(e_tag.to_string()),
+        version: None,
+    })
+}
+
+fn removed(path: &str, secs: u64) -> FileEvent {
+    FileEvent::Removed {
+        location: Path::from(path),
+        at: at(secs),
+    }
+}
+
+#[test]
+fn drops_repeated_events() {
+    let mut deduplicator = FileEventDeduplicator::new();
+    assert!(deduplicator.accept(&created("t/a.parquet", 10, "1")));
+    assert!(!deduplicator.accept(&created("t/a.parquet", 10, "1")));
+    // Other files are tracked apart
+    assert!(deduplicator.accept(&created("t/b.parquet", 10, "1")));
+
+    assert!(deduplicator.accept(&removed("t/b.parquet", 20)));
+    assert!(!deduplicator.accept(&removed("t/b.parquet", 20)));
+}
+
+#[test]
+fn drops_versions_older_than_the_latest() {
+    let mut deduplicator = FileEventDeduplicator::new();
+    assert!(deduplicator.accept(&created("t/a.parquet", 20, "2")));
+    assert!(!deduplicator.accept(&created("t/a.parquet", 10, "1")));
+    assert!(deduplicator.accept(&created("t/a.parquet", 30, "3")));
+
+    // A removal older than the file it would remove is stale too
+    assert!(!deduplicator.accept(&removed("t/a.parquet", 25)));
+}
+
+#[test]
+fn tells_overwrites_within_the_same_time_apart() {
+    let mut deduplicator = FileEventDeduplicator::new();
+    assert!(deduplicator.accept(&created("t/a.parquet", 10, "1")));
+    // Redelivered with the same version
+    assert!(!deduplicator.accept(&created("t/a.parquet", 10, "1")));
+    // Overwritten within the same second
+    assert!(deduplicator.accept(&created("t/a.parquet", 10, "2")));
+    assert!(!deduplicator.accept(&created("t/a.parquet", 10, "2")));
+}
+
+#[test]
+fn recreated_files_are_new() {
+    let mut deduplicator = FileEventDeduplicator::new();
+    assert!(deduplicator.accept(&created("t/a.parquet", 10, "1")));
+    assert!(deduplicator.accept(&removed("t/a.parquet", 20)));
+    assert!(deduplicator.accept(&created("t/a.parquet", 30, "1")));
+
+    // A creation delivered after the removal that followed it is stale
+    let mut deduplicator = FileEventDeduplicator::new();
+    assert!(deduplicator.accept(&removed("t/b.parquet", 20)));
+    assert!(!deduplicator.accept(&created("t/b.parquet", 10, "1")));
+}
+
+#[tokio::test]
+async fn deduplicated_streams_keep_errors() {
+    let events = futures::stream::iter(vec![
+        Ok(created("t/a.parquet", 20, "2")),
+        Ok(created("t/a.parquet", 20, "2")),
+        Err(DataFusionError::Execution("lagged".to_string())),
+        Ok(created("t/a.parquet", 10, "1")),
+        Ok(removed("t/a.parquet", 30)),
+    ])
+    .boxed();
+    let events: Vec<_> = deduplicated(events).collect().await;
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        events[0].as_ref().unwrap(),
+        &created("t/a.parquet", 20, "2")
+    );
+    assert!(events[1].is_err());
+    assert_eq!(events[2].as_ref().unwrap(), &removed("t/a.parquet", 30));
+
+    let events = futures::stream::iter(vec![Ok(removed("t/a.parquet", 30))]).boxed();
+    let events: Vec<_> = deduplicated(events).try_collect().await.unwrap();
+    assert_eq!(events, [removed("t/a.parquet", 30)]);
+}
@@ -0,0 +1,144 @@
+//! `ORDER BY` a column whose files stored different types must order every
+//! row in the domain of the table type, whichever file it came from.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, AsArray, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema};
+use arrow::util::display::array_value_to_string;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use parquet::arrow::ArrowWriter;
+use schema_evolution::{EvolutionOptions, SchemaEvolutionTableProvider};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// `code` is UTF8 in the old file, with a leading zero and a value that is
+/// not a number, and Int64 in the new one.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a_old.parquet"),
+        vec![
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+            ),
+            (
+                "code",
+                Arc::new(StringArray::from(vec!["9", "10", "0400", "x"])),
+            ),
+        ],
+    );
+    write_parquet(
+        &dir.path().join("b_new.parquet"),
+        vec![
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![5, 6, 7, 8])) as ArrayRef,
+            ),
+            ("code", Arc::new(Int64Array::from(vec![5, 40, 300, 1000]))),
+        ],
+    );
+    dir
+}
+
+/// Several partitions, so that the files are sorted apart and merged.
+async fn context(dir: &TempDir, options: EvolutionOptions) -> SessionContext {
+    let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(4));
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        dir.path().to_str().unwrap(),
+        Arc::new(ParquetFormat::default()),
+        options,
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+    ctx
+}
+
+/// The first column of the result, which must be `id`, space-separated.
+async fn ids(ctx: &SessionContext, sql: &str) -> String {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    let ids: Vec<String> = batches
+        .iter()
+        .flat_map(|batch| {
+            let ids = batch.column(0).as_primitive::<Int64Type>();
+            ids.values()
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    ids.join(" ")
+}
+
+async fn code_type(ctx: &SessionContext) -> String {
+    let batches = ctx
+        .sql("SELECT arrow_typeof(code) FROM t LIMIT 1")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    array_value_to_string(batches[0].column(0), 0).unwrap()
+}
+
+#[tokio::test]
+async fn inferred_utf8_orders_as_strings() {
+    let dir = dataset();
+    let ctx = context(&dir, EvolutionOptions::new()).await;
+    // Int64 and UTF8 files unify to UTF8: the numbers are compared as text
+    assert_eq!(code_type(&ctx).await, "Utf8");
+    assert_eq!(
+        ids(&ctx, "SELECT id FROM t ORDER BY code").await,
+        // "0400" "10" "1000" "300" "40" "5" "9" "x"
+        "3 2 8 7 6 5 1 4"
+    );
+    assert_eq!(
+        ids(&ctx, "SELECT id FROM t ORDER BY code DESC").await,
+        "4 1 5 6 7 8 2 3"
+    );
+    assert_eq!(
+        ids(&ctx, "SELECT id FROM t ORDER BY code LIMIT 3").await,
+        "3 2 8"
+    );
+}
+
+#[tokio::test]
+async fn pinned_int64_orders_as_numbers() {
+    let dir = dataset();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("code", DataType::Int64, true),
+    ]));
+    let ctx = context(&dir, EvolutionOptions::new().with_schema(schema)).await;
+    assert_eq!(code_type(&ctx).await, "Int64");
+    // "0400" parses as 400; "x" becomes null, last ascending and first descending
+    assert_eq!(
+        ids(&ctx, "SELECT id FROM t ORDER BY code").await,
+        "5 1 2 6 7 3 8 4"
+    );
+    assert_eq!(
+        ids(&ctx, "SELECT id FROM t ORDER BY code DESC").await,
+        "4 8 3 7 6 2 1 5"
+    );
+    assert_eq!(
+        ids(&ctx, "SELECT id FROM t ORDER BY code NULLS FIRST LIMIT 3").await,
+        "4 5 1"
+    );
+    // Ordering by the text of the numbers is still possible explicitly
+    assert_eq!(
+        ids(&ctx, "SELECT id FROM t ORDER BY CAST(code AS VARCHAR)").await,
+        "2 8 7 6 3 5 1 4"
+    );
+}
@@ -0,0 +1,61 @@
+//! Evolved tables must reach polars in the unified schema with every row.
+#![cfg(feature = "polars")]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array, RecordBatch, StringArray};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use parquet::arrow::ArrowWriter;
+use polars::prelude::DataType;
+use schema_evolution::EvolutionOptions;
+use schema_evolution::polars_interop::collect_lazy_frame;
+
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+#[tokio::test]
+async fn collects_the_unified_table() {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a.parquet"),
+        vec![("id", Arc::new(Int32Array::from(vec![1, 2])))],
+    );
+    write_parquet(
+        &dir.path().join("b.parquet"),
+        vec![
+            ("id", Arc::new(Int64Array::from(vec![3]))),
+            ("name", Arc::new(StringArray::from(vec!["c"]))),
+        ],
+    );
+
+    let frame = collect_lazy_frame(
+        &format!("{}/", dir.path().to_str().unwrap()),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new(),
+    )
+    .await
+    .unwrap()
+    .collect()
+    .unwrap();
+
+    let schema = frame.schema();
+    assert_eq!(schema.get("id"), Some(&DataType::Int64));
+    assert_eq!(schema.get("name"), Some(&DataType::String));
+    let mut rows: Vec<_> = frame
+        .column("id")
+        .unwrap()
+        .i64()
+        .unwrap()
+        .into_iter()
+        .zip(frame.column("name").unwrap().str().unwrap())
+        .map(|(id, name)| (id.unwrap(), name.map(str::to_string)))
+        .collect();
+    rows.sort();
+    assert_eq!(rows, [(1, None), (2, None), (3, Some("c".to_string()))]);
+}
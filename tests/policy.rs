@@ -0,0 +1,199 @@
+//! The coercion policy must decide every column by its own mode, and scans
+//! must name the file a column cannot be coerced in.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::error::DataFusionError;
+use datafusion::prelude::SessionContext;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::policy::{Coercion, CoercionError, CoercionMode, CoercionPolicy};
+use schema_evolution::provider::{EvolutionOptions, SchemaEvolutionTableProvider};
+
+#[test]
+fn resolve_follows_the_mode() {
+    use DataType::*;
+
+    let cases = [
+        (Int64, Int64, [Some(Coercion::Identity); 3]),
+        (Utf8View, Utf8, [Some(Coercion::Widen); 3]),
+        (
+            Dictionary(Box::new(Int32), Box::new(Utf8)),
+            LargeUtf8,
+            [Some(Coercion::Widen); 3],
+        ),
+        (
+            Int32,
+            Int64,
+            [None, Some(Coercion::Widen), Some(Coercion::Widen)],
+        ),
+        (
+            Timestamp(TimeUnit::Millisecond, None),
+            Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            [None, Some(Coercion::Widen), Some(Coercion::Widen)],
+        ),
+        (Int64, Int32, [None, None, Some(Coercion::Lenient)]),
+        (Utf8, Int64, [None, None, Some(Coercion::Lenient)]),
+    ];
+    let modes = [
+        CoercionMode::Strict,
+        CoercionMode::Widening,
+        CoercionMode::Lenient,
+    ];
+    for (file_type, table_type, expected) in cases {
+        for (mode, expected) in modes.into_iter().zip(expected) {
+            let resolved = CoercionPolicy::new(mode)
+                .resolve("c", &file_type, &table_type)
+                .ok();
+            assert_eq!(
+                resolved, expected,
+                "{file_type} to {table_type} under {mode}"
+            );
+        }
+    }
+}
+
+#[test]
+fn struct_fields_may_only_be_dropped_leniently() {
+    let user = |fields: &[(&str, DataType)]| {
+        DataType::Struct(
+            fields
+                .iter()
+                .map(|(name, data_type)| Field::new(*name, data_type.clone(), true))
+                .collect(),
+        )
+    };
+    let old = user(&[("id", DataType::Int32), ("email", DataType::Utf8)]);
+    let dropped = user(&[("id", DataType::Int64)]);
+    let added = user(&[
+        ("id", DataType::Int64),
+        ("email", DataType::Utf8),
+        ("phone", DataType::Utf8),
+    ]);
+
+    // Adding a nullable field and widening the others loses nothing
+    assert_eq!(
+        CoercionPolicy::widening().resolve("user", &old, &added),
+        Ok(Coercion::Widen)
+    );
+    // Dropping `email` loses its values
+    let err = CoercionPolicy::widening()
+        .resolve("user", &old, &dropped)
+        .unwrap_err();
+    assert_eq!(err.mode, CoercionMode::Widening);
+    assert_eq!(
+        CoercionPolicy::lenient().resolve("user", &old, &dropped),
+        Ok(Coercion::Lenient)
+    );
+}
+
+#[test]
+fn columns_override_the_default_mode() {
+    let policy = CoercionPolicy::strict().with_column("amount", CoercionMode::Lenient);
+    assert_eq!(policy.mode_for("amount"), CoercionMode::Lenient);
+    assert_eq!(policy.mode_for("id"), CoercionMode::Strict);
+    assert_eq!(
+        policy.resolve("amount", &DataType::Utf8, &DataType::Int64),
+        Ok(Coercion::Lenient)
+    );
+    assert_eq!(
+        policy.resolve("id", &DataType::Int32, &DataType::Int64),
+        Err(CoercionError {
+            file: None,
+            column: "id".to_string(),
+            file_type: DataType::Int32,
+            table_type: DataType::Int64,
+            mode: CoercionMode::Strict,
+        })
+    );
+
+    // Check reports every conflicting column, not only the first
+    let file = Schema::new(vec![
+        Field::new("id", DataType::Int32, true),
+        Field::new("amount", DataType::Utf8, true),
+        Field::new("name", DataType::Int64, true),
+    ]);
+    let table = Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("amount", DataType::Int64, true),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("missing", DataType::Utf8, true),
+    ]);
+    let conflicts: Vec<_> = policy
+        .check(&file, &table)
+        .into_iter()
+        .map(|err| err.column)
+        .collect();
+    assert_eq!(conflicts, ["id", "name"]);
+}
+
+fn write_parquet(path: &Path, batch: &RecordBatch) {
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// The first error in the chain of `err` that is a [`CoercionError`].
+fn coercion_error(err: &DataFusionError) -> Option<&CoercionError> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+    while let Some(err) = source {
+        if let Some(found) = err.downcast_ref::<CoercionError>() {
+            return Some(found);
+        }
+        source = err.source();
+    }
+    None
+}
+
+#[tokio::test]
+async fn scan_errors_name_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a.parquet"),
+        &RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from(vec![1])) as ArrayRef,
+        )])
+        .unwrap(),
+    );
+    write_parquet(
+        &dir.path().join("b.parquet"),
+        &RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(StringArray::from(vec!["2"])) as ArrayRef,
+        )])
+        .unwrap(),
+    );
+
+    let ctx = SessionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        &format!("{}/", dir.path().to_str().unwrap()),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new()
+            .with_schema(schema)
+            .with_policy(CoercionPolicy::strict()),
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+    let err = ctx
+        .sql("SELECT id FROM t")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap_err();
+
+    let coercion = coercion_error(&err).unwrap_or_else(|| panic!("{err}"));
+    assert_eq!(coercion.column, "id");
+    assert_eq!(coercion.file_type, DataType::Utf8);
+    let file = coercion.file.as_deref().unwrap();
+    assert!(file.ends_with("b.parquet"), "{file}");
+    assert!(err.to_string().contains("b.parquet"), "{err}");
+}
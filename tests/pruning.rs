@@ -0,0 +1,245 @@
+//! Filters on evolved columns must return the same rows whether or not they are
+//! pushed into the scan and whether or not file statistics are collected.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BooleanArray, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::util::pretty::pretty_format_batches;
+use datafusion::common::ScalarValue;
+use datafusion::datasource::MemTable;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use schema_evolution::{
+    CoercionPolicy, EvolutionOptions, FieldMapping, MissingColumnPolicy, NullViolation,
+    SchemaEvolutionTableProvider,
+};
+use tempfile::TempDir;
+
+/// Two rows per row group, so that min/max statistics of the stored type order
+/// differently once cast.
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let props = WriterProperties::builder()
+        .set_max_row_group_size(2)
+        .build();
+    let mut writer = ArrowWriter::try_new(
+        std::fs::File::create(path).unwrap(),
+        batch.schema(),
+        Some(props),
+    )
+    .unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// `code` is Int64 in one file and UTF8 in the other; `id` widens from Int32;
+/// `amount` is UTF8 in the old file, some of it not numeric; `qty` was called
+/// `quantity`; `region` was added with the new file; `score` is required by
+/// the table and its nulls are read as 0.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a_old.parquet"),
+        vec![
+            (
+                "id",
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+            ),
+            (
+                "code",
+                Arc::new(StringArray::from(vec!["A100", "0400", "400", "5"])),
+            ),
+            (
+                "amount",
+                Arc::new(StringArray::from(vec!["10", "9", "x", "100"])),
+            ),
+            ("quantity", Arc::new(Int64Array::from(vec![1, 2, 3, 4]))),
+            ("flag", Arc::new(Int64Array::from(vec![-1, 0, 2, 0]))),
+            (
+                "score",
+                Arc::new(Int64Array::from(vec![Some(1), None, Some(3), None])),
+            ),
+        ],
+    );
+    write_parquet(
+        &dir.path().join("b_new.parquet"),
+        vec![
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![5, 6, 7, 8])) as ArrayRef,
+            ),
+            ("code", Arc::new(Int64Array::from(vec![5, 40, 300, 1000]))),
+            ("amount", Arc::new(Int64Array::from(vec![-1, 8, 50, 5000]))),
+            ("qty", Arc::new(Int64Array::from(vec![5, 6, 7, 8]))),
+            (
+                "region",
+                Arc::new(StringArray::from(vec!["us", "us", "us", "us"])),
+            ),
+            (
+                "flag",
+                Arc::new(BooleanArray::from(vec![true, false, true, false])),
+            ),
+            (
+                "score",
+                Arc::new(Int64Array::from(vec![Some(5), None, Some(7), Some(8)])),
+            ),
+        ],
+    );
+    dir
+}
+
+fn table_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("code", DataType::Utf8, true),
+        Field::new("amount", DataType::Int64, true),
+        Field::new("qty", DataType::Int64, true),
+        Field::new("region", DataType::Utf8, true),
+        Field::new("flag", DataType::Boolean, true),
+        Field::new("score", DataType::Int64, false),
+    ]))
+}
+
+async fn context(dir: &TempDir, collect_stat: bool, pushdown_filters: bool) -> SessionContext {
+    let mut config = SessionConfig::new().with_target_partitions(1);
+    config.options_mut().execution.parquet.pushdown_filters = pushdown_filters;
+    config.options_mut().execution.parquet.reorder_filters = pushdown_filters;
+    let ctx = SessionContext::new_with_config(config);
+
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        dir.path().to_str().unwrap(),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new()
+            .with_schema(table_schema())
+            .with_field_mapping(FieldMapping::new().with_rename("quantity", "qty"))
+            .with_missing_columns(MissingColumnPolicy::new().with_literal("region", "eu"))
+            .with_policy(CoercionPolicy::lenient().with_column_null_violation(
+                "score",
+                NullViolation::Fill(ScalarValue::Int64(Some(0))),
+            ))
+            .with_collect_stat(collect_stat),
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+    ctx
+}
+
+/// The rows of `t` read without any filter, as an in-memory table to evaluate
+/// the expected results on.
+async fn reference(dir: &TempDir) -> SessionContext {
+    let ctx = context(dir, false, false).await;
+    let batches = ctx
+        .sql("SELECT * FROM t")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let reference = SessionContext::new();
+    let table = MemTable::try_new(table_schema(), vec![batches]).unwrap();
+    reference.register_table("t", Arc::new(table)).unwrap();
+    reference
+}
+
+async fn query(ctx: &SessionContext, sql: &str) -> String {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    pretty_format_batches(&batches).unwrap().to_string()
+}
+
+const QUERIES: &[&str] = &[
+    // Int64 files compared as strings: '1000' < '5' although 1000 > 5
+    "SELECT id, code FROM t WHERE code = '40' ORDER BY id",
+    "SELECT id, code FROM t WHERE code > '5' ORDER BY id",
+    "SELECT id, code FROM t WHERE code < '5' ORDER BY id",
+    "SELECT id, code FROM t WHERE code >= '300' AND code <= '400' ORDER BY id",
+    // '0400' and 400 must not be mistaken for '400'
+    "SELECT id, code FROM t WHERE code = '400' ORDER BY id",
+    "SELECT id, code FROM t WHERE code IN ('5', '0400') ORDER BY id",
+    // UTF8 files read as Int64: '10' < '9' as strings, 'x' becomes null
+    "SELECT id, amount FROM t WHERE amount < 10 ORDER BY id",
+    "SELECT id, amount FROM t WHERE amount > 9 ORDER BY id",
+    "SELECT id, amount FROM t WHERE amount BETWEEN 9 AND 50 ORDER BY id",
+    "SELECT id, amount FROM t WHERE amount IS NULL ORDER BY id",
+    // Widened Int32 -> Int64
+    "SELECT id FROM t WHERE id > 3 AND id < 6 ORDER BY id",
+    "SELECT id FROM t WHERE id = 2 OR code = '1000' ORDER BY id",
+    // Integers read as booleans: min -1 and max 0 cast to true and false
+    "SELECT id FROM t WHERE flag = false ORDER BY id",
+    "SELECT id FROM t WHERE NOT flag ORDER BY id",
+    "SELECT id FROM t WHERE flag < true ORDER BY id",
+    // Read under another name, or filled with a default, in the old file
+    "SELECT id, qty FROM t WHERE qty < 3 ORDER BY id",
+    "SELECT id FROM t WHERE qty IS NOT NULL AND id < 3 ORDER BY id",
+    "SELECT id, region FROM t WHERE region = 'eu' ORDER BY id",
+    "SELECT id FROM t WHERE region IS NULL ORDER BY id",
+    // Nulls filled after reading, which the reader must not filter on
+    "SELECT id, score FROM t WHERE score = 0 ORDER BY id",
+    "SELECT id FROM t WHERE score < 4 AND id > 1 ORDER BY id",
+    "SELECT id FROM t WHERE score IS NULL OR code = '5' ORDER BY id",
+    // Answered from statistics when they are exact
+    "SELECT count(*), count(qty), count(region), count(amount) FROM t",
+    "SELECT min(id), max(id), min(code), max(code) FROM t",
+    "SELECT min(amount), max(amount), min(qty), max(qty), min(region), max(region) FROM t",
+];
+
+async fn assert_same_rows(collect_stat: bool, pushdown_filters: bool) {
+    let dir = dataset();
+    let reference = reference(&dir).await;
+    let ctx = context(&dir, collect_stat, pushdown_filters).await;
+    for sql in QUERIES {
+        assert_eq!(
+            query(&ctx, sql).await,
+            query(&reference, sql).await,
+            "{sql} (collect_stat: {collect_stat}, pushdown_filters: {pushdown_filters})"
+        );
+    }
+}
+
+#[tokio::test]
+async fn filters_without_statistics() {
+    assert_same_rows(false, false).await;
+}
+
+#[tokio::test]
+async fn filters_with_statistics() {
+    assert_same_rows(true, false).await;
+}
+
+#[tokio::test]
+async fn pushed_down_filters_without_statistics() {
+    assert_same_rows(false, true).await;
+}
+
+#[tokio::test]
+async fn pushed_down_filters_with_statistics() {
+    assert_same_rows(true, true).await;
+}
+
+#[tokio::test]
+async fn reference_reads_every_row() {
+    let dir = dataset();
+    let reference = reference(&dir).await;
+    let expected = "\
++----+------+--------+-----+--------+-------+-------+
+| id | code | amount | qty | region | flag  | score |
++----+------+--------+-----+--------+-------+-------+
+| 1  | A100 | 10     | 1   | eu     | true  | 1     |
+| 2  | 0400 | 9      | 2   | eu     | false | 0     |
+| 3  | 400  |        | 3   | eu     | true  | 3     |
+| 4  | 5    | 100    | 4   | eu     | false | 0     |
+| 5  | 5    | -1     | 5   | us     | true  | 5     |
+| 6  | 40   | 8      | 6   | us     | false | 0     |
+| 7  | 300  | 50     | 7   | us     | true  | 7     |
+| 8  | 1000 | 5000   | 8   | us     | false | 8     |
++----+------+--------+-----+--------+-------+-------+";
+    assert_eq!(
+        query(&reference, "SELECT * FROM t ORDER BY id").await,
+        expected
+    );
+}
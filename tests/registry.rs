@@ -0,0 +1,160 @@
+//! Bootstrapping must group files by schema, order the versions by their
+//! oldest file and derive the steps between them.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::common::ScalarValue;
+use schema_evolution::discovery::DiscoveredSchemas;
+use schema_evolution::format::FileContext;
+use schema_evolution::manifest::{MigrationStep, VersionRule};
+use schema_evolution::registry::bootstrap_from_inference;
+
+fn old_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, true),
+        Field::new("status", DataType::Int32, true),
+    ]))
+}
+
+fn new_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("region", DataType::Utf8, true),
+    ]))
+}
+
+/// A file modified `seconds` after the epoch, or at an unknown time.
+fn file(path: &str, seconds: Option<u64>) -> FileContext {
+    let file = FileContext::new(path);
+    match seconds {
+        Some(seconds) => file.with_last_modified(UNIX_EPOCH + Duration::from_secs(seconds)),
+        None => file,
+    }
+}
+
+fn paths(rule: &VersionRule) -> Vec<&str> {
+    match rule {
+        VersionRule::Paths(paths) => paths.iter().map(String::as_str).collect(),
+        rule => panic!("expected paths, got {rule:?}"),
+    }
+}
+
+#[test]
+fn groups_files_by_schema_in_the_order_of_their_oldest_file() {
+    // Listed newest first, as discovery with agreement visits them
+    let discovered = DiscoveredSchemas {
+        files: vec![
+            (file("/t/c.parquet", Some(300)), old_schema()),
+            (file("/t/b.parquet", Some(200)), new_schema()),
+            (file("/t/a.parquet", Some(100)), old_schema()),
+        ],
+        skipped: Vec::new(),
+    };
+    let manifest = bootstrap_from_inference(&discovered).unwrap();
+    let versions = manifest.versions();
+    assert_eq!(versions.len(), 2);
+
+    assert_eq!(versions[0].version, 1);
+    assert_eq!(versions[0].schema, old_schema());
+    assert_eq!(paths(&versions[0].files), ["t/a.parquet", "t/c.parquet"]);
+    assert!(versions[0].migration.is_empty());
+
+    assert_eq!(versions[1].version, 2);
+    assert_eq!(paths(&versions[1].files), ["t/b.parquet"]);
+    assert_eq!(
+        versions[1].migration,
+        [
+            MigrationStep::Cast {
+                column: "id".to_string(),
+                data_type: DataType::Int64,
+            },
+            MigrationStep::Drop {
+                column: "status".to_string(),
+            },
+            MigrationStep::Add {
+                column: "region".to_string(),
+                data_type: DataType::Utf8,
+                default: ScalarValue::Utf8(None),
+            },
+        ]
+    );
+    assert_eq!(manifest.schema(), new_schema());
+
+    let plan = manifest
+        .plan(Some(&FileContext::new("t/c.parquet")), &old_schema())
+        .unwrap();
+    assert_eq!(plan.version, 1);
+    assert_eq!(plan.casts, ["id"]);
+    assert_eq!(plan.dropped, HashSet::from(["status".to_string()]));
+}
+
+#[test]
+fn files_without_a_modification_time_come_first() {
+    let discovered = DiscoveredSchemas {
+        files: vec![
+            (file("t/b.parquet", Some(100)), new_schema()),
+            (file("t/a.parquet", None), old_schema()),
+        ],
+        skipped: Vec::new(),
+    };
+    let manifest = bootstrap_from_inference(&discovered).unwrap();
+    assert_eq!(paths(&manifest.versions()[0].files), ["t/a.parquet"]);
+    assert_eq!(manifest.schema(), new_schema());
+}
+
+#[test]
+fn footer_metadata_does_not_make_a_version() {
+    let with_writer = |writer: &str| {
+        Arc::new(
+            old_schema()
+                .as_ref()
+                .clone()
+                .with_metadata(HashMap::from([("writer".to_string(), writer.to_string())])),
+        )
+    };
+    let discovered = DiscoveredSchemas {
+        files: vec![
+            (file("t/a.parquet", Some(100)), with_writer("spark")),
+            (file("t/b.parquet", Some(200)), with_writer("ingest")),
+            (file("t/c.parquet", Some(300)), old_schema()),
+        ],
+        skipped: Vec::new(),
+    };
+    let manifest = bootstrap_from_inference(&discovered).unwrap();
+    let [version] = manifest.versions() else {
+        panic!("expected one version, got {:?}", manifest.versions());
+    };
+    assert_eq!(version.schema, old_schema());
+    assert_eq!(
+        paths(&version.files),
+        ["t/a.parquet", "t/b.parquet", "t/c.parquet"]
+    );
+}
+
+#[test]
+fn needs_every_file_schema() {
+    let skipped = DiscoveredSchemas {
+        files: vec![(file("t/b.parquet", Some(100)), new_schema())],
+        skipped: vec![file("t/a.parquet", Some(50))],
+    };
+    let err = bootstrap_from_inference(&skipped).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Schema discovery skipped 1 files, e.g. t/a.parquet"),
+        "{err}"
+    );
+
+    let empty = DiscoveredSchemas {
+        files: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let err = bootstrap_from_inference(&empty).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("No files to bootstrap the manifest from"),
+        "{err}"
+    );
+}
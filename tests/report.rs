@@ -0,0 +1,106 @@
+//! Trends must count files, new schema versions and adaptations per window of
+//! modification time, and rank the columns schema changes touched.
+
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use schema_evolution::discovery::DiscoveredSchemas;
+use schema_evolution::format::FileContext;
+use schema_evolution::merge::SchemaUnifier;
+use schema_evolution::report::{TrendWindow, trend};
+
+const DAY: u64 = 86_400;
+
+fn schema(fields: Vec<(&str, DataType)>) -> SchemaRef {
+    Arc::new(Schema::new(
+        fields
+            .into_iter()
+            .map(|(name, data_type)| Field::new(name, data_type, true))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Two files of the first schema on day 0, the second and third schema on
+/// day 2, and an undated file.
+fn discovered() -> DiscoveredSchemas {
+    let v1 = schema(vec![("id", DataType::Int32)]);
+    let v2 = schema(vec![("id", DataType::Int64), ("region", DataType::Utf8)]);
+    let v3 = schema(vec![("id", DataType::Int64)]);
+    let file = |path: &str, secs: u64| {
+        FileContext::new(path).with_last_modified(UNIX_EPOCH + Duration::from_secs(secs))
+    };
+    DiscoveredSchemas {
+        files: vec![
+            (file("t/d.parquet", 2 * DAY + 2), v3),
+            (file("t/a.parquet", 10), Arc::clone(&v1)),
+            (file("t/c.parquet", 2 * DAY + 1), v2),
+            (file("t/b.parquet", 500), Arc::clone(&v1)),
+            (FileContext::new("t/e.parquet"), v1),
+        ],
+        skipped: Vec::new(),
+    }
+}
+
+#[test]
+fn summarizes_each_window_with_files() {
+    let discovered = discovered();
+    let unified = SchemaUnifier::new().unify(
+        discovered
+            .files
+            .iter()
+            .map(|(file, schema)| (file.path.clone(), Arc::clone(schema)))
+            .collect(),
+    );
+    let summary = trend(&discovered, &unified.report, Duration::from_secs(DAY));
+
+    assert_eq!(summary.undated, 1);
+    // Day 1 had no files, and the widened `id` is cast in the older files
+    assert_eq!(
+        summary.windows,
+        [
+            TrendWindow {
+                start: UNIX_EPOCH,
+                files: 2,
+                new_versions: 1,
+                adapted_files: 2,
+                casts: 2,
+            },
+            TrendWindow {
+                start: UNIX_EPOCH + Duration::from_secs(2 * DAY),
+                files: 2,
+                new_versions: 2,
+                adapted_files: 1,
+                casts: 0,
+            },
+        ]
+    );
+    // `region` was added and removed again, `id` widened once
+    assert_eq!(
+        summary.churn,
+        [("region".to_string(), 2), ("id".to_string(), 1)]
+    );
+    assert_eq!(
+        summary.to_string(),
+        "1-day windows (1 files without a modification time left out)\n  \
+         1970-01-01: 2 files, 1 new schema versions, 2 adapted, 2 casts\n  \
+         1970-01-03: 2 files, 2 new schema versions, 1 adapted, 0 casts\n\
+         \nmost changed columns:\n  region: 2\n  id: 1\n"
+    );
+}
+
+#[test]
+fn windows_are_aligned_to_the_epoch() {
+    let discovered = discovered();
+    let unified = SchemaUnifier::new().unify(Vec::new());
+    let summary = trend(&discovered, &unified.report, Duration::from_secs(3 * DAY));
+    let starts: Vec<_> = summary.windows.iter().map(|window| window.start).collect();
+    assert_eq!(starts, [UNIX_EPOCH]);
+    assert_eq!(summary.windows[0].files, 4);
+    // Without a report no file counts as adapted
+    assert_eq!(summary.windows[0].adapted_files, 0);
+    assert!(summary.to_string().starts_with("3-day windows"));
+
+    let hourly = trend(&discovered, &unified.report, Duration::from_secs(3600));
+    assert!(hourly.to_string().starts_with("3600s windows"), "{hourly}");
+}
@@ -0,0 +1,161 @@
+//! Rewritten files must read back in the target schema with the same values,
+//! each under the relative path of its old file, and a dry run must check
+//! every file without writing any.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use arrow::array::{
+    ArrayRef, AsArray, Float64Array, Int32Array, Int64Array, RecordBatch, StringArray,
+};
+use arrow::datatypes::{DataType, Field, Float64Type, Int64Type, Schema, SchemaRef};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::{ParquetReadOptions, SessionContext, col};
+use parquet::arrow::ArrowWriter;
+use schema_evolution::{RewriteOptions, rewrite_to_schema};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// A file of each version: the first stores `id` as Int32 and lacks `score`,
+/// the second stores the columns in another order.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("src");
+    write_parquet(
+        &src.join("v1/a.parquet"),
+        vec![
+            ("id", Arc::new(Int32Array::from(vec![1, 2]))),
+            ("name", Arc::new(StringArray::from(vec!["a", "b"]))),
+        ],
+    );
+    write_parquet(
+        &src.join("v2/b.parquet"),
+        vec![
+            ("score", Arc::new(Float64Array::from(vec![0.5]))),
+            ("name", Arc::new(StringArray::from(vec!["c"]))),
+            ("id", Arc::new(Int64Array::from(vec![3]))),
+        ],
+    );
+    dir
+}
+
+fn target_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("score", DataType::Float64, true),
+    ]))
+}
+
+fn url(path: &Path) -> String {
+    format!("{}/", path.to_str().unwrap())
+}
+
+#[tokio::test]
+async fn rewritten_files_read_back_in_the_target_schema() {
+    let dir = dataset();
+    let dst = dir.path().join("dst");
+    let ctx = SessionContext::new();
+    let summary = rewrite_to_schema(
+        &ctx,
+        url(&dir.path().join("src")),
+        url(&dst),
+        target_schema(),
+        RewriteOptions::new(Arc::new(ParquetFormat::default())),
+    )
+    .await
+    .unwrap();
+    assert_eq!(summary.rows(), 3);
+    let rows: Vec<_> = summary.files.iter().map(|file| file.rows).collect();
+    assert_eq!(rows, [Some(2), Some(1)]);
+
+    for relative in ["v1/a.parquet", "v2/b.parquet"] {
+        let path = dst.join(relative);
+        let df = ctx
+            .read_parquet(path.to_str().unwrap(), ParquetReadOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            df.schema().as_arrow().fields(),
+            target_schema().fields(),
+            "{relative}"
+        );
+    }
+
+    let batches = ctx
+        .read_parquet(url(&dst), ParquetReadOptions::default())
+        .await
+        .unwrap()
+        .sort_by(vec![col("id")])
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    let ids = batch
+        .column(0)
+        .as_primitive::<Int64Type>()
+        .values()
+        .to_vec();
+    assert_eq!(ids, [1, 2, 3]);
+    let names: Vec<_> = batch
+        .column(1)
+        .as_string::<i32>()
+        .iter()
+        .flatten()
+        .collect();
+    assert_eq!(names, ["a", "b", "c"]);
+    let scores: Vec<_> = batch
+        .column(2)
+        .as_primitive::<Float64Type>()
+        .iter()
+        .collect();
+    assert_eq!(scores, [None, None, Some(0.5)]);
+}
+
+#[tokio::test]
+async fn dry_runs_check_every_file_and_write_none() {
+    let dir = dataset();
+    let dst = dir.path().join("dst");
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let reported = Arc::clone(&progress);
+    let summary = rewrite_to_schema(
+        &SessionContext::new(),
+        url(&dir.path().join("src")),
+        url(&dst),
+        target_schema(),
+        RewriteOptions::new(Arc::new(ParquetFormat::default()))
+            .with_dry_run(true)
+            .with_progress(move |progress| {
+                reported
+                    .lock()
+                    .unwrap()
+                    .push((progress.done, progress.total));
+            }),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(summary.files.len(), 2);
+    assert!(summary.files.iter().all(|file| file.rows.is_none()));
+    assert_eq!(summary.rows(), 0);
+    for (file, relative) in summary
+        .files
+        .iter()
+        .zip(["dst/v1/a.parquet", "dst/v2/b.parquet"])
+    {
+        assert!(file.destination.ends_with(relative), "{}", file.destination);
+    }
+    // The old schemas are kept for the report
+    assert_eq!(summary.files[0].file_schema.fields().len(), 2);
+    assert_eq!(*progress.lock().unwrap(), [(1, 2), (2, 2)]);
+    assert!(!dst.exists());
+}
@@ -0,0 +1,214 @@
+//! Row ids must stay the same when a file is read again, in whole or
+//! filtered, and differ between files and between versions of a file; scans
+//! must therefore not split, filter inside or reverse the files.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, AsArray, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema, SchemaRef};
+use datafusion::config::ConfigOptions;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::PartitionedFile;
+use datafusion::datasource::physical_plan::{FileScanConfigBuilder, FileSource};
+use datafusion::datasource::table_schema::TableSchema;
+use datafusion::execution::object_store::ObjectStoreUrl;
+use datafusion::logical_expr::Operator;
+use datafusion::object_store::ObjectMeta;
+use datafusion::object_store::path::Path as ObjectPath;
+use datafusion::physical_expr::expressions::{BinaryExpr, col, lit};
+use datafusion::physical_expr::{EquivalenceProperties, PhysicalExpr, PhysicalSortExpr};
+use datafusion::physical_plan::filter_pushdown::PushedDown;
+use datafusion::physical_plan::sort_pushdown::SortOrderPushdownResult;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::format::EvolvingFormat;
+use schema_evolution::row_id::{FileOrdinalRowIds, ROW_ID_COLUMN, RowIdGenerator};
+use schema_evolution::{EvolutionOptions, SchemaEvolutionTableProvider};
+use tempfile::TempDir;
+
+/// Writes `ids` in row groups of two rows.
+fn write_parquet(path: &Path, ids: Vec<i64>) {
+    let batch =
+        RecordBatch::try_from_iter([("id", Arc::new(Int64Array::from(ids)) as ArrayRef)]).unwrap();
+    let properties = WriterProperties::builder()
+        .set_max_row_group_size(2)
+        .build();
+    let mut writer = ArrowWriter::try_new(
+        std::fs::File::create(path).unwrap(),
+        batch.schema(),
+        Some(properties),
+    )
+    .unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(&dir.path().join("a.parquet"), (0..6).collect());
+    write_parquet(&dir.path().join("b.parquet"), vec![10, 11]);
+    dir
+}
+
+/// A session that would split files and push filters into the reader.
+async fn context(dir: &TempDir) -> SessionContext {
+    let config = SessionConfig::new()
+        .with_target_partitions(4)
+        .with_repartition_file_min_size(1)
+        .set_bool("datafusion.execution.parquet.pushdown_filters", true);
+    let ctx = SessionContext::new_with_config(config);
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        format!("{}/", dir.path().to_str().unwrap()),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new().with_row_ids(Arc::new(FileOrdinalRowIds::new())),
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+    ctx
+}
+
+/// The row id of each `id` the query returns.
+async fn row_ids(ctx: &SessionContext, sql: &str) -> HashMap<i64, [u8; 16]> {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    let mut ids = HashMap::new();
+    for batch in batches {
+        let id = batch.column(0).as_primitive::<Int64Type>();
+        let row_id = batch.column(1).as_fixed_size_binary();
+        for row in 0..batch.num_rows() {
+            ids.insert(id.value(row), row_id.value(row).try_into().unwrap());
+        }
+    }
+    ids
+}
+
+fn ordinal(row_id: &[u8; 16]) -> u64 {
+    u64::from_be_bytes(row_id[8..].try_into().unwrap())
+}
+
+#[tokio::test]
+async fn ids_are_stable_across_reads() {
+    let dir = dataset();
+    let ctx = context(&dir).await;
+    let all = row_ids(&ctx, "SELECT id, _row_id FROM t").await;
+    assert_eq!(all.len(), 8);
+    assert_eq!(row_ids(&ctx, "SELECT id, _row_id FROM t").await, all);
+
+    // Ordinals count the rows of the file, across its row groups
+    for id in 0..6 {
+        assert_eq!(ordinal(&all[&id]), id as u64);
+    }
+    assert_eq!(ordinal(&all[&11]), 1);
+
+    // Filtered, the remaining rows keep their ids
+    let filtered = row_ids(&ctx, "SELECT id, _row_id FROM t WHERE id % 2 = 1").await;
+    assert_eq!(filtered.len(), 4);
+    for (id, row_id) in &filtered {
+        assert_eq!(row_id, &all[id], "id {id}");
+    }
+
+    // Files start their ids with different fingerprints
+    assert_eq!(all[&0][..8], all[&5][..8]);
+    assert_ne!(all[&0][..8], all[&10][..8]);
+    let unique: HashSet<_> = all.values().collect();
+    assert_eq!(unique.len(), 8);
+}
+
+#[tokio::test]
+async fn rewriting_a_file_changes_its_ids() {
+    let dir = dataset();
+    let before = row_ids(&context(&dir).await, "SELECT id, _row_id FROM t").await;
+
+    std::fs::remove_file(dir.path().join("b.parquet")).unwrap();
+    write_parquet(&dir.path().join("b.parquet"), vec![10, 11, 12]);
+    let after = row_ids(&context(&dir).await, "SELECT id, _row_id FROM t").await;
+    assert_eq!(after[&0], before[&0]);
+    assert_ne!(after[&10], before[&10]);
+    assert_eq!(ordinal(&after[&10]), 0);
+}
+
+fn meta(path: &str, e_tag: &str) -> ObjectMeta {
+    ObjectMeta {
+        location: ObjectPath::from(path),
+        last_modified: Default::default(),
+        size: 100,
+        e_tag: Some(e_tag.to_string()),
+        version: None,
+    }
+}
+
+#[test]
+fn ids_depend_on_the_path_the_version_and_the_offset() {
+    let generator = FileOrdinalRowIds::new();
+    let ids = |file: &ObjectMeta, offset| {
+        let ids = generator.generate(file, offset, 2).unwrap();
+        let ids = ids.as_fixed_size_binary();
+        [ids.value(0).to_vec(), ids.value(1).to_vec()]
+    };
+    let a = meta("t/a.parquet", "1");
+    assert_eq!(ids(&a, 0), ids(&a, 0));
+    assert_eq!(ids(&a, 0)[1], ids(&a, 1)[0]);
+    assert_ne!(ids(&a, 0), ids(&meta("t/b.parquet", "1"), 0));
+    assert_ne!(ids(&a, 0), ids(&meta("t/a.parquet", "2"), 0));
+}
+
+fn table_schema(row_ids: bool) -> SchemaRef {
+    let mut fields = vec![Field::new("id", DataType::Int64, true)];
+    if row_ids {
+        fields.push(Field::new(
+            ROW_ID_COLUMN,
+            DataType::FixedSizeBinary(16),
+            false,
+        ));
+    }
+    Arc::new(Schema::new(fields))
+}
+
+/// The file source of a table with or without the row-id column.
+fn file_source(row_ids: bool) -> Arc<dyn FileSource> {
+    let factory = SchemaEvolutionAdapterFactory::new().with_row_ids(Arc::new(FileOrdinalRowIds));
+    EvolvingFormat::new(Arc::new(ParquetFormat::default()))
+        .with_adapter_factory(factory)
+        .file_source(TableSchema::from_file_schema(table_schema(row_ids)))
+}
+
+#[test]
+fn files_are_read_whole_and_in_order_with_row_ids() {
+    let mut config = ConfigOptions::default();
+    config.execution.parquet.pushdown_filters = true;
+    let filter: Arc<dyn PhysicalExpr> = Arc::new(BinaryExpr::new(
+        col("id", &table_schema(true)).unwrap(),
+        Operator::Gt,
+        lit(5i64),
+    ));
+
+    for row_ids in [false, true] {
+        let source = file_source(row_ids);
+        let scan =
+            FileScanConfigBuilder::new(ObjectStoreUrl::local_filesystem(), Arc::clone(&source))
+                .with_file(PartitionedFile::new("t/a.parquet", 1 << 20))
+                .build();
+        let repartitioned = source.repartitioned(4, 1, None, &scan).unwrap();
+        assert_eq!(repartitioned.is_none(), row_ids);
+
+        let pushdown = source
+            .try_pushdown_filters(vec![Arc::clone(&filter)], &config)
+            .unwrap();
+        let pushed = matches!(pushdown.filters[..], [PushedDown::Yes]);
+        assert_eq!(pushed, !row_ids);
+    }
+
+    let order = [PhysicalSortExpr::new_default(
+        col("id", &table_schema(true)).unwrap(),
+    )];
+    let reversed = file_source(true)
+        .try_reverse_output(&order, &EquivalenceProperties::new(table_schema(true)))
+        .unwrap();
+    assert!(matches!(reversed, SortOrderPushdownResult::Unsupported));
+}
@@ -0,0 +1,100 @@
+//! A single file must be read in the target schema, in the file's row order,
+//! with the columns it lacks as nulls.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, AsArray, Int32Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema, SchemaRef};
+use datafusion::prelude::SessionContext;
+use futures::TryStreamExt;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use schema_evolution::adapt_file_to_schema;
+
+/// Writes the rows in row groups of one row each.
+fn write_parquet(path: &Path, batch: &RecordBatch) {
+    let properties = WriterProperties::builder()
+        .set_max_row_group_size(1)
+        .build();
+    let mut writer = ArrowWriter::try_new(
+        std::fs::File::create(path).unwrap(),
+        batch.schema(),
+        Some(properties),
+    )
+    .unwrap();
+    writer.write(batch).unwrap();
+    writer.close().unwrap();
+}
+
+fn target_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("score", DataType::Float64, true),
+    ]))
+}
+
+#[tokio::test]
+async fn reads_reordered_and_widened_columns_in_file_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("old.parquet");
+    // `name` first, `id` as Int32, no `score`, ids not sorted
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "name",
+            Arc::new(StringArray::from(vec!["c", "a", "d", "b"])) as ArrayRef,
+        ),
+        ("id", Arc::new(Int32Array::from(vec![3, 1, 4, 2]))),
+    ])
+    .unwrap();
+    write_parquet(&path, &batch);
+
+    let stream = adapt_file_to_schema(
+        &SessionContext::new(),
+        path.to_str().unwrap(),
+        target_schema(),
+    )
+    .await
+    .unwrap();
+    let batches: Vec<RecordBatch> = stream.try_collect().await.unwrap();
+    let batch = arrow::compute::concat_batches(&target_schema(), &batches).unwrap();
+
+    assert_eq!(batch.schema(), target_schema());
+    let ids = batch
+        .column(0)
+        .as_primitive::<Int64Type>()
+        .values()
+        .to_vec();
+    assert_eq!(ids, [3, 1, 4, 2]);
+    let names: Vec<_> = batch
+        .column(1)
+        .as_string::<i32>()
+        .iter()
+        .flatten()
+        .collect();
+    assert_eq!(names, ["c", "a", "d", "b"]);
+    assert_eq!(batch.column(2).null_count(), 4);
+}
+
+#[tokio::test]
+async fn unknown_extensions_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("events.csv");
+    std::fs::write(&path, "id\n1\n").unwrap();
+
+    let err = adapt_file_to_schema(
+        &SessionContext::new(),
+        path.to_str().unwrap(),
+        target_schema(),
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(
+        err.to_string()
+            .contains("expected a .parquet or .vortex file"),
+        "{err}"
+    );
+    assert!(err.to_string().contains("events.csv"), "{err}");
+}
@@ -0,0 +1,227 @@
+//! The table schema must come from the first source of the chain that has
+//! one, only inference may read the footers, and the report must name the
+//! source taken.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use datafusion::catalog::TableProvider;
+use datafusion::common::Result;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::prelude::SessionContext;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::manifest::EvolutionManifest;
+use schema_evolution::{
+    EvolutionOptions, SchemaEvolutionTableProvider, SchemaRegistry, SchemaResolution, SchemaSource,
+};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// Inferred, the table is `id, name`.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a.parquet"),
+        vec![("id", Arc::new(Int32Array::from(vec![1])))],
+    );
+    write_parquet(
+        &dir.path().join("b.parquet"),
+        vec![
+            ("id", Arc::new(Int64Array::from(vec![2]))),
+            ("name", Arc::new(StringArray::from(vec!["b"]))),
+        ],
+    );
+    dir
+}
+
+/// A schema telling which source it came from by its second column.
+fn schema(source: &str) -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new(source, DataType::Utf8, true),
+    ]))
+}
+
+fn manifest() -> Arc<EvolutionManifest> {
+    let manifest = EvolutionManifest::from_json(
+        r#"{"versions": [
+          {"version": 1, "files": {"path": "*"},
+           "columns": [{"name": "id", "type": "Int64"}, {"name": "declared", "type": "Utf8"}]}
+        ]}"#,
+    )
+    .unwrap();
+    Arc::new(manifest)
+}
+
+/// A registry with at most one table's schema.
+#[derive(Debug)]
+struct Registry(Option<SchemaRef>);
+
+#[async_trait]
+impl SchemaRegistry for Registry {
+    async fn latest_schema(&self, _table_url: &ListingTableUrl) -> Result<Option<SchemaRef>> {
+        Ok(self.0.clone())
+    }
+}
+
+fn registry(schema: Option<SchemaRef>) -> Arc<dyn SchemaRegistry> {
+    Arc::new(Registry(schema))
+}
+
+async fn provider(
+    dir: &TempDir,
+    options: EvolutionOptions,
+) -> Result<SchemaEvolutionTableProvider> {
+    SchemaEvolutionTableProvider::try_new(
+        &SessionContext::new().state(),
+        format!("{}/", dir.path().to_str().unwrap()),
+        Arc::new(ParquetFormat::default()),
+        options,
+    )
+    .await
+}
+
+/// The name of the column telling the source apart.
+fn source_column(provider: &SchemaEvolutionTableProvider) -> String {
+    provider.schema().field(1).name().clone()
+}
+
+#[tokio::test]
+async fn explicit_schema_comes_first_and_skips_inference() {
+    let dir = dataset();
+    let options = EvolutionOptions::new()
+        .with_schema(schema("explicit"))
+        .with_registry(registry(Some(schema("registered"))))
+        .with_manifest(manifest());
+    let provider = provider(&dir, options).await.unwrap();
+    assert_eq!(source_column(&provider), "explicit");
+    assert_eq!(
+        provider.schema_resolution(),
+        &SchemaResolution {
+            source: SchemaSource::Explicit,
+            skipped: Vec::new(),
+        }
+    );
+    // No footer was read
+    let report = provider.report();
+    assert!(report.files.is_empty());
+    assert_eq!(
+        report.to_string(),
+        "table schema from the explicit schema\n"
+    );
+}
+
+#[tokio::test]
+async fn registry_comes_before_the_manifest() {
+    let dir = dataset();
+    let options = EvolutionOptions::new()
+        .with_registry(registry(Some(schema("registered"))))
+        .with_manifest(manifest());
+    let provider = provider(&dir, options).await.unwrap();
+    assert_eq!(source_column(&provider), "registered");
+    assert_eq!(
+        provider.schema_resolution().skipped,
+        [SchemaSource::Explicit]
+    );
+    assert!(provider.report().files.is_empty());
+}
+
+#[tokio::test]
+async fn registry_without_the_table_falls_back_to_the_manifest() {
+    let dir = dataset();
+    let options = EvolutionOptions::new()
+        .with_registry(registry(None))
+        .with_manifest(manifest());
+    let provider = provider(&dir, options).await.unwrap();
+    assert_eq!(source_column(&provider), "declared");
+    let resolution = provider.schema_resolution();
+    assert_eq!(resolution.source, SchemaSource::Manifest);
+    assert_eq!(
+        resolution.skipped,
+        [SchemaSource::Explicit, SchemaSource::Registry]
+    );
+    assert_eq!(
+        resolution.to_string(),
+        "table schema from the manifest (skipped: explicit schema, registry)"
+    );
+}
+
+#[tokio::test]
+async fn inference_comes_last() {
+    let dir = dataset();
+    let provider = provider(&dir, EvolutionOptions::new()).await.unwrap();
+    assert_eq!(source_column(&provider), "name");
+    let resolution = provider.schema_resolution();
+    assert_eq!(resolution.source, SchemaSource::Inference);
+    assert_eq!(
+        resolution.skipped,
+        [
+            SchemaSource::Explicit,
+            SchemaSource::Registry,
+            SchemaSource::Manifest
+        ]
+    );
+    // The widened `id` of the first file is reported after the source
+    let report = provider.report().to_string();
+    assert!(
+        report.starts_with(
+            "table schema from the inference \
+             (skipped: explicit schema, registry, manifest)\n"
+        ),
+        "{report}"
+    );
+    assert_eq!(provider.report().files.len(), 2);
+}
+
+#[tokio::test]
+async fn chains_follow_the_configured_order() {
+    let dir = dataset();
+    let options = EvolutionOptions::new()
+        .with_schema(schema("explicit"))
+        .with_manifest(manifest())
+        .with_schema_sources(vec![SchemaSource::Manifest, SchemaSource::Explicit]);
+    let manifest_first = provider(&dir, options).await.unwrap();
+    assert_eq!(source_column(&manifest_first), "declared");
+
+    // Sources left out of the chain are not used even when configured
+    let options = EvolutionOptions::new()
+        .with_schema(schema("explicit"))
+        .with_schema_sources(vec![SchemaSource::Registry, SchemaSource::Inference]);
+    let inferred = provider(&dir, options).await.unwrap();
+    assert_eq!(source_column(&inferred), "name");
+    assert_eq!(
+        inferred.schema_resolution().skipped,
+        [SchemaSource::Registry]
+    );
+}
+
+#[tokio::test]
+async fn fails_when_no_source_is_configured() {
+    let dir = dataset();
+    let options = EvolutionOptions::new()
+        .with_registry(registry(None))
+        .with_schema_sources(vec![
+            SchemaSource::Explicit,
+            SchemaSource::Registry,
+            SchemaSource::Manifest,
+        ]);
+    let err = provider(&dir, options).await.unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "No source of the table schema is configured \
+             (tried: explicit schema, registry, manifest)"
+        ),
+        "{err}"
+    );
+}
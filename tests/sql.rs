@@ -0,0 +1,126 @@
+//! Historical column names in user SQL must be replaced with the current ones,
+//! without changing what unambiguous names refer to or what columns are
+//! called in the output.
+
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, AsArray, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::DataType;
+use datafusion::prelude::SessionContext;
+use schema_evolution::mapping::FieldMapping;
+use schema_evolution::sql::ColumnAliases;
+
+/// `events` renamed `product_code` to `sku` and then to `code`, and `state`
+/// used to be `status`; `orders` still has a `status` of its own.
+fn context() -> SessionContext {
+    let ctx = SessionContext::new();
+    let events = RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+        ("code", Arc::new(StringArray::from(vec!["A1", "B2"]))),
+        ("state", Arc::new(StringArray::from(vec!["new", "done"]))),
+    ])
+    .unwrap();
+    let orders = RecordBatch::try_from_iter(vec![
+        ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+        ("status", Arc::new(StringArray::from(vec!["paid", "open"]))),
+        ("ref", Arc::new(StringArray::from(vec!["o-1", "o-2"]))),
+    ])
+    .unwrap();
+    ctx.register_batch("events", events).unwrap();
+    ctx.register_batch("orders", orders).unwrap();
+    ctx
+}
+
+fn aliases() -> ColumnAliases {
+    ColumnAliases::new().with_table(
+        "events",
+        &FieldMapping::new()
+            .with_rename("product_code", "sku")
+            .with_rename("sku", "code")
+            .with_rename("status", "state"),
+    )
+}
+
+/// The output column names and the first column's values as strings.
+async fn run(
+    aliases: &ColumnAliases,
+    ctx: &SessionContext,
+    sql: &str,
+) -> (Vec<String>, Vec<String>) {
+    let df = aliases.sql(ctx, sql).await.unwrap();
+    let names = df
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().clone())
+        .collect();
+    let batches = df.collect().await.unwrap();
+    let values = batches
+        .iter()
+        .flat_map(|batch| {
+            let column = arrow::compute::cast(batch.column(0), &DataType::Utf8).unwrap();
+            column
+                .as_string::<i32>()
+                .iter()
+                .map(|value| value.unwrap_or_default().to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    (names, values)
+}
+
+#[tokio::test]
+async fn chained_renames_keep_the_name_the_query_used() {
+    let ctx = context();
+    let (names, values) = run(
+        &aliases(),
+        &ctx,
+        "SELECT product_code, id FROM events WHERE product_code = 'A1'",
+    )
+    .await;
+    assert_eq!(names, ["product_code", "id"]);
+    assert_eq!(values, ["A1"]);
+
+    let (names, values) = run(&aliases(), &ctx, "SELECT e.sku FROM events e ORDER BY e.id").await;
+    assert_eq!(names, ["sku"]);
+    assert_eq!(values, ["A1", "B2"]);
+
+    // Current names are untouched
+    let (names, _) = run(&aliases(), &ctx, "SELECT code FROM events").await;
+    assert_eq!(names, ["code"]);
+}
+
+#[tokio::test]
+async fn bare_names_another_table_has_are_left_alone() {
+    let ctx = context();
+    let sql = "SELECT status FROM events JOIN orders ON events.id = orders.id ORDER BY events.id";
+    let (_, values) = run(&aliases(), &ctx, sql).await;
+    assert_eq!(values, ["paid", "open"]);
+
+    // Qualified, the historical name is still the renamed column
+    let sql = "SELECT events.status FROM events JOIN orders ON events.id = orders.id \
+               ORDER BY events.id";
+    let (names, values) = run(&aliases(), &ctx, sql).await;
+    assert_eq!(names, ["status"]);
+    assert_eq!(values, ["new", "done"]);
+
+    // Alone, the bare name is the renamed column
+    let (_, values) = run(&aliases(), &ctx, "SELECT status FROM events ORDER BY id").await;
+    assert_eq!(values, ["new", "done"]);
+}
+
+#[tokio::test]
+async fn names_renamed_differently_per_table_must_be_qualified() {
+    let ctx = context();
+    let aliases = aliases().with_table("orders", &FieldMapping::new().with_rename("sku", "ref"));
+
+    let sql = "SELECT o.sku AS reference, e.sku AS product FROM events e \
+               JOIN orders o ON e.id = o.id ORDER BY e.id";
+    let (names, values) = run(&aliases, &ctx, sql).await;
+    assert_eq!(names, ["reference", "product"]);
+    assert_eq!(values, ["o-1", "o-2"]);
+
+    let sql = "SELECT sku FROM events JOIN orders ON events.id = orders.id";
+    let err = aliases.sql(&ctx, sql).await.unwrap_err();
+    assert!(err.to_string().contains("sku"), "{err}");
+}
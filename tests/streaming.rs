@@ -0,0 +1,107 @@
+//! A running stream must pick up files written after it started, adapted to
+//! the table schema, and read a file again when it is overwritten.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow::array::{ArrayRef, AsArray, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema, SchemaRef};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::execution::SendableRecordBatchStream;
+use datafusion::prelude::SessionContext;
+use futures::StreamExt;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::streaming::EvolvingStream;
+
+/// Writes next to `path` and renames, so the stream never lists a half
+/// written file.
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let partial = path.with_extension("tmp");
+    let mut writer = ArrowWriter::try_new(
+        std::fs::File::create(&partial).unwrap(),
+        batch.schema(),
+        None,
+    )
+    .unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+    std::fs::rename(partial, path).unwrap();
+}
+
+fn table_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("region", DataType::Utf8, true),
+    ]))
+}
+
+async fn next_batch(stream: &mut SendableRecordBatchStream) -> RecordBatch {
+    tokio::time::timeout(Duration::from_secs(10), stream.next())
+        .await
+        .expect("no file was read in time")
+        .unwrap()
+        .unwrap()
+}
+
+fn ids(batch: &RecordBatch) -> Vec<i64> {
+    batch
+        .column(0)
+        .as_primitive::<Int64Type>()
+        .values()
+        .to_vec()
+}
+
+#[tokio::test]
+async fn reads_drifted_files_as_they_appear() {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a.parquet"),
+        vec![("id", Arc::new(Int32Array::from(vec![1, 2])))],
+    );
+
+    let url = ListingTableUrl::parse(format!("{}/", dir.path().to_str().unwrap())).unwrap();
+    let table = EvolvingStream::new(url, Arc::new(ParquetFormat::default()), table_schema())
+        .with_poll_interval(Duration::from_millis(50))
+        .into_table()
+        .unwrap();
+    let ctx = SessionContext::new();
+    ctx.register_table("events", Arc::new(table)).unwrap();
+    let mut stream = ctx
+        .sql("SELECT id, region FROM events")
+        .await
+        .unwrap()
+        .execute_stream()
+        .await
+        .unwrap();
+
+    // The file present at start: `id` widened, `region` missing
+    let batch = next_batch(&mut stream).await;
+    assert_eq!(batch.schema(), table_schema());
+    assert_eq!(ids(&batch), [1, 2]);
+    assert_eq!(batch.column(1).null_count(), 2);
+
+    // A file written later, with the columns reordered and `id` as strings
+    write_parquet(
+        &dir.path().join("b.parquet"),
+        vec![
+            ("region", Arc::new(StringArray::from(vec!["eu"]))),
+            ("id", Arc::new(StringArray::from(vec!["3"]))),
+        ],
+    );
+    let batch = next_batch(&mut stream).await;
+    assert_eq!(batch.schema(), table_schema());
+    assert_eq!(ids(&batch), [3]);
+    assert_eq!(batch.column(1).as_string::<i32>().value(0), "eu");
+
+    // Overwritten in place, the file is read again in whole
+    write_parquet(
+        &dir.path().join("a.parquet"),
+        vec![("id", Arc::new(Int64Array::from(vec![4, 5, 6])))],
+    );
+    let batch = next_batch(&mut stream).await;
+    assert_eq!(batch.schema(), table_schema());
+    assert_eq!(ids(&batch), [4, 5, 6]);
+}
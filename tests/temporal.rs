@@ -0,0 +1,179 @@
+//! Temporal casts must keep instants, round towards the past when coarsening,
+//! and never wrap around when a finer unit runs out of range.
+
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, RecordBatch, TimestampMillisecondArray, TimestampSecondArray,
+};
+use arrow::compute::CastOptions;
+use arrow::datatypes::{DataType, Field, TimeUnit, TimestampNanosecondType, TimestampSecondType};
+use datafusion::common::format::DEFAULT_CAST_OPTIONS;
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_expr::expressions::col;
+use schema_evolution::merge::common_type;
+use schema_evolution::policy::CoercionMode;
+use schema_evolution::temporal::{NaiveTimestamps, TemporalCastExpr};
+
+fn cast(
+    array: ArrayRef,
+    to: DataType,
+    naive: NaiveTimestamps,
+    options: CastOptions<'static>,
+) -> datafusion::common::Result<ArrayRef> {
+    let batch = RecordBatch::try_from_iter(vec![("t", array)]).unwrap();
+    let expr = TemporalCastExpr::new(
+        col("t", &batch.schema()).unwrap(),
+        Arc::new(Field::new("t", to, true)),
+        naive,
+        options,
+    );
+    expr.evaluate(&batch)?.into_array(batch.num_rows())
+}
+
+fn seconds(tz: Option<&str>) -> DataType {
+    DataType::Timestamp(TimeUnit::Second, tz.map(Into::into))
+}
+
+#[test]
+fn coarsening_rounds_towards_the_past() {
+    let millis: ArrayRef = Arc::new(TimestampMillisecondArray::from(vec![
+        -1, -999, -1000, -1001, 0, 999, 1999,
+    ]));
+    let cast = cast(
+        millis,
+        seconds(None),
+        NaiveTimestamps::Utc,
+        DEFAULT_CAST_OPTIONS,
+    )
+    .unwrap();
+    assert_eq!(
+        cast.as_primitive::<TimestampSecondType>().values(),
+        &[-1, -1, -1, -2, 0, 0, 1]
+    );
+}
+
+#[test]
+fn timezones_keep_or_convert_instants() {
+    let naive: ArrayRef = Arc::new(TimestampSecondArray::from(vec![0, 3600]));
+    let values = |array: ArrayRef| {
+        array
+            .as_primitive::<TimestampSecondType>()
+            .values()
+            .to_vec()
+    };
+
+    // Naive values read as UTC instants only gain the timezone
+    let utc = cast(
+        Arc::clone(&naive),
+        seconds(Some("+02:00")),
+        NaiveTimestamps::Utc,
+        DEFAULT_CAST_OPTIONS,
+    )
+    .unwrap();
+    assert_eq!(utc.data_type(), &seconds(Some("+02:00")));
+    assert_eq!(values(utc), [0, 3600]);
+
+    // Read as wall-clock times in the column's timezone, they are two hours
+    // earlier in UTC
+    let local = cast(
+        Arc::clone(&naive),
+        seconds(Some("+02:00")),
+        NaiveTimestamps::Local,
+        DEFAULT_CAST_OPTIONS,
+    )
+    .unwrap();
+    assert_eq!(values(local), [-7200, -3600]);
+
+    // Values with an offset are instants already, whatever `naive` says
+    let offset: ArrayRef =
+        Arc::new(TimestampSecondArray::from(vec![0, 3600]).with_timezone("+02:00"));
+    for naive in [NaiveTimestamps::Utc, NaiveTimestamps::Local] {
+        let utc = cast(
+            Arc::clone(&offset),
+            seconds(Some("UTC")),
+            naive,
+            DEFAULT_CAST_OPTIONS,
+        )
+        .unwrap();
+        assert_eq!(utc.data_type(), &seconds(Some("UTC")));
+        assert_eq!(values(utc), [0, 3600]);
+    }
+}
+
+#[test]
+fn supertype_takes_the_finer_unit_and_a_timezone() {
+    use TimeUnit::*;
+
+    let ts = |unit, tz: Option<&str>| DataType::Timestamp(unit, tz.map(Into::into));
+    let cases = [
+        (
+            ts(Millisecond, None),
+            ts(Microsecond, Some("UTC")),
+            Some(ts(Microsecond, Some("UTC"))),
+        ),
+        (
+            ts(Second, Some("+02:00")),
+            ts(Millisecond, Some("UTC")),
+            Some(ts(Millisecond, Some("UTC"))),
+        ),
+        (
+            ts(Second, None),
+            ts(Millisecond, None),
+            Some(ts(Millisecond, None)),
+        ),
+        (
+            ts(Millisecond, Some("UTC")),
+            ts(Millisecond, None),
+            Some(ts(Millisecond, Some("UTC"))),
+        ),
+        (
+            DataType::Date32,
+            ts(Millisecond, None),
+            Some(ts(Millisecond, None)),
+        ),
+        // Nanoseconds only reach the years 1677 to 2262
+        (ts(Second, None), ts(Nanosecond, None), None),
+        (DataType::Date64, ts(Nanosecond, None), None),
+    ];
+    for (left, right, expected) in cases {
+        assert_eq!(
+            common_type(&left, &right, CoercionMode::Widening),
+            expected,
+            "{left} and {right}"
+        );
+        assert_eq!(
+            common_type(&right, &left, CoercionMode::Widening),
+            expected,
+            "{right} and {left}"
+        );
+    }
+}
+
+#[test]
+fn finer_units_out_of_range_do_not_wrap() {
+    // Year 2286 and year 1653, both outside the range of nanoseconds
+    let extreme: ArrayRef = Arc::new(TimestampSecondArray::from(vec![
+        1,
+        10_000_000_000,
+        -10_000_000_000,
+    ]));
+    let nanos = DataType::Timestamp(TimeUnit::Nanosecond, None);
+
+    let lenient = cast(
+        Arc::clone(&extreme),
+        nanos.clone(),
+        NaiveTimestamps::Utc,
+        CastOptions {
+            safe: true,
+            ..DEFAULT_CAST_OPTIONS
+        },
+    )
+    .unwrap();
+    let lenient = lenient.as_primitive::<TimestampNanosecondType>();
+    assert_eq!(lenient.value(0), 1_000_000_000);
+    assert!(lenient.is_null(1) && lenient.is_null(2));
+
+    let err = cast(extreme, nanos, NaiveTimestamps::Utc, DEFAULT_CAST_OPTIONS).unwrap_err();
+    assert!(err.to_string().contains("Overflow"), "{err}");
+}
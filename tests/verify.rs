@@ -0,0 +1,93 @@
+//! Verifying a pinned schema must check only the files the discovery selects,
+//! and name the file of each conflict.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
+use datafusion::prelude::SessionContext;
+use parquet::arrow::ArrowWriter;
+use schema_evolution::adapter::SchemaEvolutionAdapterFactory;
+use schema_evolution::discovery::SchemaDiscovery;
+use schema_evolution::policy::CoercionPolicy;
+use schema_evolution::verify::{SchemaVerifier, Verification};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, column: ArrayRef) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let batch = RecordBatch::try_from_iter(vec![("id", column)]).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// Daily partitions; the May one stores `id` as strings.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("dt=2024-05-31/part-0.parquet"),
+        Arc::new(StringArray::from(vec!["1"])),
+    );
+    for partition in ["dt=2024-06-01", "dt=2024-06-02"] {
+        write_parquet(
+            &dir.path().join(partition).join("part-0.parquet"),
+            Arc::new(Int64Array::from(vec![2])),
+        );
+    }
+    dir
+}
+
+fn pinned() -> SchemaRef {
+    Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]))
+}
+
+async fn verify(dir: &TempDir, discovery: SchemaDiscovery) -> Verification {
+    SchemaVerifier::new()
+        .with_discovery(discovery)
+        .with_adapter_factory(
+            SchemaEvolutionAdapterFactory::new().with_policy(CoercionPolicy::strict()),
+        )
+        .verify(
+            &SessionContext::new().state(),
+            &ListingTableUrl::parse(format!("{}/", dir.path().to_str().unwrap())).unwrap(),
+            &ListingOptions::new(Arc::new(ParquetFormat::default())),
+            &pinned(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn checks_only_the_selected_partitions() {
+    let dir = dataset();
+    let june = SchemaDiscovery::new().with_paths(glob::Pattern::new("dt=2024-06-*").unwrap());
+    let verification = verify(&dir, june).await;
+    assert!(verification.is_compatible(), "{verification}");
+    assert_eq!(verification.files, 2);
+    assert_eq!(verification.to_string(), "2 files checked, 0 conflicts\n");
+}
+
+#[tokio::test]
+async fn names_the_file_of_each_conflict() {
+    let dir = dataset();
+    let verification = verify(&dir, SchemaDiscovery::new()).await;
+    assert!(!verification.is_compatible());
+    assert_eq!(verification.files, 3);
+    let [conflict] = verification.conflicts.as_slice() else {
+        panic!("{verification}");
+    };
+    assert_eq!(conflict.column, "id");
+    assert_eq!(conflict.file_type, DataType::Utf8);
+    let file = conflict.file.as_deref().unwrap();
+    assert!(file.ends_with("dt=2024-05-31/part-0.parquet"), "{file}");
+    assert!(
+        verification
+            .to_string()
+            .starts_with("3 files checked, 1 conflicts\n"),
+        "{verification}"
+    );
+}
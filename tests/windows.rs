@@ -0,0 +1,170 @@
+//! Window functions partitioned and ordered by adapted columns must see the
+//! same values as over the table's rows read up front.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, Int32Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+    TimestampMillisecondArray,
+};
+use arrow::util::display::array_value_to_string;
+use arrow::util::pretty::pretty_format_batches;
+use datafusion::datasource::MemTable;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use parquet::arrow::ArrowWriter;
+use schema_evolution::{EvolutionOptions, FieldMapping, SchemaEvolutionTableProvider};
+use tempfile::TempDir;
+
+fn write_parquet(path: &Path, columns: Vec<(&str, ArrayRef)>) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let mut writer =
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// `account` was called `user`; `ts` is stored in milliseconds in the old file
+/// and microseconds in the new one; `amount` widens from Int32. Rows of both
+/// files interleave in time within each account.
+fn dataset() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_parquet(
+        &dir.path().join("a_old.parquet"),
+        vec![
+            (
+                "user",
+                Arc::new(StringArray::from(vec!["a", "b", "a"])) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(TimestampMillisecondArray::from(vec![1_000, 2_000, 3_000])),
+            ),
+            ("amount", Arc::new(Int32Array::from(vec![10, 20, 30]))),
+        ],
+    );
+    write_parquet(
+        &dir.path().join("b_new.parquet"),
+        vec![
+            (
+                "account",
+                Arc::new(StringArray::from(vec!["a", "b", "b"])) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(TimestampMicrosecondArray::from(vec![
+                    4_000_000, 5_000_000, 1_500_000,
+                ])),
+            ),
+            ("amount", Arc::new(Int64Array::from(vec![40, 50, 15]))),
+        ],
+    );
+    dir
+}
+
+async fn context(dir: &TempDir) -> SessionContext {
+    let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(4));
+    let provider = SchemaEvolutionTableProvider::try_new(
+        &ctx.state(),
+        dir.path().to_str().unwrap(),
+        Arc::new(ParquetFormat::default()),
+        EvolutionOptions::new()
+            .with_field_mapping(FieldMapping::new().with_rename("user", "account")),
+    )
+    .await
+    .unwrap();
+    ctx.register_table("t", Arc::new(provider)).unwrap();
+    ctx
+}
+
+/// The rows of `t`, as an in-memory table to evaluate the expected results on.
+async fn reference(ctx: &SessionContext) -> SessionContext {
+    let df = ctx.sql("SELECT * FROM t").await.unwrap();
+    let schema = Arc::new(df.schema().as_arrow().clone());
+    let batches = df.collect().await.unwrap();
+    let reference = SessionContext::new();
+    let table = MemTable::try_new(schema, vec![batches]).unwrap();
+    reference.register_table("t", Arc::new(table)).unwrap();
+    reference
+}
+
+async fn query(ctx: &SessionContext, sql: &str) -> String {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    pretty_format_batches(&batches).unwrap().to_string()
+}
+
+const QUERIES: &[&str] = &[
+    "SELECT account, amount, ROW_NUMBER() OVER (PARTITION BY account ORDER BY ts) AS n \
+     FROM t ORDER BY account, n",
+    "SELECT account, ts, LAG(ts) OVER (PARTITION BY account ORDER BY ts) AS previous, \
+     ts - LAG(ts) OVER (PARTITION BY account ORDER BY ts) AS gap \
+     FROM t ORDER BY account, ts",
+    "SELECT account, ts, SUM(amount) OVER (PARTITION BY account ORDER BY ts) AS running \
+     FROM t ORDER BY account, ts",
+    "SELECT ts, RANK() OVER (ORDER BY amount DESC) AS rank, \
+     FIRST_VALUE(account) OVER (ORDER BY ts) AS first \
+     FROM t ORDER BY ts",
+    "SELECT account, ts, \
+     AVG(amount) OVER (PARTITION BY account ORDER BY ts ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) AS avg \
+     FROM t WHERE amount > 10 ORDER BY account, ts",
+    "SELECT account, COUNT(*) OVER (PARTITION BY account ORDER BY ts \
+     RANGE BETWEEN INTERVAL '1.5 seconds' PRECEDING AND CURRENT ROW) AS recent \
+     FROM t ORDER BY account, ts",
+];
+
+#[tokio::test]
+async fn windows_match_materialized_rows() {
+    let dir = dataset();
+    let ctx = context(&dir).await;
+    let reference = reference(&ctx).await;
+    for sql in QUERIES {
+        assert_eq!(
+            query(&ctx, sql).await,
+            query(&reference, sql).await,
+            "{sql}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn rows_of_both_files_share_partitions() {
+    let dir = dataset();
+    let ctx = context(&dir).await;
+    let batches = ctx
+        .sql(
+            "SELECT account, amount, \
+             ROW_NUMBER() OVER (PARTITION BY account ORDER BY ts) AS n, \
+             LAG(amount) OVER (PARTITION BY account ORDER BY ts) AS previous \
+             FROM t ORDER BY account, n",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let rows: Vec<String> = batches
+        .iter()
+        .flat_map(|batch| {
+            (0..batch.num_rows()).map(|row| {
+                (0..batch.num_columns())
+                    .map(|column| array_value_to_string(batch.column(column), row).unwrap())
+                    .collect::<Vec<_>>()
+                    .join(":")
+            })
+        })
+        .collect();
+    // The old file's `user` partitions with the new file's `account`, and the
+    // millisecond and microsecond timestamps order together
+    assert_eq!(
+        rows,
+        [
+            "a:10:1:",
+            "a:30:2:10",
+            "a:40:3:30",
+            "b:15:1:",
+            "b:20:2:15",
+            "b:50:3:20",
+        ]
+    );
+}